@@ -15,7 +15,8 @@ use malachitebft_app_channel::app::types::{LocallyProposedValue, ProposedValue};
 use malachitebft_app_channel::{AppMsg, Channels, NetworkMsg};
 use malachitebft_eth_engine::engine::Engine;
 use malachitebft_eth_engine::json_structures::ExecutionBlock;
-use malachitebft_eth_engine::validator_executor::ValidatorExecutor;
+use malachitebft_eth_engine::network::Network;
+use malachitebft_eth_engine::validator_executor::{ChainHead, ValidatorExecutor};
 use malachitebft_eth_types::codec::proto::ProtobufCodec;
 use malachitebft_eth_types::{Block, BlockHash, Height, TestContext};
 use tokio::sync::mpsc::Receiver;
@@ -29,16 +30,20 @@ pub async fn run(
     block_interval: Duration,
     mut shutdown_rx: Receiver<()>,
 ) -> eyre::Result<()> {
-    // Initialize ValidatorExecutor for on-chain validator management
-    let validator_executor = Arc::new(ValidatorExecutor::new(Arc::new(engine.eth.clone()))?);
-
     // Get chain ID
     let chain_id_hex = engine.eth.get_chain_id().await?;
     let chain_id = u64::from_str_radix(chain_id_hex.trim_start_matches("0x"), 16)?;
+    let network = Network::from_chain_id(chain_id);
+
+    // Initialize ValidatorExecutor for on-chain validator management
+    let validator_executor = Arc::new(ValidatorExecutor::for_network(
+        Arc::new(engine.eth.clone()),
+        network,
+    )?);
 
     info!(
-        "Validator executor initialized with epoch_length=100, max_validators=21, chain_id={}",
-        chain_id
+        "Validator executor initialized with epoch_length=100, max_validators=21, chain_id={}, network={}",
+        chain_id, network
     );
 
     let mut shutdown_flag = false;
@@ -445,10 +450,15 @@ pub async fn run(
                             prev_randao: new_block_prev_randao,
                             extra_data: Default::default(),
                         });
+                        validator_executor.observe_head(ChainHead {
+                            number: new_block_number,
+                            hash: new_block_hash,
+                            parent_hash: parent_block_hash,
+                        });
 
-                        // Check if we're at an epoch boundary and update cached validator set
-                        if validator_executor.is_epoch_boundary(new_block_number + 1, state.epoch_length).await {
-                            info!("🔄 Epoch boundary detected at block {}, checking for validator set update", new_block_number + 1);
+                        // Check if the next epoch's validator set is due and update cached validator set
+                        if let Some(epoch) = validator_executor.transition_epoch_at(new_block_number, state.epoch_length) {
+                            info!("🔄 Epoch {} transition at block {}, checking for validator set update", epoch, new_block_number + 1);
 
                             info!("📊 Current validator set BEFORE StakeHub update:");
                             let current_validator_set = state.get_current_validator_set();
@@ -458,7 +468,7 @@ pub async fn run(
                                       i + 1, validator.consensus_address, validator.operator_address, validator.voting_power, validator.public_key);
                             }
 
-                            match validator_executor.get_validator_set_from_stake_hub().await {
+                            match validator_executor.refresh_validator_set(epoch).await {
                                 Ok(Some(validator_set)) => {
                                     // Update the cached validator set
                                     state.update_validator_set(validator_set);
@@ -647,6 +657,7 @@ pub async fn run(
             }
             _ = shutdown_rx.recv() => {
                 info!("Received shutdown signal, exiting run loop");
+                validator_executor.shutdown().await;
                 shutdown_flag = true;
             }
         }
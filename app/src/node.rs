@@ -240,7 +240,10 @@ impl App {
         config: &Config,
     ) -> eyre::Result<(ValidatorSet, u64)> {
         use malachitebft_eth_engine::genesis::parse_validators_from_extra_data;
-        use malachitebft_eth_types::{Address, PublicKey, Validator};
+        use malachitebft_eth_engine::network::Network;
+        use malachitebft_eth_engine::validator_executor::{ValidatorExecutor, ValidatorSetFetch};
+        use malachitebft_eth_types::{Address, Validator};
+        use std::sync::Arc;
         use url::Url;
 
         // Step 1: Get Reth RPC URL from config
@@ -283,20 +286,32 @@ impl App {
             epoch_length
         );
 
+        // A network whose validators all register through StakeHub starts with none in genesis
+        if validator_infos.is_empty() {
+            info!("📖 No validators in genesis extraData, loading the initial set from StakeHub");
+            let chain_id_hex = eth_rpc.get_chain_id().await?;
+            let chain_id = u64::from_str_radix(chain_id_hex.trim_start_matches("0x"), 16)?;
+            let executor = ValidatorExecutor::for_network(
+                Arc::new(eth_rpc),
+                Network::from_chain_id(chain_id),
+            )?;
+            return match executor.get_validator_set_from_stake_hub().await {
+                ValidatorSetFetch::Available(validator_set) => Ok((validator_set, epoch_length)),
+                ValidatorSetFetch::Empty => Err(eyre::eyre!(
+                    "No validators found in genesis extraData nor elected by StakeHub"
+                )),
+                ValidatorSetFetch::Unavailable(reason) => Err(eyre::eyre!(
+                    "No validators found in genesis extraData and StakeHub is unavailable: {}",
+                    reason
+                )),
+            };
+        }
+
         // Step 4: Convert to Malachite ValidatorSet (preserve operator_address from genesis)
         let validators: Vec<Validator> = validator_infos
             .into_iter()
             .enumerate()
             .map(|(_i, info)| {
-                // Validate Tendermint public key length
-                if info.tendermint_pubkey.len() != 32 {
-                    return Err(eyre::eyre!(
-                        "Invalid Tendermint public key length for validator {}: {} bytes",
-                        info.consensus_address,
-                        info.tendermint_pubkey.len()
-                    ));
-                }
-
                 // Check if it's a placeholder (all zeros) - reject it
                 let is_placeholder = info.tendermint_pubkey.iter().all(|&b| b == 0);
                 if is_placeholder {
@@ -308,14 +323,14 @@ impl App {
                     ));
                 }
 
-                // Convert Tendermint public key to array
-                let pubkey_array: [u8; 32] = info
-                    .tendermint_pubkey
-                    .try_into()
-                    .map_err(|_| eyre::eyre!("Invalid public key length"))?;
-
                 // Convert to Malachite PublicKey
-                let public_key = PublicKey::from_bytes(pubkey_array);
+                let public_key = info.public_key().map_err(|e| {
+                    eyre::eyre!(
+                        "Invalid Tendermint public key for validator {}: {}",
+                        info.consensus_address,
+                        e
+                    )
+                })?;
 
                 // Build operator Address from alloy address bytes
                 let operator_bytes: [u8; 20] = info.operator_address.into();
@@ -0,0 +1,125 @@
+//! Benchmarks for the validator election selection.
+//!
+//! Run with `cargo bench -p malachitebft-eth-engine --bench election`.
+//! Criterion reports time and throughput (candidates per second); the peak heap usage of a single
+//! selection is printed once per configuration.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use alloy_primitives::{Address, U256};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use malachitebft_eth_engine::stake_hub_client::{
+    get_top_validators_by_voting_power, ElectionConfig, ValidatorElectionInfo,
+};
+
+const CANDIDATE_COUNTS: &[usize] = &[100, 1_000, 10_000, 100_000];
+const MAX_ELECTED: &[u64] = &[21, 100, 1_000];
+
+/// Allocator tracking the current and peak number of live heap bytes
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Deterministic candidates with pseudo-random voting powers, kept distinct by folding in the
+/// index so the tie-break isn't measured
+fn candidates(count: usize) -> Vec<ValidatorElectionInfo> {
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    (0..count)
+        .map(|i| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            let mut address = [0u8; 20];
+            address[12..].copy_from_slice(&(i as u64).to_be_bytes());
+            ValidatorElectionInfo {
+                consensus_address: Address::from(address),
+                voting_power: U256::from((seed % 1_000_000) * 1_000_000 + i as u64 + 1)
+                    * U256::from(10u64.pow(10)),
+                operator_address: Address::from(address),
+                tendermint_pub_key: vec![0u8; 32],
+                status: None,
+                active: None,
+                unbonding: None,
+                self_stake: None,
+            }
+        })
+        .collect()
+}
+
+/// Peak heap bytes allocated by one selection, on top of what was live before it
+fn peak_selection_bytes(input: &[ValidatorElectionInfo], max_elected: u64) -> usize {
+    let input = input.to_vec();
+    let baseline = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let elected = get_top_validators_by_voting_power(
+        input,
+        U256::from(max_elected),
+        &ElectionConfig::default(),
+    );
+    let peak = PEAK.load(Ordering::Relaxed);
+    drop(elected);
+    peak.saturating_sub(baseline)
+}
+
+fn bench_election(c: &mut Criterion) {
+    let config = ElectionConfig::default();
+    let mut group = c.benchmark_group("get_top_validators_by_voting_power");
+
+    for &count in CANDIDATE_COUNTS {
+        let input = candidates(count);
+        group.throughput(Throughput::Elements(count as u64));
+
+        for &max_elected in MAX_ELECTED {
+            println!(
+                "candidates={} max_elected={}: peak heap {} bytes",
+                count,
+                max_elected,
+                peak_selection_bytes(&input, max_elected)
+            );
+
+            group.bench_with_input(
+                BenchmarkId::new(format!("max_elected={}", max_elected), count),
+                &input,
+                |b, input| {
+                    b.iter_batched(
+                        || input.clone(),
+                        |input| {
+                            get_top_validators_by_voting_power(
+                                input,
+                                U256::from(max_elected),
+                                &config,
+                            )
+                        },
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_election);
+criterion_main!(benches);
@@ -0,0 +1,48 @@
+//! Block height sources
+//! Where the executor learns the current height: the execution client or local consensus state
+
+use crate::ethereum_rpc::EthereumRPC;
+use async_trait::async_trait;
+use color_eyre::eyre::Result;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Provides the height of the latest block
+#[async_trait]
+pub trait BlockHeightSource: Send + Sync {
+    async fn block_height(&self) -> Result<u64>;
+}
+
+/// Asks the execution client with `eth_blockNumber`
+#[async_trait]
+impl BlockHeightSource for EthereumRPC {
+    async fn block_height(&self) -> Result<u64> {
+        let block_number = self.get_block_number().await?;
+        Ok(u64::from_str_radix(
+            block_number.trim_start_matches("0x"),
+            16,
+        )?)
+    }
+}
+
+/// Height kept up to date by the caller, e.g. from the blocks the node commits, so reading it
+/// costs no RPC round trip
+#[derive(Debug, Default)]
+pub struct LocalBlockHeight(AtomicU64);
+
+impl LocalBlockHeight {
+    pub fn new(height: u64) -> Self {
+        Self(AtomicU64::new(height))
+    }
+
+    /// Record `height` as the latest block
+    pub fn set(&self, height: u64) {
+        self.0.store(height, Ordering::Release);
+    }
+}
+
+#[async_trait]
+impl BlockHeightSource for LocalBlockHeight {
+    async fn block_height(&self) -> Result<u64> {
+        Ok(self.0.load(Ordering::Acquire))
+    }
+}
@@ -0,0 +1,190 @@
+//! Validator set diffs
+//! Compares two validator sets keyed by consensus address
+
+use crate::genesis::GenesisValidatorInfo;
+use crate::interop::abci::ValidatorUpdate;
+use crate::stake_hub_client::ElectedValidator;
+use alloy_primitives::Address;
+use malachitebft_eth_types::Validator;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A validator present in both sets with different details
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorChange {
+    pub before: ElectedValidator,
+    pub after: ElectedValidator,
+}
+
+/// Difference between two validator sets, each list sorted by consensus address
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorSetDiff {
+    /// Only in the later set
+    pub added: Vec<ElectedValidator>,
+    /// Only in the earlier set
+    pub removed: Vec<ElectedValidator>,
+    /// In both sets, with a different operator, voting power or pubkey
+    pub changed: Vec<ValidatorChange>,
+}
+
+impl ValidatorSetDiff {
+    /// Diff `before` against `after`, matching validators by consensus address
+    pub fn between(
+        before: impl IntoIterator<Item = ElectedValidator>,
+        after: impl IntoIterator<Item = ElectedValidator>,
+    ) -> Self {
+        let mut before: BTreeMap<Address, ElectedValidator> = before
+            .into_iter()
+            .map(|v| (v.consensus_address, v))
+            .collect();
+        let mut diff = Self::default();
+
+        let after: BTreeMap<Address, ElectedValidator> = after
+            .into_iter()
+            .map(|v| (v.consensus_address, v))
+            .collect();
+        for (address, after) in after {
+            match before.remove(&address) {
+                None => diff.added.push(after),
+                Some(before) if before != after => {
+                    diff.changed.push(ValidatorChange { before, after })
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed = before.into_values().collect();
+        diff
+    }
+
+    /// Whether both sets are identical
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// The diff as CometBFT `abci.ValidatorUpdate`s for `EndBlock`: removals, then changes,
+    /// then additions. A changed pubkey removes the old key and adds the new one, since
+    /// CometBFT identifies validators by pubkey.
+    pub fn to_abci_updates(&self) -> Vec<ValidatorUpdate> {
+        let mut updates = Vec::new();
+        for validator in &self.removed {
+            updates.push(ValidatorUpdate::removal(&validator.tendermint_pub_key));
+        }
+        for ValidatorChange { before, after } in &self.changed {
+            if before.tendermint_pub_key != after.tendermint_pub_key {
+                updates.push(ValidatorUpdate::removal(&before.tendermint_pub_key));
+            }
+            updates.push(ValidatorUpdate::new(
+                &after.tendermint_pub_key,
+                after.voting_power,
+            ));
+        }
+        for validator in &self.added {
+            updates.push(ValidatorUpdate::new(
+                &validator.tendermint_pub_key,
+                validator.voting_power,
+            ));
+        }
+        updates
+    }
+
+    /// [`Self::to_abci_updates`], protobuf-encoded
+    pub fn to_abci_update_bytes(&self) -> Vec<Vec<u8>> {
+        self.to_abci_updates()
+            .iter()
+            .map(ValidatorUpdate::to_bytes)
+            .collect()
+    }
+}
+
+impl From<&GenesisValidatorInfo> for ElectedValidator {
+    fn from(validator: &GenesisValidatorInfo) -> Self {
+        Self {
+            consensus_address: validator.consensus_address,
+            operator_address: validator.operator_address,
+            voting_power: validator.voting_power,
+            tendermint_pub_key: validator.tendermint_pubkey.clone(),
+        }
+    }
+}
+
+impl From<&Validator> for ElectedValidator {
+    fn from(validator: &Validator) -> Self {
+        Self {
+            consensus_address: validator.consensus_address.to_alloy_address(),
+            operator_address: validator.operator_address.to_alloy_address(),
+            voting_power: validator.voting_power,
+            tendermint_pub_key: validator.public_key.as_bytes().to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    fn validator(byte: u8, voting_power: u64) -> ElectedValidator {
+        ElectedValidator {
+            consensus_address: Address::repeat_byte(byte),
+            operator_address: Address::repeat_byte(byte.wrapping_add(0x80)),
+            voting_power,
+            tendermint_pub_key: vec![byte; 32],
+        }
+    }
+
+    #[test]
+    fn test_diff_of_identical_sets_is_empty() {
+        let set = vec![validator(1, 10), validator(2, 20)];
+        let reordered = vec![validator(2, 20), validator(1, 10)];
+
+        assert!(ValidatorSetDiff::between(set, reordered).is_empty());
+    }
+
+    #[test]
+    fn test_abci_updates_decode_to_pubkey_and_power() {
+        let mut rotated = validator(3, 30);
+        rotated.tendermint_pub_key = vec![0x33; 32];
+        let before = vec![validator(1, 10), validator(2, 20), validator(3, 30)];
+        let after = vec![validator(2, 25), rotated, validator(4, 40)];
+
+        let bytes = ValidatorSetDiff::between(before, after).to_abci_update_bytes();
+        // pub_key is field 1 (length-delimited), nesting ed25519 as its own field 1
+        assert!(bytes.iter().all(|b| b[..4] == [0x0a, 34, 0x0a, 32]));
+
+        let updates: Vec<(Vec<u8>, i64)> = bytes
+            .iter()
+            .map(|b| {
+                let update = ValidatorUpdate::decode(b.as_slice()).unwrap();
+                (update.pub_key.unwrap().ed25519, update.power)
+            })
+            .collect();
+        assert_eq!(
+            updates,
+            vec![
+                (vec![1; 32], 0),
+                (vec![2; 32], 25),
+                (vec![3; 32], 0),
+                (vec![0x33; 32], 30),
+                (vec![4; 32], 40),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_reports_added_removed_and_changed() {
+        let before = vec![validator(3, 30), validator(1, 10), validator(2, 20)];
+        let after = vec![validator(4, 40), validator(2, 25), validator(3, 30)];
+
+        let diff = ValidatorSetDiff::between(before, after);
+
+        assert_eq!(diff.added, vec![validator(4, 40)]);
+        assert_eq!(diff.removed, vec![validator(1, 10)]);
+        assert_eq!(
+            diff.changed,
+            vec![ValidatorChange {
+                before: validator(2, 20),
+                after: validator(2, 25),
+            }]
+        );
+    }
+}
@@ -0,0 +1,352 @@
+//! Epoch arithmetic
+//! Epoch `n` covers blocks `n * epoch_length..(n + 1) * epoch_length`. Block 0 is the genesis and
+//! never counts as an epoch boundary; every later multiple of the epoch length does.
+
+use thiserror::Error;
+
+/// Errors of the epoch helpers
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Error)]
+pub enum EpochError {
+    /// The epoch length is zero, so no block maps to an epoch
+    #[error("Epoch length must be greater than zero")]
+    ZeroEpochLength,
+
+    /// The first block of the epoch doesn't fit in a `u64`
+    #[error("Epoch {epoch} with length {epoch_length} starts beyond the last representable block")]
+    Overflow { epoch: u64, epoch_length: u64 },
+
+    /// The block deciding the epoch's validator set is beyond the chain head
+    #[error("Epoch {epoch} is decided at block {decided_at}, beyond the chain head {head}")]
+    FutureEpoch {
+        epoch: u64,
+        decided_at: u64,
+        head: u64,
+    },
+
+    /// An [`EpochSchedule`] needs at least one epoch length
+    #[error("Epoch length history is empty")]
+    EmptyHistory,
+}
+
+/// Whether `block_number` starts a new epoch. Always `false` for block 0 and for a zero epoch
+/// length.
+pub fn is_epoch_boundary(block_number: u64, epoch_length: u64) -> bool {
+    block_number > 0 && block_number.checked_rem(epoch_length) == Some(0)
+}
+
+/// Epoch containing `block_number`
+pub fn epoch_of(block_number: u64, epoch_length: u64) -> Result<u64, EpochError> {
+    block_number
+        .checked_div(epoch_length)
+        .ok_or(EpochError::ZeroEpochLength)
+}
+
+/// First block of `epoch`
+pub fn epoch_start_block(epoch: u64, epoch_length: u64) -> Result<u64, EpochError> {
+    if epoch_length == 0 {
+        return Err(EpochError::ZeroEpochLength);
+    }
+    epoch.checked_mul(epoch_length).ok_or(EpochError::Overflow {
+        epoch,
+        epoch_length,
+    })
+}
+
+/// Number of blocks from `block_number` to the next epoch boundary strictly after it.
+/// On a boundary (and at block 0) this is a full epoch length.
+pub fn blocks_until_next_epoch(block_number: u64, epoch_length: u64) -> Result<u64, EpochError> {
+    let offset = block_number
+        .checked_rem(epoch_length)
+        .ok_or(EpochError::ZeroEpochLength)?;
+    Ok(epoch_length - offset)
+}
+
+/// Block whose state decides the validator set of `epoch`: the last block before the epoch
+/// starts. Epoch 0 has no such block and is decided by the genesis state at block 0.
+///
+/// Fails with [`EpochError::FutureEpoch`] if that block is beyond `head`.
+pub fn epoch_decision_block(epoch: u64, epoch_length: u64, head: u64) -> Result<u64, EpochError> {
+    let decided_at = epoch_start_block(epoch, epoch_length)?.saturating_sub(1);
+    if decided_at > head {
+        return Err(EpochError::FutureEpoch {
+            epoch,
+            decided_at,
+            head,
+        });
+    }
+    Ok(decided_at)
+}
+
+/// Epochs of a chain whose epoch length changed over time.
+///
+/// An epoch length first read at block `b` takes effect at the first epoch boundary at or after
+/// `b`, so the epoch running at `b` keeps its length. Epoch indices keep counting across changes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EpochSchedule {
+    /// Runs of epochs sharing a length, by ascending start block. The first starts at block 0.
+    segments: Vec<EpochSegment>,
+    /// Block of the latest epoch length recorded
+    observed_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct EpochSegment {
+    start_block: u64,
+    first_epoch: u64,
+    epoch_length: u64,
+}
+
+impl EpochSchedule {
+    /// Schedule of a chain whose epoch length never changed
+    pub fn new(epoch_length: u64) -> Result<Self, EpochError> {
+        Self::from_history(&[(0, epoch_length)])
+    }
+
+    /// Schedule from an epoch length history as `(block, epoch_length)` pairs by ascending
+    /// block, such as reported by
+    /// [`StakeHubClient::epoch_length_history`](crate::stake_hub_client::StakeHubClient::epoch_length_history).
+    /// The first length applies from genesis.
+    pub fn from_history(history: &[(u64, u64)]) -> Result<Self, EpochError> {
+        let (&(_, first_length), changes) =
+            history.split_first().ok_or(EpochError::EmptyHistory)?;
+        if first_length == 0 {
+            return Err(EpochError::ZeroEpochLength);
+        }
+        let mut schedule = Self {
+            segments: vec![EpochSegment {
+                start_block: 0,
+                first_epoch: 0,
+                epoch_length: first_length,
+            }],
+            observed_at: 0,
+        };
+        for &(block, epoch_length) in changes {
+            schedule.extend(block, epoch_length)?;
+        }
+        Ok(schedule)
+    }
+
+    /// Record that the epoch length read at `block` is `epoch_length`. A length differing from
+    /// the last one starts a new run of epochs at the next boundary. Readings older than the
+    /// latest one recorded are already covered and ignored.
+    pub fn extend(&mut self, block: u64, epoch_length: u64) -> Result<(), EpochError> {
+        if epoch_length == 0 {
+            return Err(EpochError::ZeroEpochLength);
+        }
+        if block < self.observed_at {
+            return Ok(());
+        }
+        self.observed_at = block;
+        let last = *self.segments.last().unwrap();
+        if epoch_length == last.epoch_length {
+            return Ok(());
+        }
+        // The change waits for the running epoch to end
+        let elapsed = block.saturating_sub(last.start_block);
+        let epochs = elapsed.div_ceil(last.epoch_length);
+        let overflow = EpochError::Overflow {
+            epoch: last.first_epoch.saturating_add(epochs),
+            epoch_length: last.epoch_length,
+        };
+        let start_block = epochs
+            .checked_mul(last.epoch_length)
+            .and_then(|offset| offset.checked_add(last.start_block))
+            .ok_or(overflow)?;
+        let segment = EpochSegment {
+            start_block,
+            first_epoch: last.first_epoch + epochs,
+            epoch_length,
+        };
+        // A later change within the same epoch supersedes the earlier one
+        if start_block == last.start_block {
+            *self.segments.last_mut().unwrap() = segment;
+        } else {
+            self.segments.push(segment);
+        }
+        Ok(())
+    }
+
+    /// Epoch length of the last run of epochs
+    pub fn latest_epoch_length(&self) -> u64 {
+        self.segments.last().unwrap().epoch_length
+    }
+
+    fn segment_at_block(&self, block_number: u64) -> &EpochSegment {
+        let index = self
+            .segments
+            .partition_point(|segment| segment.start_block <= block_number);
+        &self.segments[index - 1]
+    }
+
+    /// Epoch length in effect at `block_number`
+    pub fn epoch_length_at(&self, block_number: u64) -> u64 {
+        self.segment_at_block(block_number).epoch_length
+    }
+
+    /// Epoch containing `block_number`
+    pub fn epoch_of(&self, block_number: u64) -> u64 {
+        let segment = self.segment_at_block(block_number);
+        segment.first_epoch + (block_number - segment.start_block) / segment.epoch_length
+    }
+
+    /// First block of `epoch`
+    pub fn epoch_start_block(&self, epoch: u64) -> Result<u64, EpochError> {
+        let index = self
+            .segments
+            .partition_point(|segment| segment.first_epoch <= epoch);
+        let segment = &self.segments[index - 1];
+        let epoch_length = segment.epoch_length;
+        (epoch - segment.first_epoch)
+            .checked_mul(epoch_length)
+            .and_then(|offset| offset.checked_add(segment.start_block))
+            .ok_or(EpochError::Overflow {
+                epoch,
+                epoch_length,
+            })
+    }
+
+    /// Whether `block_number` starts a new epoch. Always `false` for block 0.
+    pub fn is_epoch_boundary(&self, block_number: u64) -> bool {
+        let segment = self.segment_at_block(block_number);
+        block_number > 0
+            && (block_number - segment.start_block).checked_rem(segment.epoch_length) == Some(0)
+    }
+
+    /// Block whose state decides the validator set of `epoch`, like [`epoch_decision_block`]
+    pub fn epoch_decision_block(&self, epoch: u64, head: u64) -> Result<u64, EpochError> {
+        let decided_at = self.epoch_start_block(epoch)?.saturating_sub(1);
+        if decided_at > head {
+            return Err(EpochError::FutureEpoch {
+                epoch,
+                decided_at,
+                head,
+            });
+        }
+        Ok(decided_at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_zero() {
+        assert!(!is_epoch_boundary(0, 100));
+        assert_eq!(epoch_of(0, 100), Ok(0));
+        assert_eq!(blocks_until_next_epoch(0, 100), Ok(100));
+        assert_eq!(blocks_until_next_epoch(99, 100), Ok(1));
+        assert_eq!(blocks_until_next_epoch(100, 100), Ok(100));
+    }
+
+    #[test]
+    fn test_epoch_zero() {
+        assert_eq!(epoch_start_block(0, 100), Ok(0));
+        assert_eq!(epoch_decision_block(0, 100, 0), Ok(0));
+        assert_eq!(epoch_decision_block(1, 100, 99), Ok(99));
+    }
+
+    #[test]
+    fn test_future_epoch_beyond_head() {
+        assert_eq!(
+            epoch_decision_block(3, 100, 250),
+            Err(EpochError::FutureEpoch {
+                epoch: 3,
+                decided_at: 299,
+                head: 250,
+            })
+        );
+        assert!(matches!(
+            epoch_start_block(u64::MAX, 100),
+            Err(EpochError::Overflow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_zero_epoch_length() {
+        assert!(!is_epoch_boundary(100, 0));
+        assert_eq!(epoch_of(100, 0), Err(EpochError::ZeroEpochLength));
+        assert_eq!(
+            blocks_until_next_epoch(5, 0),
+            Err(EpochError::ZeroEpochLength)
+        );
+        assert_eq!(epoch_start_block(1, 0), Err(EpochError::ZeroEpochLength));
+    }
+
+    #[test]
+    fn test_schedule_across_epoch_length_change() {
+        // Lengthened from 100 to 150 during epoch 2
+        let schedule = EpochSchedule::from_history(&[(0, 100), (250, 150)]).unwrap();
+
+        for (block, epoch) in [
+            (0, 0),
+            (99, 0),
+            (100, 1),
+            (299, 2),
+            (300, 3),
+            (449, 3),
+            (450, 4),
+        ] {
+            assert_eq!(schedule.epoch_of(block), epoch, "block {}", block);
+        }
+        assert_eq!(schedule.epoch_length_at(299), 100);
+        assert_eq!(schedule.epoch_length_at(300), 150);
+        assert_eq!(schedule.epoch_start_block(2), Ok(200));
+        assert_eq!(schedule.epoch_start_block(3), Ok(300));
+        assert_eq!(schedule.epoch_start_block(5), Ok(600));
+        assert!(schedule.is_epoch_boundary(300));
+        assert!(schedule.is_epoch_boundary(450));
+        // A boundary under the old length only
+        assert!(!schedule.is_epoch_boundary(400));
+        assert!(!schedule.is_epoch_boundary(0));
+
+        // Without changes, the schedule matches the plain arithmetic
+        let constant = EpochSchedule::new(100).unwrap();
+        assert_eq!(
+            EpochSchedule::from_history(&[(0, 100), (500, 100)])
+                .unwrap()
+                .segments,
+            constant.segments
+        );
+        assert_eq!(constant.epoch_of(450), epoch_of(450, 100).unwrap());
+        assert_eq!(constant.epoch_start_block(7), epoch_start_block(7, 100));
+
+        assert_eq!(
+            EpochSchedule::from_history(&[]),
+            Err(EpochError::EmptyHistory)
+        );
+        assert_eq!(
+            EpochSchedule::from_history(&[(0, 100), (250, 0)]),
+            Err(EpochError::ZeroEpochLength)
+        );
+    }
+
+    #[test]
+    fn test_extend_schedule_with_live_epoch_length() {
+        let mut schedule = EpochSchedule::from_history(&[(0, 100), (250, 150)]).unwrap();
+        assert_eq!(schedule.epoch_decision_block(4, 500), Ok(449));
+
+        // Read at 500, within epoch 4 (450..600): epoch 5 starts at 600 with the new length
+        schedule.extend(500, 50).unwrap();
+        assert_eq!(schedule.latest_epoch_length(), 50);
+        assert_eq!(schedule.epoch_start_block(5), Ok(600));
+        assert_eq!(schedule.epoch_start_block(6), Ok(650));
+        assert_eq!(schedule.epoch_of(649), 5);
+        assert!(schedule.is_epoch_boundary(700));
+        assert_eq!(
+            schedule.epoch_decision_block(6, 600),
+            Err(EpochError::FutureEpoch {
+                epoch: 6,
+                decided_at: 649,
+                head: 600,
+            })
+        );
+
+        // Older readings and unchanged lengths leave it as is
+        let extended = schedule.clone();
+        schedule.extend(300, 150).unwrap();
+        schedule.extend(700, 50).unwrap();
+        assert_eq!(schedule.segments, extended.segments);
+        assert_eq!(schedule.extend(800, 0), Err(EpochError::ZeroEpochLength));
+    }
+}
@@ -1,10 +1,14 @@
+use async_trait::async_trait;
 use color_eyre::eyre;
-use reqwest::{header::CONTENT_TYPE, Client, Url};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::{Client, Url};
 use serde::de::DeserializeOwned;
 use serde_json::json;
 use std::time::Duration;
-use tracing::debug;
+use thiserror::Error;
+use tracing::{debug, trace};
 
+use alloy_primitives::{B256, U256};
 use alloy_rpc_types_txpool::{TxpoolInspect, TxpoolStatus};
 
 use crate::json_structures::*;
@@ -29,6 +33,7 @@ pub struct TransactionReceipt {
 
 /// Log struct
 #[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Log {
     pub address: String,
     pub topics: Vec<String>,
@@ -60,11 +65,112 @@ pub struct Transaction {
     pub s: String,
 }
 
+/// Block whose state an `eth_call` is executed against
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BlockTag {
+    #[default]
+    Latest,
+    /// Latest block the node considers finalized
+    Finalized,
+    Number(u64),
+    /// State with this root, sent as an EIP-1898 style `{"stateRoot": ...}` object.
+    /// Not part of the standard API: only archive nodes or proxies that resolve state roots
+    /// accept it, others reject the call.
+    StateRoot(B256),
+}
+
+impl BlockTag {
+    /// The block parameter as sent over JSON-RPC
+    pub fn to_param(&self) -> serde_json::Value {
+        match self {
+            BlockTag::Latest => json!("latest"),
+            BlockTag::Finalized => json!("finalized"),
+            BlockTag::Number(number) => json!(format!("0x{:x}", number)),
+            BlockTag::StateRoot(root) => json!({ "stateRoot": root.to_string() }),
+        }
+    }
+}
+
+/// An `eth_call` result without the `0x` prefix nodes put on hex. Some proxies return a single
+/// uint result as a decimal string, which only a caller knowing the output type can tell apart
+/// from unprefixed hex, see [`decode_unprefixed_call_result`].
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+#[error("eth_call result without 0x prefix: {0}")]
+pub struct UnprefixedCallResult(pub String);
+
+/// JSON-RPC error object returned by the node
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+#[error("Server Message: code: {code}, message: {message}")]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    /// Whether the call was executed and reverted: code 3 when the revert carries data,
+    /// `-32000` with an `execution reverted` message when it doesn't
+    pub fn is_revert(&self) -> bool {
+        self.code == 3 || (self.code == -32000 && self.message.starts_with("execution reverted"))
+    }
+}
+
+/// Decode the result of an `eth_call` into ABI-encoded bytes. Fails with
+/// [`UnprefixedCallResult`] unless it is `0x`-prefixed.
+pub fn decode_call_result(result: &str) -> eyre::Result<Vec<u8>> {
+    let Some(hex_str) = result.strip_prefix("0x") else {
+        return Err(UnprefixedCallResult(result.to_string()).into());
+    };
+    hex::decode(hex_str).map_err(|e| eyre::eyre!("Failed to decode hex response: {}", e))
+}
+
+/// Decode an unprefixed `eth_call` result. With `single_uint`, for a function returning a single
+/// uint, a string of decimal digits is read as the value and ABI-encoded as one word, unless it
+/// is a whole 64-digit word already. Anything else is decoded as hex.
+pub fn decode_unprefixed_call_result(result: &str, single_uint: bool) -> eyre::Result<Vec<u8>> {
+    let decimal = !result.is_empty() && result.bytes().all(|b| b.is_ascii_digit());
+    if single_uint && decimal && result.len() != 64 {
+        let value = U256::from_str_radix(result, 10)
+            .map_err(|e| eyre::eyre!("Failed to decode decimal response {}: {}", result, e))?;
+        return Ok(value.to_be_bytes::<32>().to_vec());
+    }
+    hex::decode(result).map_err(|e| eyre::eyre!("Failed to decode hex response: {}", e))
+}
+
+/// Read-only contract access used by the system contract clients.
+/// Implemented by [`EthereumRPC`]; abstracted so the clients can be driven without a live node.
+#[async_trait]
+pub trait ContractCaller: Send + Sync {
+    /// Execute an `eth_call` against contract `to` with ABI-encoded `data`
+    async fn eth_call(&self, to: &str, data: &[u8]) -> eyre::Result<Vec<u8>>;
+
+    /// Execute an `eth_call` against the state at `block`
+    async fn eth_call_at(&self, to: &str, data: &[u8], block: BlockTag) -> eyre::Result<Vec<u8>> {
+        match block {
+            BlockTag::Latest => self.eth_call(to, data).await,
+            _ => Err(eyre::eyre!("Historical eth_call is not supported by this caller")),
+        }
+    }
+
+    /// Execute an `eth_getLogs` query with the given filter object
+    async fn get_logs(&self, _filter: serde_json::Value) -> eyre::Result<Vec<Log>> {
+        Err(eyre::eyre!("eth_getLogs is not supported by this caller"))
+    }
+
+    /// Number of the block `block` currently refers to, e.g. the latest finalized one
+    async fn block_number_at(&self, _block: BlockTag) -> eyre::Result<u64> {
+        Err(eyre::eyre!("Resolving block tags is not supported by this caller"))
+    }
+}
+
 /// RPC client for Ethereum server.
 #[derive(Clone)]
 pub struct EthereumRPC {
     client: Client,
     url: Url,
+    /// Sent with every request, e.g. an `Authorization` bearer token
+    headers: HeaderMap,
+    /// Log every request at trace level, with header values redacted
+    trace_calls: bool,
 }
 
 impl EthereumRPC {
@@ -72,15 +178,52 @@ impl EthereumRPC {
         Ok(Self {
             client: Client::builder().build()?,
             url,
+            headers: HeaderMap::new(),
+            trace_calls: false,
         })
     }
 
+    /// Send the header `name: value` with every request.
+    /// The value is treated as a secret and never logged.
+    pub fn with_header(mut self, name: &str, value: &str) -> eyre::Result<Self> {
+        let name = HeaderName::from_bytes(name.as_bytes())?;
+        let mut value = HeaderValue::from_str(value)?;
+        value.set_sensitive(true);
+        self.headers.insert(name, value);
+        Ok(self)
+    }
+
+    /// Log every request (method, params and header names) at trace level
+    pub fn with_trace_calls(mut self, trace_calls: bool) -> Self {
+        self.trace_calls = trace_calls;
+        self
+    }
+
+    /// Trace line of a request. Header values are redacted since they usually carry credentials.
+    fn describe_request(&self, method: &str, params: &serde_json::Value) -> String {
+        let headers: Vec<String> = self
+            .headers
+            .keys()
+            .map(|name| format!("{}: <redacted>", name))
+            .collect();
+        format!(
+            "{} {} params={} headers=[{}]",
+            self.url,
+            method,
+            params,
+            headers.join(", ")
+        )
+    }
+
     pub async fn rpc_request<D: DeserializeOwned>(
         &self,
         method: &str,
         params: serde_json::Value,
         timeout: Duration,
     ) -> eyre::Result<D> {
+        if self.trace_calls {
+            trace!("RPC request: {}", self.describe_request(method, &params));
+        }
         let body = JsonRequestBody {
             jsonrpc: "2.0",
             method,
@@ -92,6 +235,7 @@ impl EthereumRPC {
             .post(self.url.clone())
             .timeout(timeout)
             .header(CONTENT_TYPE, "application/json")
+            .headers(self.headers.clone())
             .json(&body);
         let body: JsonResponseBody = request.send().await?.error_for_status()?.json().await?;
 
@@ -99,11 +243,11 @@ impl EthereumRPC {
 
         match (body.result, body.error) {
             (result, None) => serde_json::from_value(result).map_err(Into::into),
-            (_, Some(error)) => Err(eyre::eyre!(
-                "Server Message: code: {}, message: {}",
-                error.code,
-                error.message,
-            )),
+            (_, Some(error)) => Err(RpcError {
+                code: error.code,
+                message: error.message,
+            }
+            .into()),
         }
     }
 
@@ -113,6 +257,12 @@ impl EthereumRPC {
             .await
     }
 
+    /// Get the number of the latest block, as a hex quantity
+    pub async fn get_block_number(&self) -> eyre::Result<String> {
+        self.rpc_request("eth_blockNumber", json!([]), Duration::from_secs(1))
+            .await
+    }
+
     pub async fn get_block_by_number(
         &self,
         block_number: &str,
@@ -171,19 +321,29 @@ impl EthereumRPC {
 
     /// eth_call wrapper for contracts
     pub async fn eth_call(&self, to: &str, data: &[u8]) -> eyre::Result<Vec<u8>> {
+        self.eth_call_at(to, data, BlockTag::Latest).await
+    }
+
+    /// eth_call wrapper for contracts, against the state at `block`. An unprefixed result is
+    /// returned as an [`UnprefixedCallResult`] error, for the caller to decode by output type.
+    pub async fn eth_call_at(&self, to: &str, data: &[u8], block: BlockTag) -> eyre::Result<Vec<u8>> {
         let params = json!([
             {
                 "to": to,
                 "data": format!("0x{}", hex::encode(data))
             },
-            "latest"
+            block.to_param()
         ]);
         
         let result: String = self.rpc_request("eth_call", params, Duration::from_secs(5)).await?;
         
-        // Remove 0x prefix and decode hex
-        let hex_str = result.strip_prefix("0x").unwrap_or(&result);
-        hex::decode(hex_str).map_err(|e| eyre::eyre!("Failed to decode hex response: {}", e))
+        decode_call_result(&result)
+    }
+
+    /// Get logs matching a filter object (eth_getLogs)
+    pub async fn get_logs(&self, filter: serde_json::Value) -> eyre::Result<Vec<Log>> {
+        let params = json!([filter]);
+        self.rpc_request("eth_getLogs", params, Duration::from_secs(10)).await
     }
 
     /// Get transaction receipt (eth_getTransactionReceipt)
@@ -215,3 +375,160 @@ impl EthereumRPC {
         self.rpc_request("eth_estimateGas", params, Duration::from_secs(5)).await
     }
 }
+
+#[async_trait]
+impl ContractCaller for EthereumRPC {
+    async fn eth_call(&self, to: &str, data: &[u8]) -> eyre::Result<Vec<u8>> {
+        EthereumRPC::eth_call(self, to, data).await
+    }
+
+    async fn eth_call_at(&self, to: &str, data: &[u8], block: BlockTag) -> eyre::Result<Vec<u8>> {
+        EthereumRPC::eth_call_at(self, to, data, block).await
+    }
+
+    async fn get_logs(&self, filter: serde_json::Value) -> eyre::Result<Vec<Log>> {
+        EthereumRPC::get_logs(self, filter).await
+    }
+
+    async fn block_number_at(&self, block: BlockTag) -> eyre::Result<u64> {
+        let tag = block.to_param();
+        let tag = tag
+            .as_str()
+            .ok_or_else(|| eyre::eyre!("Can't resolve block {:?} to a number", block))?;
+        self.get_block_by_number(tag)
+            .await?
+            .map(|block| block.block_number)
+            .ok_or_else(|| eyre::eyre!("Block {} not found", tag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const TOKEN: &str = "Bearer s3cr3t-token";
+
+    /// Answer a single JSON-RPC request with `result` and return the raw request received
+    async fn serve_once(listener: TcpListener, result: &str) -> String {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        // Read the head and the body announced by its content length
+        loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request).to_lowercase();
+            if let Some(head_end) = text.find("\r\n\r\n") {
+                let content_length = text
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .and_then(|len| len.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                if request.len() >= head_end + 4 + content_length {
+                    break;
+                }
+            }
+        }
+
+        let body = format!(r#"{{"jsonrpc":"2.0","id":1,"result":"{}"}}"#, result);
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+        String::from_utf8_lossy(&request).into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_custom_header_is_sent() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve_once(listener, "0x2ca"));
+
+        let rpc = EthereumRPC::new(url.parse().unwrap())
+            .unwrap()
+            .with_header("Authorization", TOKEN)
+            .unwrap();
+        assert_eq!(rpc.get_chain_id().await.unwrap(), "0x2ca");
+
+        let request = server.await.unwrap();
+        assert!(request
+            .to_lowercase()
+            .contains(&format!("authorization: {}", TOKEN.to_lowercase())));
+    }
+
+    #[tokio::test]
+    async fn test_state_root_is_sent_as_block_param() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve_once(listener, "0x2a"));
+
+        let root = B256::repeat_byte(0xab);
+        let rpc = EthereumRPC::new(url.parse().unwrap()).unwrap();
+        let result = rpc
+            .eth_call_at(
+                "0x0000000000000000000000000000000000002002",
+                &[1, 2, 3, 4],
+                BlockTag::StateRoot(root),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result, vec![0x2a]);
+
+        let request = server.await.unwrap();
+        assert!(request.contains(&format!(r#"{{"stateRoot":"{}"}}"#, root)));
+    }
+
+    #[test]
+    fn test_header_value_is_redacted_in_trace() {
+        let rpc = EthereumRPC::new("http://127.0.0.1:8545".parse().unwrap())
+            .unwrap()
+            .with_header("Authorization", TOKEN)
+            .unwrap()
+            .with_trace_calls(true);
+
+        let line = rpc.describe_request("eth_chainId", &json!([]));
+
+        assert!(line.contains("authorization: <redacted>"));
+        assert!(!line.contains("s3cr3t"));
+        assert!(!format!("{:?}", rpc.headers).contains("s3cr3t"));
+    }
+
+    #[test]
+    fn test_decode_decimal_call_result() {
+        let stake = U256::from(123_456_789u64) * U256::from(10u64.pow(18));
+
+        // Only a single uint output is read as decimal
+        let err = decode_call_result(&stake.to_string()).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<UnprefixedCallResult>(),
+            Some(&UnprefixedCallResult(stake.to_string()))
+        );
+        let decoded = decode_unprefixed_call_result(&stake.to_string(), true).unwrap();
+        assert_eq!(decoded.len(), 32);
+        assert_eq!(U256::from_be_slice(&decoded), stake);
+
+        let word = hex::encode(stake.to_be_bytes::<32>());
+        assert_eq!(decode_call_result(&format!("0x{}", word)).unwrap(), decoded);
+        assert_eq!(decode_unprefixed_call_result(&word, true).unwrap(), decoded);
+        assert!(decode_call_result("0x12g").is_err());
+
+        // All-digit hex stays hex
+        let all_digits = format!("{:064x}", 0x1234);
+        assert_eq!(
+            U256::from_be_slice(&decode_unprefixed_call_result(&all_digits, true).unwrap()),
+            U256::from(0x1234)
+        );
+        assert_eq!(
+            decode_unprefixed_call_result("1234", false).unwrap(),
+            vec![0x12, 0x34]
+        );
+        assert_eq!(
+            decode_unprefixed_call_result("12ab", true).unwrap(),
+            vec![0x12, 0xab]
+        );
+    }
+}
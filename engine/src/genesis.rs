@@ -2,18 +2,32 @@
 // Parses validator addresses from genesis block header's extraData field
 // Format (similar to BSC Parlia):
 // - First 32 bytes: vanity (all zeros)
-// - Middle: validator
+// - Middle: validator records (fixed 80-byte records, or an RLP list) + epoch length
 // - Last 65 bytes: seal (all zeros in genesis)
 
-use alloy_primitives::Address;
+use crate::pubkey::{to_public_key, to_public_key_with, PublicKeyError};
+use crate::stake_hub_client::{
+    find_collisions, Collision, CollisionKind, ValidatorElectionInfo, VOTING_POWER_DIVISOR,
+};
+use alloy_primitives::{keccak256, Address, B256, U256};
+use alloy_rlp::{Decodable, Encodable, Header};
 use color_eyre::eyre::{eyre, Result};
+use malachitebft_eth_types::PublicKey;
+use std::fmt;
+use std::io::{ErrorKind, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 use tracing::info;
 
 const EXTRA_VANITY_LEN: usize = 32;
 const EXTRA_SEAL_LEN: usize = 65;
+const EPOCH_LENGTH_LEN: usize = 8;
+const VALIDATOR_RECORD_LEN: usize = 80;
+const STAKE_LEN: usize = 32;
+const STAKE_RECORD_LEN: usize = VALIDATOR_RECORD_LEN - 8 + STAKE_LEN;
 
 /// Validator information from genesis extraData
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GenesisValidatorInfo {
     pub consensus_address: Address, // Consensus address (primary identifier)
     pub operator_address: Address,  // Operator address (for contract interactions)
@@ -21,6 +35,49 @@ pub struct GenesisValidatorInfo {
     pub voting_power: u64,          // Voting power from genesis
 }
 
+impl GenesisValidatorInfo {
+    /// The tendermint pubkey as a consensus [`PublicKey`]
+    pub fn public_key(&self) -> Result<PublicKey, PublicKeyError> {
+        to_public_key(&self.tendermint_pubkey)
+    }
+}
+
+/// Encoding of the validator records between the vanity and the epoch length
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExtraDataLayout {
+    /// Back-to-back `consensusAddr(20) + operatorAddr(20) + votingPower(8) + tendermintPubKey(32)` records
+    #[default]
+    Fixed,
+    /// A single RLP list of `[consensusAddr, operatorAddr, votingPower, tendermintPubKey]` records,
+    /// see [`encode_validators_rlp`]
+    Rlp,
+    /// Back-to-back `consensusAddr(20) + operatorAddr(20) + stake(32) + tendermintPubKey(32)`
+    /// records, with the stake as the big-endian `uint256` StakeHub reports. It is scaled to a
+    /// voting power like elected stakes (see [`VOTING_POWER_DIVISOR`]), so genesis and StakeHub
+    /// powers share a unit. See [`encode_stake_records`].
+    FixedStake,
+}
+
+/// Options of genesis extraData parsing and validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenesisOptions {
+    /// Accept validators using the same address as consensus and operator address
+    pub allow_same_address: bool,
+    pub layout: ExtraDataLayout,
+    /// Also report pubkeys that aren't canonically encoded
+    pub strict_pubkeys: bool,
+}
+
+impl Default for GenesisOptions {
+    fn default() -> Self {
+        Self {
+            allow_same_address: true,
+            layout: ExtraDataLayout::default(),
+            strict_pubkeys: false,
+        }
+    }
+}
+
 /// Parse validators WITH complete information from extraData
 ///
 /// Format: vanity(32) + [consensusAddr(20) + operatorAddr(20) + votingPower(8) + tendermintPubKey(32)] * N + epochLength(8) + seal(65)
@@ -32,6 +89,26 @@ pub struct GenesisValidatorInfo {
 /// * `Vec<GenesisValidatorInfo>` - List of validators with complete information
 /// * `u64` - Epoch length in blocks
 pub fn parse_validators_from_extra_data(extra_data: &[u8]) -> Result<(Vec<GenesisValidatorInfo>, u64)> {
+    parse_validators_from_extra_data_with_options(extra_data, &GenesisOptions::default())
+}
+
+/// Parse an extraData whose validator records use the [`ExtraDataLayout::Rlp`] layout:
+/// vanity(32) + rlp([[consensusAddr, operatorAddr, votingPower, tendermintPubKey], ...]) + epochLength(8) + seal(65)
+pub fn parse_validators_from_extra_data_rlp(
+    extra_data: &[u8],
+) -> Result<(Vec<GenesisValidatorInfo>, u64)> {
+    let options = GenesisOptions {
+        layout: ExtraDataLayout::Rlp,
+        ..GenesisOptions::default()
+    };
+    parse_validators_from_extra_data_with_options(extra_data, &options)
+}
+
+/// Same as [`parse_validators_from_extra_data`], with explicit [`GenesisOptions`]
+pub fn parse_validators_from_extra_data_with_options(
+    extra_data: &[u8],
+    options: &GenesisOptions,
+) -> Result<(Vec<GenesisValidatorInfo>, u64)> {
     // Minimum length check
     let min_len = EXTRA_VANITY_LEN + EXTRA_SEAL_LEN;
 
@@ -71,6 +148,46 @@ pub fn parse_validators_from_extra_data(extra_data: &[u8]) -> Result<(Vec<Genesi
 
     // Calculate validator data length (excluding epoch_length)
     let validator_data_len = middle_data_len - 8;
+    let validator_data = &extra_data[EXTRA_VANITY_LEN..EXTRA_VANITY_LEN + validator_data_len];
+
+    if options.layout == ExtraDataLayout::Rlp {
+        let validators = decode_validators_rlp(validator_data)
+            .map_err(|e| eyre!("Invalid RLP validator records in extraData: {}", e))?;
+        info!(
+            "📖 Parsing RLP extraData: {} validators, epoch length {} blocks",
+            validators.len(),
+            epoch_length
+        );
+        return check_parsed_validators(validators, epoch_length, options);
+    }
+
+    if options.layout == ExtraDataLayout::FixedStake {
+        let records = validator_data.chunks_exact(STAKE_RECORD_LEN);
+        if !records.remainder().is_empty() {
+            return Err(eyre!(
+                "Invalid extraData format: validator data length {} is not a multiple of {}. \
+                 Expected format: vanity(32) + [consensusAddr(20) + operatorAddr(20) + stake(32) + tendermintPubKey(32)] * N + epochLength(8) + seal(65)",
+                validator_data_len,
+                STAKE_RECORD_LEN
+            ));
+        }
+        let validators = records
+            .enumerate()
+            .map(|(i, record)| match decode_stake_record(record) {
+                (validator, false) => Ok(validator),
+                (_, true) => Err(eyre!(
+                    "Validator #{} has a stake too large for a voting power",
+                    i + 1
+                )),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        info!(
+            "📖 Parsing stake extraData: {} validators, epoch length {} blocks",
+            validators.len(),
+            epoch_length
+        );
+        return check_parsed_validators(validators, epoch_length, options);
+    }
 
     // Format: N * (20 + 20 + 8 + 32) = N * 80
     if validator_data_len % 80 != 0 {
@@ -89,49 +206,40 @@ pub fn parse_validators_from_extra_data(extra_data: &[u8]) -> Result<(Vec<Genesi
     info!("   Epoch length: {} blocks", epoch_length);
     info!("   Format: vanity(32) + [consensusAddr(20) + operatorAddr(20) + votingPower(8) + tendermintPubKey(32)] * {} + epochLength(8) + seal(65)", validator_count);
 
+    let validators = validator_data
+        .chunks_exact(VALIDATOR_RECORD_LEN)
+        .map(decode_validator_record)
+        .collect();
+    check_parsed_validators(validators, epoch_length, options)
+}
+
+/// Apply the per-validator checks of [`GenesisOptions`] to parsed validators and log them
+fn check_parsed_validators(
+    validators: Vec<GenesisValidatorInfo>,
+    epoch_length: u64,
+    options: &GenesisOptions,
+) -> Result<(Vec<GenesisValidatorInfo>, u64)> {
     let mut result = Vec::new();
-    for i in 0..validator_count {
-        let validator_start = EXTRA_VANITY_LEN + (i * 80);
-
-        // Extract consensus address (20 bytes)
-        let consensus_addr =
-            Address::from_slice(&extra_data[validator_start..validator_start + 20]);
-
-        // Extract operator address (20 bytes)
-        let operator_addr =
-            Address::from_slice(&extra_data[validator_start + 20..validator_start + 40]);
-
-        // Extract voting power (8 bytes, big-endian uint64)
-        let voting_power_bytes = &extra_data[validator_start + 40..validator_start + 48];
-        let voting_power = u64::from_be_bytes([
-            voting_power_bytes[0],
-            voting_power_bytes[1],
-            voting_power_bytes[2],
-            voting_power_bytes[3],
-            voting_power_bytes[4],
-            voting_power_bytes[5],
-            voting_power_bytes[6],
-            voting_power_bytes[7],
-        ]);
-
-        // Extract tendermint public key (32 bytes)
-        let tendermint_pubkey = extra_data[validator_start + 48..validator_start + 80].to_vec();
+    for (i, validator) in validators.into_iter().enumerate() {
+        if !options.allow_same_address && validator.consensus_address == validator.operator_address
+        {
+            return Err(eyre!(
+                "Validator #{} uses {} as both consensus and operator address",
+                i + 1,
+                validator.consensus_address
+            ));
+        }
 
         info!(
             "  Validator #{}: consensus={}, operator={}, voting_power={}, pubkey={:?}",
             i + 1,
-            consensus_addr,
-            operator_addr,
-            voting_power,
-            hex::encode(&tendermint_pubkey)
+            validator.consensus_address,
+            validator.operator_address,
+            validator.voting_power,
+            hex::encode(&validator.tendermint_pubkey)
         );
 
-        result.push(GenesisValidatorInfo {
-            consensus_address: consensus_addr,
-            operator_address: operator_addr,
-            tendermint_pubkey,
-            voting_power,
-        });
+        result.push(validator);
     }
 
     info!(
@@ -141,3 +249,848 @@ pub fn parse_validators_from_extra_data(extra_data: &[u8]) -> Result<(Vec<Genesi
     );
     Ok((result, epoch_length))
 }
+
+/// Streaming version of [`parse_validators_from_extra_data`], for the
+/// [`ExtraDataLayout::Fixed`] layout: reads the extraData from
+/// `reader` and passes each validator to `on_validator` as soon as its record is read, so the
+/// whole extraData is never held in memory. Returns the epoch length.
+///
+/// Since the validator count isn't encoded, a record is only known to be one once the
+/// epochLength(8) + seal(65) tail is buffered behind it.
+pub fn parse_validators_from_reader<R: Read>(
+    mut reader: R,
+    mut on_validator: impl FnMut(GenesisValidatorInfo) -> Result<()>,
+) -> Result<u64> {
+    let mut vanity = [0u8; EXTRA_VANITY_LEN];
+    reader
+        .read_exact(&mut vanity)
+        .map_err(|e| eyre!("extraData too short to contain the vanity: {}", e))?;
+
+    let tail_len = EPOCH_LENGTH_LEN + EXTRA_SEAL_LEN;
+    let mut buffer = Vec::with_capacity(2 * VALIDATOR_RECORD_LEN + tail_len);
+    let mut chunk = [0u8; VALIDATOR_RECORD_LEN];
+    loop {
+        while buffer.len() >= VALIDATOR_RECORD_LEN + tail_len {
+            on_validator(decode_validator_record(&buffer[..VALIDATOR_RECORD_LEN]))?;
+            buffer.drain(..VALIDATOR_RECORD_LEN);
+        }
+
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    if buffer.len() != tail_len {
+        return Err(eyre!(
+            "Invalid extraData format: {} trailing bytes after the validator records, expected epochLength(8) + seal(65)",
+            buffer.len()
+        ));
+    }
+    Ok(u64::from_be_bytes(
+        buffer[..EPOCH_LENGTH_LEN].try_into().unwrap(),
+    ))
+}
+
+/// Decode one `consensusAddr(20) + operatorAddr(20) + votingPower(8) + tendermintPubKey(32)` record
+fn decode_validator_record(record: &[u8]) -> GenesisValidatorInfo {
+    // Extract consensus address (20 bytes)
+    let consensus_address = Address::from_slice(&record[0..20]);
+
+    // Extract operator address (20 bytes)
+    let operator_address = Address::from_slice(&record[20..40]);
+
+    // Extract voting power (8 bytes, big-endian uint64)
+    let voting_power = u64::from_be_bytes(record[40..48].try_into().unwrap());
+
+    // Extract tendermint public key (32 bytes)
+    let tendermint_pubkey = record[48..80].to_vec();
+
+    GenesisValidatorInfo {
+        consensus_address,
+        operator_address,
+        tendermint_pubkey,
+        voting_power,
+    }
+}
+
+/// Validators and epoch length parsed from a genesis extraData
+type ParsedExtraData = (Vec<GenesisValidatorInfo>, u64);
+
+/// Genesis validators parsed once and reused, for pipelines that see the same genesis extraData
+/// repeatedly. The cache holds the last successful parse, keyed by the keccak256 hash of its
+/// extraData; parse errors are returned without being cached.
+#[derive(Debug, Default)]
+pub struct GenesisValidators {
+    options: GenesisOptions,
+    cached: Mutex<Option<(B256, ParsedExtraData)>>,
+    /// Number of times extraData was actually parsed
+    parses: AtomicU64,
+}
+
+impl GenesisValidators {
+    pub fn new(options: GenesisOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Validators and epoch length of `extra_data`, parsed with
+    /// [`parse_validators_from_extra_data_with_options`] unless the same bytes were parsed last
+    pub fn get_or_parse(&self, extra_data: &[u8]) -> Result<ParsedExtraData> {
+        let hash = keccak256(extra_data);
+        let mut cached = self.cached.lock().unwrap();
+        if let Some((cached_hash, parsed)) = cached.as_ref() {
+            if *cached_hash == hash {
+                return Ok(parsed.clone());
+            }
+        }
+
+        self.parses.fetch_add(1, Ordering::Relaxed);
+        let parsed = parse_validators_from_extra_data_with_options(extra_data, &self.options)?;
+        *cached = Some((hash, parsed.clone()));
+        Ok(parsed)
+    }
+}
+
+/// Copy of `extra_data` with its epoch length replaced by `new_epoch_length`. The extraData must
+/// parse with [`parse_validators_from_extra_data_with_options`]; every other byte, including the
+/// vanity and seal, is kept as is.
+pub fn rewrite_epoch_length(extra_data: &[u8], new_epoch_length: u64) -> Result<Vec<u8>> {
+    rewrite_epoch_length_with_options(extra_data, new_epoch_length, &GenesisOptions::default())
+}
+
+/// Same as [`rewrite_epoch_length`], with explicit [`GenesisOptions`]
+pub fn rewrite_epoch_length_with_options(
+    extra_data: &[u8],
+    new_epoch_length: u64,
+    options: &GenesisOptions,
+) -> Result<Vec<u8>> {
+    parse_validators_from_extra_data_with_options(extra_data, options)?;
+
+    let mut rewritten = extra_data.to_vec();
+    let epoch_length_start = rewritten.len() - EXTRA_SEAL_LEN - EPOCH_LENGTH_LEN;
+    rewritten[epoch_length_start..epoch_length_start + EPOCH_LENGTH_LEN]
+        .copy_from_slice(&new_epoch_length.to_be_bytes());
+    Ok(rewritten)
+}
+
+/// Decode an [`ExtraDataLayout::FixedStake`] record. The voting power saturates at `u64::MAX`,
+/// with `true` returned if it overflowed.
+fn decode_stake_record(record: &[u8]) -> (GenesisValidatorInfo, bool) {
+    let stake = U256::from_be_slice(&record[40..40 + STAKE_LEN]);
+    let voting_power = u64::try_from(stake / U256::from(VOTING_POWER_DIVISOR));
+    let validator = GenesisValidatorInfo {
+        consensus_address: Address::from_slice(&record[0..20]),
+        operator_address: Address::from_slice(&record[20..40]),
+        tendermint_pubkey: record[40 + STAKE_LEN..].to_vec(),
+        voting_power: voting_power.unwrap_or(u64::MAX),
+    };
+    (validator, voting_power.is_err())
+}
+
+/// Records of `validators` for the middle section of an [`ExtraDataLayout::FixedStake`]
+/// extraData, each voting power written as its stake (`power * VOTING_POWER_DIVISOR`).
+/// Pubkeys must be 32 bytes long.
+pub fn encode_stake_records(validators: &[GenesisValidatorInfo]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(validators.len() * STAKE_RECORD_LEN);
+    for validator in validators {
+        let stake = U256::from(validator.voting_power) * U256::from(VOTING_POWER_DIVISOR);
+        out.extend_from_slice(validator.consensus_address.as_slice());
+        out.extend_from_slice(validator.operator_address.as_slice());
+        out.extend_from_slice(&stake.to_be_bytes::<STAKE_LEN>());
+        out.extend_from_slice(&validator.tendermint_pubkey);
+    }
+    out
+}
+
+/// RLP encoding of `validators` for the middle section of an [`ExtraDataLayout::Rlp`] extraData
+pub fn encode_validators_rlp(validators: &[GenesisValidatorInfo]) -> Vec<u8> {
+    let mut out = Vec::new();
+    alloy_rlp::encode_list(validators, &mut out);
+    out
+}
+
+/// Decode the validator section of an [`ExtraDataLayout::Rlp`] extraData, which must hold exactly
+/// one RLP list
+fn decode_validators_rlp(mut data: &[u8]) -> alloy_rlp::Result<Vec<GenesisValidatorInfo>> {
+    let validators = Vec::<GenesisValidatorInfo>::decode(&mut data)?;
+    if !data.is_empty() {
+        return Err(alloy_rlp::Error::Custom(
+            "trailing bytes after the validator list",
+        ));
+    }
+    Ok(validators)
+}
+
+impl GenesisValidatorInfo {
+    fn rlp_payload_length(&self) -> usize {
+        self.consensus_address.length()
+            + self.operator_address.length()
+            + self.voting_power.length()
+            + self.tendermint_pubkey.as_slice().length()
+    }
+}
+
+impl Encodable for GenesisValidatorInfo {
+    fn encode(&self, out: &mut dyn alloy_rlp::BufMut) {
+        Header {
+            list: true,
+            payload_length: self.rlp_payload_length(),
+        }
+        .encode(out);
+        self.consensus_address.encode(out);
+        self.operator_address.encode(out);
+        self.voting_power.encode(out);
+        self.tendermint_pubkey.as_slice().encode(out);
+    }
+
+    fn length(&self) -> usize {
+        let payload_length = self.rlp_payload_length();
+        payload_length + alloy_rlp::length_of_length(payload_length)
+    }
+}
+
+impl Decodable for GenesisValidatorInfo {
+    fn decode(buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+        let mut payload = Header::decode_bytes(buf, true)?;
+        let consensus_address = Address::decode(&mut payload)?;
+        let operator_address = Address::decode(&mut payload)?;
+        let voting_power = u64::decode(&mut payload)?;
+        let tendermint_pubkey = Header::decode_bytes(&mut payload, false)?.to_vec();
+        if !payload.is_empty() {
+            return Err(alloy_rlp::Error::Custom(
+                "unexpected fields in validator record",
+            ));
+        }
+
+        Ok(Self {
+            consensus_address,
+            operator_address,
+            tendermint_pubkey,
+            voting_power,
+        })
+    }
+}
+
+/// Problem found in a genesis extraData by [`validate_extra_data`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtraDataIssue {
+    /// Shorter than vanity + epoch length + seal
+    TooShort { len: usize, min_len: usize },
+    /// The validator section doesn't split into whole records of `record_len` bytes
+    MisalignedValidatorData { len: usize, record_len: usize },
+    /// The validator section of an [`ExtraDataLayout::Rlp`] extraData isn't a valid RLP list of
+    /// validator records
+    InvalidRlp { reason: String },
+    /// No validator record at all
+    NoValidators,
+    /// The epoch length is zero
+    ZeroEpochLength,
+    /// Validator `index` (0-based) has no voting power
+    ZeroVotingPower { index: usize },
+    /// Validator `index` has a stake whose voting power doesn't fit a `u64`
+    VotingPowerOverflow { index: usize },
+    /// Validator `index` has a zero consensus or operator address
+    ZeroAddress { index: usize },
+    /// Validator `index` uses the same consensus and operator address, which the
+    /// [`GenesisOptions`] forbid
+    SameConsensusAndOperatorAddress { index: usize },
+    /// Validator `index` has a pubkey that isn't a valid Ed25519 point
+    InvalidPubkey { index: usize },
+    /// Validators `first` and `duplicate` share a consensus address
+    DuplicateConsensusAddress {
+        address: Address,
+        first: usize,
+        duplicate: usize,
+    },
+    /// Validators `first` and `duplicate` share an operator address
+    DuplicateOperatorAddress {
+        address: Address,
+        first: usize,
+        duplicate: usize,
+    },
+    /// Validators `first` and `duplicate` share a tendermint pubkey
+    DuplicatePubkey { first: usize, duplicate: usize },
+}
+
+impl fmt::Display for ExtraDataIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtraDataIssue::TooShort { len, min_len } => {
+                write!(
+                    f,
+                    "extraData too short: {} bytes, expected at least {}",
+                    len, min_len
+                )
+            }
+            ExtraDataIssue::MisalignedValidatorData { len, record_len } => write!(
+                f,
+                "validator data length {} is not a multiple of {}",
+                len, record_len
+            ),
+            ExtraDataIssue::InvalidRlp { reason } => {
+                write!(f, "invalid RLP validator records: {}", reason)
+            }
+            ExtraDataIssue::NoValidators => write!(f, "no validators"),
+            ExtraDataIssue::ZeroEpochLength => write!(f, "epoch length is zero"),
+            ExtraDataIssue::ZeroVotingPower { index } => {
+                write!(f, "validator #{} has zero voting power", index + 1)
+            }
+            ExtraDataIssue::VotingPowerOverflow { index } => {
+                write!(
+                    f,
+                    "validator #{} has a voting power overflowing u64",
+                    index + 1
+                )
+            }
+            ExtraDataIssue::ZeroAddress { index } => {
+                write!(
+                    f,
+                    "validator #{} has a zero consensus or operator address",
+                    index + 1
+                )
+            }
+            ExtraDataIssue::SameConsensusAndOperatorAddress { index } => write!(
+                f,
+                "validator #{} uses the same consensus and operator address",
+                index + 1
+            ),
+            ExtraDataIssue::InvalidPubkey { index } => {
+                write!(f, "validator #{} has an invalid Ed25519 pubkey", index + 1)
+            }
+            ExtraDataIssue::DuplicateConsensusAddress {
+                address,
+                first,
+                duplicate,
+            } => write!(
+                f,
+                "validators #{} and #{} share consensus address {}",
+                first + 1,
+                duplicate + 1,
+                address
+            ),
+            ExtraDataIssue::DuplicateOperatorAddress {
+                address,
+                first,
+                duplicate,
+            } => write!(
+                f,
+                "validators #{} and #{} share operator address {}",
+                first + 1,
+                duplicate + 1,
+                address
+            ),
+            ExtraDataIssue::DuplicatePubkey { first, duplicate } => write!(
+                f,
+                "validators #{} and #{} share a tendermint pubkey",
+                first + 1,
+                duplicate + 1
+            ),
+        }
+    }
+}
+
+/// Result of [`validate_extra_data`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Number of complete validator records found
+    pub validator_count: usize,
+    /// Epoch length, if the extraData is long enough to contain one
+    pub epoch_length: Option<u64>,
+    /// Every problem found, in extraData order
+    pub issues: Vec<ExtraDataIssue>,
+}
+
+impl ValidationReport {
+    /// Whether no issue was found
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let epoch_length = self.epoch_length.map_or_else(
+            || "unknown".to_string(),
+            |epoch_length| epoch_length.to_string(),
+        );
+        if self.is_valid() {
+            return write!(
+                f,
+                "PASS: {} validators, epoch length {}",
+                self.validator_count, epoch_length
+            );
+        }
+
+        write!(
+            f,
+            "FAIL: {} issue(s) ({} validators, epoch length {})",
+            self.issues.len(),
+            self.validator_count,
+            epoch_length
+        )?;
+        for issue in &self.issues {
+            write!(f, "\n  - {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Validate a genesis extraData end to end, collecting every problem instead of stopping at the
+/// first one. Same format as [`parse_validators_from_extra_data`].
+pub fn validate_extra_data(extra_data: &[u8]) -> ValidationReport {
+    validate_extra_data_with_options(extra_data, &GenesisOptions::default())
+}
+
+/// Same as [`validate_extra_data`], with explicit [`GenesisOptions`]
+pub fn validate_extra_data_with_options(
+    extra_data: &[u8],
+    options: &GenesisOptions,
+) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    let min_len = EXTRA_VANITY_LEN + EPOCH_LENGTH_LEN + EXTRA_SEAL_LEN;
+    if extra_data.len() < min_len {
+        report.issues.push(ExtraDataIssue::TooShort {
+            len: extra_data.len(),
+            min_len,
+        });
+        return report;
+    }
+
+    let epoch_length_start = extra_data.len() - EXTRA_SEAL_LEN - EPOCH_LENGTH_LEN;
+    let epoch_length = u64::from_be_bytes(
+        extra_data[epoch_length_start..epoch_length_start + EPOCH_LENGTH_LEN]
+            .try_into()
+            .unwrap(),
+    );
+    report.epoch_length = Some(epoch_length);
+
+    let validator_data = &extra_data[EXTRA_VANITY_LEN..epoch_length_start];
+    let validators: Vec<GenesisValidatorInfo> = match options.layout {
+        ExtraDataLayout::Fixed => {
+            // Check every complete record even if the section is misaligned
+            let records = validator_data.chunks_exact(VALIDATOR_RECORD_LEN);
+            if !records.remainder().is_empty() {
+                report.issues.push(ExtraDataIssue::MisalignedValidatorData {
+                    len: validator_data.len(),
+                    record_len: VALIDATOR_RECORD_LEN,
+                });
+            }
+            records.map(decode_validator_record).collect()
+        }
+        ExtraDataLayout::FixedStake => {
+            let records = validator_data.chunks_exact(STAKE_RECORD_LEN);
+            if !records.remainder().is_empty() {
+                report.issues.push(ExtraDataIssue::MisalignedValidatorData {
+                    len: validator_data.len(),
+                    record_len: STAKE_RECORD_LEN,
+                });
+            }
+            records
+                .enumerate()
+                .map(|(index, record)| {
+                    let (validator, overflowed) = decode_stake_record(record);
+                    if overflowed {
+                        report
+                            .issues
+                            .push(ExtraDataIssue::VotingPowerOverflow { index });
+                    }
+                    validator
+                })
+                .collect()
+        }
+        ExtraDataLayout::Rlp => match decode_validators_rlp(validator_data) {
+            Ok(validators) => validators,
+            Err(e) => {
+                report.issues.push(ExtraDataIssue::InvalidRlp {
+                    reason: e.to_string(),
+                });
+                Vec::new()
+            }
+        },
+    };
+    report.validator_count = validators.len();
+    if validators.is_empty() {
+        report.issues.push(ExtraDataIssue::NoValidators);
+    }
+
+    let election_infos: Vec<ValidatorElectionInfo> = validators
+        .iter()
+        .map(|v| ValidatorElectionInfo {
+            consensus_address: v.consensus_address,
+            voting_power: U256::from(v.voting_power),
+            operator_address: v.operator_address,
+            tendermint_pub_key: v.tendermint_pubkey.clone(),
+            status: None,
+            active: None,
+            unbonding: None,
+            self_stake: None,
+        })
+        .collect();
+    let mut collisions = find_collisions(&election_infos).into_iter().peekable();
+
+    for (index, validator) in validators.iter().enumerate() {
+        if validator.voting_power == 0 {
+            report
+                .issues
+                .push(ExtraDataIssue::ZeroVotingPower { index });
+        }
+        if validator.consensus_address.is_zero() || validator.operator_address.is_zero() {
+            report.issues.push(ExtraDataIssue::ZeroAddress { index });
+        }
+        if !options.allow_same_address && validator.consensus_address == validator.operator_address
+        {
+            report
+                .issues
+                .push(ExtraDataIssue::SameConsensusAndOperatorAddress { index });
+        }
+        if to_public_key_with(&validator.tendermint_pubkey, options.strict_pubkeys).is_err() {
+            report.issues.push(ExtraDataIssue::InvalidPubkey { index });
+        }
+        // Collisions come ordered by their duplicate, so each is reported at its validator
+        while let Some(Collision {
+            kind,
+            first,
+            duplicate,
+        }) = collisions.next_if(|c| c.duplicate == index)
+        {
+            report.issues.push(match kind {
+                CollisionKind::ConsensusAddress => ExtraDataIssue::DuplicateConsensusAddress {
+                    address: validator.consensus_address,
+                    first,
+                    duplicate,
+                },
+                CollisionKind::OperatorAddress => ExtraDataIssue::DuplicateOperatorAddress {
+                    address: validator.operator_address,
+                    first,
+                    duplicate,
+                },
+                CollisionKind::TendermintPubKey => {
+                    ExtraDataIssue::DuplicatePubkey { first, duplicate }
+                }
+            });
+        }
+    }
+
+    if epoch_length == 0 {
+        report.issues.push(ExtraDataIssue::ZeroEpochLength);
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(seed: u8, voting_power: u64) -> Vec<u8> {
+        let pubkey = ed25519_consensus::SigningKey::from([seed; 32]).verification_key();
+        let mut record = Vec::with_capacity(VALIDATOR_RECORD_LEN);
+        record.extend_from_slice(Address::repeat_byte(seed).as_slice());
+        record.extend_from_slice(Address::repeat_byte(seed.wrapping_add(0x80)).as_slice());
+        record.extend_from_slice(&voting_power.to_be_bytes());
+        record.extend_from_slice(pubkey.as_bytes());
+        record
+    }
+
+    fn extra_data(records: &[Vec<u8>], epoch_length: u64) -> Vec<u8> {
+        let mut extra_data = vec![0u8; EXTRA_VANITY_LEN];
+        for record in records {
+            extra_data.extend_from_slice(record);
+        }
+        extra_data.extend_from_slice(&epoch_length.to_be_bytes());
+        extra_data.extend_from_slice(&[0u8; EXTRA_SEAL_LEN]);
+        extra_data
+    }
+
+    #[test]
+    fn test_valid_extra_data_passes() {
+        let report = validate_extra_data(&extra_data(&[record(1, 10), record(2, 10)], 100));
+        assert!(report.is_valid(), "{}", report);
+        assert_eq!(report.validator_count, 2);
+        assert_eq!(report.epoch_length, Some(100));
+    }
+
+    #[test]
+    fn test_all_issues_are_reported() {
+        let mut bad_pubkey = record(3, 10);
+        // y = 2 is not the y-coordinate of any curve point
+        bad_pubkey[48..80].copy_from_slice(&[0; 32]);
+        bad_pubkey[48] = 2;
+        let mut data = extra_data(&[record(1, 10), record(1, 0), bad_pubkey], 0);
+        // A stray byte in the validator section
+        data.insert(EXTRA_VANITY_LEN + 3 * VALIDATOR_RECORD_LEN, 0);
+
+        let report = validate_extra_data(&data);
+
+        assert_eq!(report.validator_count, 3);
+        assert_eq!(
+            report.issues,
+            vec![
+                ExtraDataIssue::MisalignedValidatorData {
+                    len: 241,
+                    record_len: VALIDATOR_RECORD_LEN
+                },
+                ExtraDataIssue::ZeroVotingPower { index: 1 },
+                ExtraDataIssue::DuplicateConsensusAddress {
+                    address: Address::repeat_byte(1),
+                    first: 0,
+                    duplicate: 1,
+                },
+                ExtraDataIssue::DuplicateOperatorAddress {
+                    address: Address::repeat_byte(0x81),
+                    first: 0,
+                    duplicate: 1,
+                },
+                ExtraDataIssue::DuplicatePubkey {
+                    first: 0,
+                    duplicate: 1
+                },
+                ExtraDataIssue::InvalidPubkey { index: 2 },
+                ExtraDataIssue::ZeroEpochLength,
+            ]
+        );
+        assert!(report.to_string().starts_with("FAIL: 7 issue(s)"));
+    }
+
+    #[test]
+    fn test_same_address_follows_policy() {
+        let mut same_address = record(2, 10);
+        same_address.copy_within(0..20, 20);
+        let data = extra_data(&[record(1, 10), same_address], 100);
+        let forbid = GenesisOptions {
+            allow_same_address: false,
+            ..GenesisOptions::default()
+        };
+
+        assert!(validate_extra_data(&data).is_valid());
+        let (validators, _) = parse_validators_from_extra_data(&data).unwrap();
+        assert_eq!(
+            validators[1].consensus_address,
+            validators[1].operator_address
+        );
+
+        assert_eq!(
+            validate_extra_data_with_options(&data, &forbid).issues,
+            vec![ExtraDataIssue::SameConsensusAndOperatorAddress { index: 1 }]
+        );
+        assert!(parse_validators_from_extra_data_with_options(&data, &forbid).is_err());
+    }
+
+    #[test]
+    fn test_reader_parser_matches_slice_parser() {
+        let data = extra_data(&[record(1, 10), record(2, 20), record(3, 30)], 100);
+
+        let mut streamed = Vec::new();
+        let epoch_length = parse_validators_from_reader(std::io::Cursor::new(&data), |v| {
+            streamed.push(v);
+            Ok(())
+        })
+        .unwrap();
+
+        let (validators, expected_epoch_length) = parse_validators_from_extra_data(&data).unwrap();
+        assert_eq!(streamed, validators);
+        assert_eq!(epoch_length, expected_epoch_length);
+
+        let mut misaligned = data.clone();
+        misaligned.insert(EXTRA_VANITY_LEN, 0);
+        assert!(parse_validators_from_reader(misaligned.as_slice(), |_| Ok(())).is_err());
+    }
+
+    #[test]
+    fn test_extra_data_with_only_epoch_length() {
+        let data = extra_data(&[], 200);
+        assert_eq!(
+            data.len(),
+            EXTRA_VANITY_LEN + EPOCH_LENGTH_LEN + EXTRA_SEAL_LEN
+        );
+
+        let (validators, epoch_length) = parse_validators_from_extra_data(&data).unwrap();
+        assert!(validators.is_empty());
+        assert_eq!(epoch_length, 200);
+
+        let mut streamed = Vec::new();
+        let epoch_length = parse_validators_from_reader(data.as_slice(), |v| {
+            streamed.push(v);
+            Ok(())
+        })
+        .unwrap();
+        assert!(streamed.is_empty());
+        assert_eq!(epoch_length, 200);
+
+        // Not an error, but flagged for operators expecting genesis validators
+        assert_eq!(
+            validate_extra_data(&data).issues,
+            vec![ExtraDataIssue::NoValidators]
+        );
+    }
+
+    #[test]
+    fn test_rlp_layout_round_trip() {
+        let fixed = extra_data(&[record(1, 10), record(2, 20), record(3, 300)], 100);
+        let (validators, _) = parse_validators_from_extra_data(&fixed).unwrap();
+
+        let rlp = extra_data(&[encode_validators_rlp(&validators)], 100);
+        let (decoded, epoch_length) = parse_validators_from_extra_data_rlp(&rlp).unwrap();
+        assert_eq!(decoded, validators);
+        assert_eq!(epoch_length, 100);
+
+        let options = GenesisOptions {
+            layout: ExtraDataLayout::Rlp,
+            ..GenesisOptions::default()
+        };
+        assert!(validate_extra_data_with_options(&rlp, &options).is_valid());
+
+        // The fixed layout can't read it, and a truncated list is rejected
+        assert!(parse_validators_from_extra_data(&rlp).is_err());
+        let mut truncated = rlp.clone();
+        truncated.remove(EXTRA_VANITY_LEN + 1);
+        assert!(parse_validators_from_extra_data_rlp(&truncated).is_err());
+        assert!(matches!(
+            validate_extra_data_with_options(&truncated, &options).issues[0],
+            ExtraDataIssue::InvalidRlp { .. }
+        ));
+    }
+
+    #[test]
+    fn test_genesis_public_keys() {
+        let fixed = extra_data(&[record(1, 10)], 100);
+        let (validators, _) = parse_validators_from_extra_data(&fixed).unwrap();
+        let key = ed25519_consensus::SigningKey::from([1; 32]).verification_key();
+        assert_eq!(
+            validators[0].public_key().unwrap().as_bytes(),
+            key.as_bytes()
+        );
+
+        // The RLP layout carries pubkeys of any length
+        let mut short = validators[0].clone();
+        short.tendermint_pubkey.truncate(31);
+        let mut long = validators[0].clone();
+        long.tendermint_pubkey.push(0);
+        assert_eq!(short.public_key(), Err(PublicKeyError::InvalidLength(31)));
+        assert_eq!(long.public_key(), Err(PublicKeyError::InvalidLength(33)));
+
+        let rlp = extra_data(&[encode_validators_rlp(&[short, long])], 100);
+        let options = GenesisOptions {
+            layout: ExtraDataLayout::Rlp,
+            ..GenesisOptions::default()
+        };
+        let issues = validate_extra_data_with_options(&rlp, &options).issues;
+        assert!(issues.contains(&ExtraDataIssue::InvalidPubkey { index: 0 }));
+        assert!(issues.contains(&ExtraDataIssue::InvalidPubkey { index: 1 }));
+    }
+
+    #[test]
+    fn test_genesis_validators_parse_once() {
+        let first = extra_data(&[record(1, 10), record(2, 20)], 100);
+        let second = extra_data(&[record(3, 30)], 200);
+        let genesis = GenesisValidators::default();
+
+        let parsed = genesis.get_or_parse(&first).unwrap();
+        assert_eq!(parsed, parse_validators_from_extra_data(&first).unwrap());
+        assert_eq!(genesis.get_or_parse(&first.clone()).unwrap(), parsed);
+        assert_eq!(genesis.parses.load(Ordering::Relaxed), 1);
+
+        let (validators, epoch_length) = genesis.get_or_parse(&second).unwrap();
+        assert_eq!((validators.len(), epoch_length), (1, 200));
+        assert_eq!(genesis.parses.load(Ordering::Relaxed), 2);
+
+        // Failures aren't cached
+        assert!(genesis.get_or_parse(&first[..40]).is_err());
+        assert!(genesis.get_or_parse(&first[..40]).is_err());
+        assert_eq!(genesis.parses.load(Ordering::Relaxed), 4);
+        assert_eq!(genesis.get_or_parse(&second).unwrap().1, 200);
+        assert_eq!(genesis.parses.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn test_strict_pubkeys_report_non_canonical_keys() {
+        let fixed = extra_data(&[record(1, 10)], 100);
+        let (mut validators, _) = parse_validators_from_extra_data(&fixed).unwrap();
+        // y = p + 1, a non-canonical encoding of the point y = 1
+        let mut non_canonical = [0xff; 32];
+        non_canonical[0] = 0xee;
+        non_canonical[31] = 0x7f;
+        validators[0].tendermint_pubkey = non_canonical.to_vec();
+
+        let rlp = extra_data(&[encode_validators_rlp(&validators)], 100);
+        let mut options = GenesisOptions {
+            layout: ExtraDataLayout::Rlp,
+            ..GenesisOptions::default()
+        };
+        assert!(validate_extra_data_with_options(&rlp, &options).is_valid());
+        options.strict_pubkeys = true;
+        assert_eq!(
+            validate_extra_data_with_options(&rlp, &options).issues,
+            vec![ExtraDataIssue::InvalidPubkey { index: 0 }]
+        );
+    }
+
+    #[test]
+    fn test_stake_layout_round_trip() {
+        let fixed = extra_data(&[record(1, 10), record(2, 20), record(3, 300)], 100);
+        let (validators, _) = parse_validators_from_extra_data(&fixed).unwrap();
+        let options = GenesisOptions {
+            layout: ExtraDataLayout::FixedStake,
+            ..GenesisOptions::default()
+        };
+
+        let records = encode_stake_records(&validators);
+        assert_eq!(records.len(), 3 * STAKE_RECORD_LEN);
+        let data = extra_data(&[records], 100);
+        let (decoded, epoch_length) =
+            parse_validators_from_extra_data_with_options(&data, &options).unwrap();
+        assert_eq!(decoded, validators);
+        assert_eq!(epoch_length, 100);
+        assert!(validate_extra_data_with_options(&data, &options).is_valid());
+
+        // Stakes scale like StakeHub's, rounding down
+        let mut stake_record = encode_stake_records(&validators[..1]);
+        stake_record[40..72]
+            .copy_from_slice(&U256::from(25 * VOTING_POWER_DIVISOR - 1).to_be_bytes::<32>());
+        let data = extra_data(&[stake_record.clone()], 100);
+        let (decoded, _) = parse_validators_from_extra_data_with_options(&data, &options).unwrap();
+        assert_eq!(decoded[0].voting_power, 24);
+
+        // A stake overflowing the voting power is rejected
+        stake_record[40..72].fill(0xff);
+        let data = extra_data(&[stake_record], 100);
+        assert!(parse_validators_from_extra_data_with_options(&data, &options).is_err());
+        assert!(validate_extra_data_with_options(&data, &options)
+            .issues
+            .contains(&ExtraDataIssue::VotingPowerOverflow { index: 0 }));
+    }
+
+    #[test]
+    fn test_rewrite_epoch_length_only_touches_epoch_bytes() {
+        let mut data = extra_data(&[record(1, 10), record(2, 20)], 100);
+        data[..EXTRA_VANITY_LEN].fill(0xaa);
+        let seal_start = data.len() - EXTRA_SEAL_LEN;
+        data[seal_start..].fill(0xbb);
+
+        let rewritten = rewrite_epoch_length(&data, 200).unwrap();
+        assert_eq!(rewritten.len(), data.len());
+        let epoch_length_start = seal_start - EPOCH_LENGTH_LEN;
+        let changed: Vec<usize> = (0..data.len())
+            .filter(|&i| data[i] != rewritten[i])
+            .collect();
+        assert!(changed
+            .iter()
+            .all(|i| (epoch_length_start..seal_start).contains(i)));
+
+        let (validators, epoch_length) = parse_validators_from_extra_data(&rewritten).unwrap();
+        assert_eq!(epoch_length, 200);
+        assert_eq!(
+            validators,
+            parse_validators_from_extra_data(&data).unwrap().0
+        );
+
+        assert!(rewrite_epoch_length(&data[1..], 200).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+//! CometBFT ABCI messages
+//! Hand-written prost equivalents of the `tendermint.abci` and `tendermint.crypto` messages the
+//! ABCI adapter returns, with the same field tags so they encode identically
+
+use crate::diff::ValidatorSetDiff;
+use malachitebft_eth_types::ValidatorSet;
+use prost::Message;
+
+/// `tendermint.crypto.PublicKey`, restricted to the `ed25519` case of its `sum` oneof
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct PublicKey {
+    #[prost(bytes = "vec", tag = "1")]
+    pub ed25519: Vec<u8>,
+}
+
+/// `tendermint.abci.ValidatorUpdate`. A power of 0 removes the validator.
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct ValidatorUpdate {
+    #[prost(message, optional, tag = "1")]
+    pub pub_key: Option<PublicKey>,
+    #[prost(int64, tag = "2")]
+    pub power: i64,
+}
+
+impl ValidatorUpdate {
+    /// Update of the validator with the Ed25519 `pubkey` to `power`, saturating at `i64::MAX`
+    pub fn new(pubkey: &[u8], power: u64) -> Self {
+        Self {
+            pub_key: Some(PublicKey {
+                ed25519: pubkey.to_vec(),
+            }),
+            power: i64::try_from(power).unwrap_or(i64::MAX),
+        }
+    }
+
+    /// Removal of the validator with the Ed25519 `pubkey`
+    pub fn removal(pubkey: &[u8]) -> Self {
+        Self::new(pubkey, 0)
+    }
+
+    /// Protobuf encoding, as returned in `EndBlock`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encode_to_vec()
+    }
+}
+
+/// Updates taking an ABCI app from the `old` validator set to the `new` one, with a zero-power
+/// update for every validator that left. See [`ValidatorSetDiff::to_abci_updates`] for the order.
+pub fn to_validator_updates(old: &ValidatorSet, new: &ValidatorSet) -> Vec<ValidatorUpdate> {
+    ValidatorSetDiff::between(old.iter().map(Into::into), new.iter().map(Into::into))
+        .to_abci_updates()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_consensus::SigningKey;
+    use malachitebft_eth_types::{PublicKey, Validator};
+
+    fn validator(seed: u8, power: u64) -> Validator {
+        let key = SigningKey::from([seed; 32]).verification_key();
+        Validator::new(PublicKey::new(key), power)
+    }
+
+    fn pubkey(seed: u8) -> Vec<u8> {
+        SigningKey::from([seed; 32])
+            .verification_key()
+            .as_bytes()
+            .to_vec()
+    }
+
+    #[test]
+    fn test_epoch_transition_updates() {
+        let old = ValidatorSet::new([validator(1, 10), validator(2, 20), validator(3, 30)]);
+        let new = ValidatorSet::new([validator(2, 25), validator(3, 30), validator(4, 40)]);
+
+        assert_eq!(
+            to_validator_updates(&old, &new),
+            vec![
+                ValidatorUpdate::removal(&pubkey(1)),
+                ValidatorUpdate::new(&pubkey(2), 25),
+                ValidatorUpdate::new(&pubkey(4), 40),
+            ]
+        );
+        assert_eq!(to_validator_updates(&new, &new), vec![]);
+        assert_eq!(
+            to_validator_updates(&new, &old).first(),
+            Some(&ValidatorUpdate::removal(&pubkey(4)))
+        );
+    }
+}
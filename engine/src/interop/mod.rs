@@ -0,0 +1,4 @@
+//! Interoperability with other consensus stacks
+//! Conversions of the engine's validator sets into the messages other stacks expect
+
+pub mod abci;
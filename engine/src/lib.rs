@@ -1,9 +1,24 @@
 pub mod auth;
+pub mod block_height;
+pub mod diff;
 pub mod engine;
 pub mod engine_rpc;
+pub mod epoch;
 pub mod ethereum_rpc;
 pub mod genesis;
+pub mod interop;
 pub mod json_structures;
+pub mod logs;
+pub mod merkle;
+pub mod metrics;
+pub mod network;
+pub mod proposer;
+pub mod pubkey;
+pub mod rate_limit;
+pub mod slash_indicator_client;
+pub mod snapshot;
 pub mod stake_hub_client;
 pub mod system_contracts;
+#[cfg(test)]
+mod test_utils;
 pub mod validator_executor;
@@ -0,0 +1,255 @@
+//! Event log queries
+//! Splits large `eth_getLogs` block ranges into windows that providers accept
+
+use crate::ethereum_rpc::{ContractCaller, Log, RpcError};
+use color_eyre::eyre::{eyre, Report, Result};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde_json::json;
+use tracing::debug;
+
+/// JSON-RPC error codes providers use to reject an `eth_getLogs` range as too large:
+/// `-32005` is the EIP-1474 "limit exceeded" (e.g. "query returned more than 10000 results"),
+/// `-32062` Ankr's "block range is too wide" and `-32614` QuickNode's range limit
+const RANGE_LIMIT_ERROR_CODES: &[i64] = &[-32005, -32062, -32614];
+
+/// Default number of blocks covered by a single `eth_getLogs` request
+pub const DEFAULT_LOG_CHUNK_SIZE: u64 = 5_000;
+
+/// Default number of `eth_getLogs` requests in flight at once
+pub const DEFAULT_LOG_CONCURRENCY: usize = 4;
+
+/// How a block range is split into `eth_getLogs` requests
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LogQueryOptions {
+    /// Blocks per request; ranges rejected by the provider are halved further
+    pub chunk_size: u64,
+    /// Maximum number of requests in flight
+    pub concurrency: usize,
+}
+
+impl Default for LogQueryOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_LOG_CHUNK_SIZE,
+            concurrency: DEFAULT_LOG_CONCURRENCY,
+        }
+    }
+}
+
+/// Log filter without its block range
+#[derive(Clone, Debug, Default)]
+pub struct LogFilter {
+    /// Contract emitting the events
+    pub address: String,
+    /// Accepted values of the first topic (the event signature); empty matches any event
+    pub event_signatures: Vec<String>,
+}
+
+impl LogFilter {
+    fn to_json(&self, from_block: u64, to_block: u64) -> serde_json::Value {
+        json!({
+            "address": self.address,
+            "fromBlock": format!("0x{:x}", from_block),
+            "toBlock": format!("0x{:x}", to_block),
+            "topics": [self.event_signatures],
+        })
+    }
+}
+
+/// Fetch all logs matching `filter` in `from_block..=to_block`.
+///
+/// The range is split into `chunk_size` windows fetched concurrently; results are returned in
+/// block order. A window the provider rejects with one of the range limit error codes is split in
+/// half and retried, down to single blocks.
+pub async fn get_logs_chunked(
+    caller: &dyn ContractCaller,
+    filter: &LogFilter,
+    from_block: u64,
+    to_block: u64,
+    options: LogQueryOptions,
+) -> Result<Vec<Log>> {
+    if from_block > to_block {
+        return Ok(Vec::new());
+    }
+
+    let chunk_size = options.chunk_size.max(1);
+    let windows = (from_block..=to_block)
+        .step_by(chunk_size as usize)
+        .map(|start| (start, start.saturating_add(chunk_size - 1).min(to_block)));
+
+    let chunks: Vec<Vec<Log>> = stream::iter(windows)
+        .map(|(start, end)| get_logs_splitting(caller, filter, start, end))
+        .buffered(options.concurrency.max(1))
+        .try_collect()
+        .await?;
+
+    Ok(chunks.into_iter().flatten().collect())
+}
+
+async fn get_logs_splitting(
+    caller: &dyn ContractCaller,
+    filter: &LogFilter,
+    from_block: u64,
+    to_block: u64,
+) -> Result<Vec<Log>> {
+    match caller.get_logs(filter.to_json(from_block, to_block)).await {
+        Ok(logs) => Ok(logs),
+        Err(e) if from_block < to_block && is_range_limit(&e) => {
+            let mid = from_block + (to_block - from_block) / 2;
+            debug!(
+                "eth_getLogs rejected blocks {}..={} ({}), splitting at {}",
+                from_block, to_block, e, mid
+            );
+            let mut logs = Box::pin(get_logs_splitting(caller, filter, from_block, mid)).await?;
+            logs.extend(Box::pin(get_logs_splitting(caller, filter, mid + 1, to_block)).await?);
+            Ok(logs)
+        }
+        Err(e) => Err(eyre!(
+            "eth_getLogs failed for blocks {}..={}: {}",
+            from_block,
+            to_block,
+            e
+        )),
+    }
+}
+
+/// Whether a provider error means the range should be narrowed
+fn is_range_limit(error: &Report) -> bool {
+    error
+        .downcast_ref::<RpcError>()
+        .is_some_and(|error| RANGE_LIMIT_ERROR_CODES.contains(&error.code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// Provider emitting one log per block and rejecting ranges wider than `max_range` blocks
+    struct RangeLimitedProvider {
+        max_range: u64,
+        requests: Mutex<Vec<(u64, u64)>>,
+    }
+
+    fn parse_block(value: &serde_json::Value) -> u64 {
+        u64::from_str_radix(value.as_str().unwrap().trim_start_matches("0x"), 16).unwrap()
+    }
+
+    fn log_at(block: u64) -> Log {
+        Log {
+            address: "0x0000000000000000000000000000000000002002".to_string(),
+            topics: vec![],
+            data: "0x".to_string(),
+            block_number: format!("0x{:x}", block),
+            transaction_hash: format!("0x{:064x}", block),
+            transaction_index: "0x0".to_string(),
+            block_hash: format!("0x{:064x}", block),
+            log_index: "0x0".to_string(),
+            removed: false,
+        }
+    }
+
+    #[async_trait]
+    impl ContractCaller for RangeLimitedProvider {
+        async fn eth_call(&self, _to: &str, _data: &[u8]) -> Result<Vec<u8>> {
+            Err(eyre!("eth_call not supported"))
+        }
+
+        async fn get_logs(&self, filter: serde_json::Value) -> Result<Vec<Log>> {
+            let from = parse_block(&filter["fromBlock"]);
+            let to = parse_block(&filter["toBlock"]);
+            self.requests.lock().unwrap().push((from, to));
+            if to - from + 1 > self.max_range {
+                return Err(RpcError {
+                    code: -32005,
+                    message: "query returned more than 10000 results".to_string(),
+                }
+                .into());
+            }
+            Ok((from..=to).map(log_at).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejected_ranges_are_split_until_accepted() {
+        let provider = RangeLimitedProvider {
+            max_range: 3,
+            requests: Mutex::new(Vec::new()),
+        };
+        let options = LogQueryOptions {
+            chunk_size: 10,
+            concurrency: 2,
+        };
+
+        let logs = get_logs_chunked(&provider, &LogFilter::default(), 5, 34, options)
+            .await
+            .unwrap();
+
+        let blocks: Vec<String> = logs.into_iter().map(|log| log.block_number).collect();
+        let expected: Vec<String> = (5..=34).map(|b| format!("0x{:x}", b)).collect();
+        assert_eq!(blocks, expected);
+
+        // Each window of 10 is halved into 5s, which are split into 3 and 2
+        let mut requests = provider.requests.into_inner().unwrap();
+        requests.sort();
+        let mut expected: Vec<_> = [5, 15, 25]
+            .into_iter()
+            .flat_map(|start| {
+                [(0, 9), (0, 4), (0, 2), (3, 4), (5, 9), (5, 7), (8, 9)]
+                    .map(|(from, to)| (start + from, start + to))
+            })
+            .collect();
+        expected.sort();
+        assert_eq!(requests, expected);
+    }
+
+    #[tokio::test]
+    async fn test_other_errors_are_not_retried() {
+        struct FailingProvider {
+            error: fn() -> Report,
+            requests: AtomicUsize,
+        }
+
+        #[async_trait]
+        impl ContractCaller for FailingProvider {
+            async fn eth_call(&self, _to: &str, _data: &[u8]) -> Result<Vec<u8>> {
+                Err(eyre!("eth_call not supported"))
+            }
+
+            async fn get_logs(&self, _filter: serde_json::Value) -> Result<Vec<Log>> {
+                self.requests.fetch_add(1, Ordering::SeqCst);
+                Err((self.error)())
+            }
+        }
+
+        // Messages that merely mention a range don't make it a range limit
+        let errors: [fn() -> Report; 2] = [
+            || eyre!("connection refused"),
+            || {
+                RpcError {
+                    code: -32602,
+                    message: "invalid block range params".to_string(),
+                }
+                .into()
+            },
+        ];
+        for error in errors {
+            let provider = FailingProvider {
+                error,
+                requests: AtomicUsize::new(0),
+            };
+            let result = get_logs_chunked(
+                &provider,
+                &LogFilter::default(),
+                0,
+                100,
+                LogQueryOptions::default(),
+            )
+            .await;
+            assert!(result.is_err());
+            assert_eq!(provider.requests.load(Ordering::SeqCst), 1);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Merkle commitments over validator sets
+//! Leaves are hashed as keccak256(0x00 || data) and inner nodes as keccak256(0x01 || left || right),
+//! so a leaf can never be passed off as an inner node. The leaf level is padded with zero hashes
+//! up to the next power of two, which makes every proof exactly log2(width) siblings long.
+
+use alloy_primitives::{keccak256, B256};
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Inclusion proof for a single leaf
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MerkleProof {
+    /// Position of the leaf in the (unpadded) leaf list
+    pub index: usize,
+    /// Sibling hashes from the leaf level up to the root
+    pub siblings: Vec<B256>,
+}
+
+/// Hash raw leaf data with the leaf domain prefix
+pub fn hash_leaf(data: &[u8]) -> B256 {
+    let mut buf = Vec::with_capacity(1 + data.len());
+    buf.push(LEAF_PREFIX);
+    buf.extend_from_slice(data);
+    keccak256(buf)
+}
+
+/// Hash two child nodes with the inner-node domain prefix
+pub fn hash_node(left: &B256, right: &B256) -> B256 {
+    let mut buf = [0u8; 65];
+    buf[0] = NODE_PREFIX;
+    buf[1..33].copy_from_slice(left.as_slice());
+    buf[33..].copy_from_slice(right.as_slice());
+    keccak256(buf)
+}
+
+/// Compute the root over already hashed leaves. The root of an empty list is `B256::ZERO`.
+pub fn merkle_root(leaves: &[B256]) -> B256 {
+    if leaves.is_empty() {
+        return B256::ZERO;
+    }
+
+    let mut level = padded(leaves);
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| hash_node(&pair[0], &pair[1]))
+            .collect();
+    }
+    level[0]
+}
+
+/// Build the inclusion proof for the leaf at `index`, or `None` if out of range
+pub fn merkle_proof(leaves: &[B256], index: usize) -> Option<MerkleProof> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut siblings = Vec::new();
+    let mut level = padded(leaves);
+    let mut position = index;
+    while level.len() > 1 {
+        siblings.push(level[position ^ 1]);
+        level = level
+            .chunks(2)
+            .map(|pair| hash_node(&pair[0], &pair[1]))
+            .collect();
+        position /= 2;
+    }
+
+    Some(MerkleProof { index, siblings })
+}
+
+/// Verify that `leaf` sits at `proof.index` under `root`
+pub fn verify_inclusion_proof(root: B256, leaf: B256, proof: &MerkleProof) -> bool {
+    let mut hash = leaf;
+    let mut position = proof.index;
+    for sibling in &proof.siblings {
+        hash = if position & 1 == 0 {
+            hash_node(&hash, sibling)
+        } else {
+            hash_node(sibling, &hash)
+        };
+        position /= 2;
+    }
+
+    // Any index bits left over mean the proof is too short for the claimed position
+    position == 0 && hash == root
+}
+
+fn padded(leaves: &[B256]) -> Vec<B256> {
+    let mut level = leaves.to_vec();
+    level.resize(leaves.len().next_power_of_two(), B256::ZERO);
+    level
+}
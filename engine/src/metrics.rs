@@ -0,0 +1,96 @@
+//! Validator set KPIs
+//! Decentralization measures over voting powers, exported as one JSON snapshot
+
+use serde::{Deserialize, Serialize};
+
+/// Point-in-time KPIs of the cached validator set, see
+/// [`ValidatorExecutor::metrics_snapshot`](crate::validator_executor::ValidatorExecutor::metrics_snapshot)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    /// Epoch the set was last refreshed for, if it was refreshed at all
+    pub epoch: Option<u64>,
+    pub validator_count: usize,
+    /// See [`total_voting_power`]
+    pub total_voting_power: u128,
+    /// See [`nakamoto_coefficient`]
+    pub nakamoto_coefficient: usize,
+    /// See [`gini_coefficient`]
+    pub gini_coefficient: f64,
+    /// Validators added or removed by the last change of the set
+    pub churn: usize,
+}
+
+/// Sum of the voting powers, in `u128` so it can't overflow
+pub fn total_voting_power(voting_powers: &[u64]) -> u128 {
+    voting_powers.iter().map(|&p| u128::from(p)).sum()
+}
+
+/// Fewest validators holding more than a third of the voting power, enough to halt consensus.
+/// Zero without voting power.
+pub fn nakamoto_coefficient(voting_powers: &[u64]) -> usize {
+    let total = total_voting_power(voting_powers);
+    let mut sorted = voting_powers.to_vec();
+    sorted.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut held = 0u128;
+    for (count, power) in sorted.into_iter().enumerate() {
+        held += u128::from(power);
+        if held * 3 > total {
+            return count + 1;
+        }
+    }
+    0
+}
+
+/// Gini coefficient of the voting powers: 0 when all are equal, approaching 1 as a single
+/// validator holds everything. Zero without voting power.
+pub fn gini_coefficient(voting_powers: &[u64]) -> f64 {
+    let total: f64 = voting_powers.iter().map(|&p| p as f64).sum();
+    if total == 0.0 {
+        return 0.0;
+    }
+    let mut sorted = voting_powers.to_vec();
+    sorted.sort_unstable();
+
+    let n = sorted.len() as f64;
+    let weighted: f64 = sorted
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| (i + 1) as f64 * p as f64)
+        .sum();
+    2.0 * weighted / (n * total) - (n + 1.0) / n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_total_voting_power_does_not_overflow() {
+        assert_eq!(total_voting_power(&[10, 20]), 30);
+        assert_eq!(
+            total_voting_power(&[u64::MAX, u64::MAX]),
+            2 * u128::from(u64::MAX)
+        );
+        assert_eq!(total_voting_power(&[]), 0);
+    }
+
+    #[test]
+    fn test_nakamoto_coefficient() {
+        assert_eq!(nakamoto_coefficient(&[10, 20, 30, 40]), 1);
+        assert_eq!(nakamoto_coefficient(&[10, 10, 10, 10]), 2);
+        // Exactly a third isn't more than a third
+        assert_eq!(nakamoto_coefficient(&[10, 10, 10]), 2);
+        assert_eq!(nakamoto_coefficient(&[5]), 1);
+        assert_eq!(nakamoto_coefficient(&[]), 0);
+        assert_eq!(nakamoto_coefficient(&[0, 0]), 0);
+    }
+
+    #[test]
+    fn test_gini_coefficient() {
+        assert_eq!(gini_coefficient(&[10, 10, 10, 10]), 0.0);
+        assert!((gini_coefficient(&[40, 10, 30, 20]) - 0.25).abs() < 1e-12);
+        assert!((gini_coefficient(&[0, 0, 0, 100]) - 0.75).abs() < 1e-12);
+        assert_eq!(gini_coefficient(&[]), 0.0);
+    }
+}
@@ -0,0 +1,100 @@
+//! Network registry
+//! Maps known networks to their chain ids and system contract addresses
+
+use crate::stake_hub_client::ElectionInfoLayout;
+use crate::system_contracts::STAKE_HUB_CONTRACT;
+use alloy_primitives::Address;
+use std::fmt;
+
+/// Chain id of LazAI mainnet
+pub const MAINNET_CHAIN_ID: u64 = 52924;
+/// Chain id of LazAI testnet
+pub const TESTNET_CHAIN_ID: u64 = 133718;
+/// Chain id of the local devnet (see `assets/genesis.json`)
+pub const DEVNET_CHAIN_ID: u64 = 714;
+
+/// Network the node is running on
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+    Devnet,
+    /// Any other chain, identified by its chain id
+    Custom(u64),
+}
+
+impl Network {
+    /// Resolve a chain id to a known network, or [`Network::Custom`] otherwise
+    pub fn from_chain_id(chain_id: u64) -> Self {
+        match chain_id {
+            MAINNET_CHAIN_ID => Network::Mainnet,
+            TESTNET_CHAIN_ID => Network::Testnet,
+            DEVNET_CHAIN_ID => Network::Devnet,
+            other => Network::Custom(other),
+        }
+    }
+
+    /// Chain id of the network
+    pub fn chain_id(&self) -> u64 {
+        match self {
+            Network::Mainnet => MAINNET_CHAIN_ID,
+            Network::Testnet => TESTNET_CHAIN_ID,
+            Network::Devnet => DEVNET_CHAIN_ID,
+            Network::Custom(chain_id) => *chain_id,
+        }
+    }
+
+    /// Address of the StakeHub system contract.
+    /// System contracts are predeployed at fixed addresses by the genesis, so custom
+    /// networks built from the same genesis layout share them.
+    pub fn stake_hub_address(&self) -> Address {
+        STAKE_HUB_CONTRACT.parse().unwrap()
+    }
+
+    /// `getValidatorElectionInfo` layout of the StakeHub deployed on the network.
+    /// Known networks run the embedded ABI; custom ones are detected from the ABI in use.
+    pub fn election_info_layout(&self) -> ElectionInfoLayout {
+        match self {
+            Network::Mainnet | Network::Testnet | Network::Devnet => {
+                ElectionInfoLayout::ParallelArrays
+            }
+            Network::Custom(_) => ElectionInfoLayout::Auto,
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Network::Mainnet => write!(f, "mainnet"),
+            Network::Testnet => write!(f, "testnet"),
+            Network::Devnet => write!(f, "devnet"),
+            Network::Custom(chain_id) => write!(f, "custom({})", chain_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_networks_resolve() {
+        let stake_hub: Address = "0x0000000000000000000000000000000000002002"
+            .parse()
+            .unwrap();
+
+        for network in [Network::Mainnet, Network::Testnet, Network::Devnet] {
+            assert_eq!(Network::from_chain_id(network.chain_id()), network);
+            assert_eq!(network.stake_hub_address(), stake_hub);
+        }
+        assert_eq!(Network::from_chain_id(714), Network::Devnet);
+    }
+
+    #[test]
+    fn test_unknown_chain_id_is_custom() {
+        let network = Network::from_chain_id(31337);
+        assert_eq!(network, Network::Custom(31337));
+        assert_eq!(network.chain_id(), 31337);
+    }
+}
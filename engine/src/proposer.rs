@@ -0,0 +1,88 @@
+//! Block proposer recovery
+//! Maps a sealed block header back to the validator that signed it
+
+use alloy_primitives::{keccak256, Address, B256};
+use color_eyre::eyre::{eyre, Result};
+use ed25519_consensus::{Signature, VerificationKey};
+use malachitebft_eth_types::ValidatorSet;
+
+/// Length of the Ed25519 seal appended to a header
+pub const SEAL_LENGTH: usize = 64;
+
+/// Hash signed by the proposer: keccak256 of the header without its seal
+pub fn seal_hash(unsealed_header: &[u8]) -> B256 {
+    keccak256(unsealed_header)
+}
+
+/// Recover the consensus address of the validator that sealed `header_bytes`.
+///
+/// The header is expected to end with a [`SEAL_LENGTH`]-byte Ed25519 signature over
+/// [`seal_hash`] of the preceding bytes. Ed25519 signatures don't allow public key recovery,
+/// so the seal is checked against every key in `set`.
+pub fn recover_proposer(header_bytes: &[u8], set: &ValidatorSet) -> Result<Address> {
+    if header_bytes.len() < SEAL_LENGTH {
+        return Err(eyre!(
+            "Header of {} bytes is too short to contain a seal",
+            header_bytes.len()
+        ));
+    }
+
+    let (unsealed, seal) = header_bytes.split_at(header_bytes.len() - SEAL_LENGTH);
+    let signature = Signature::from(<[u8; SEAL_LENGTH]>::try_from(seal)?);
+    let message = seal_hash(unsealed);
+
+    set.iter()
+        .find(|validator| {
+            VerificationKey::try_from(*validator.public_key.as_bytes())
+                .is_ok_and(|key| key.verify(&signature, message.as_slice()).is_ok())
+        })
+        .map(|validator| validator.consensus_address.to_alloy_address())
+        .ok_or_else(|| eyre!("Header seal does not match any validator in the set"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_consensus::SigningKey;
+    use malachitebft_eth_types::{PublicKey, Validator};
+
+    fn validator_set(keys: &[SigningKey]) -> ValidatorSet {
+        ValidatorSet::new(
+            keys.iter()
+                .map(|key| Validator::new(PublicKey::new(key.verification_key()), 1)),
+        )
+    }
+
+    fn seal(header: &[u8], key: &SigningKey) -> Vec<u8> {
+        let signature = key.sign(seal_hash(header).as_slice());
+        let mut sealed = header.to_vec();
+        sealed.extend_from_slice(&signature.to_bytes());
+        sealed
+    }
+
+    #[test]
+    fn test_recover_proposer_of_signed_header() {
+        let keys: Vec<_> = (1..=4).map(|i| SigningKey::from([i; 32])).collect();
+        let set = validator_set(&keys);
+        let header = seal(b"header of block 42", &keys[2]);
+
+        let proposer = recover_proposer(&header, &set).unwrap();
+
+        let expected = set
+            .get_by_public_key(&PublicKey::new(keys[2].verification_key()))
+            .unwrap();
+        assert_eq!(proposer, expected.consensus_address.to_alloy_address());
+        assert!(set
+            .iter()
+            .any(|v| v.consensus_address.to_alloy_address() == proposer));
+    }
+
+    #[test]
+    fn test_recover_proposer_rejects_unknown_signer() {
+        let set = validator_set(&[SigningKey::from([1; 32])]);
+        let header = seal(b"header of block 42", &SigningKey::from([9; 32]));
+
+        assert!(recover_proposer(&header, &set).is_err());
+        assert!(recover_proposer(&[0u8; 10], &set).is_err());
+    }
+}
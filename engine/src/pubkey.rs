@@ -0,0 +1,113 @@
+//! Tendermint pubkey conversion
+//! Genesis stores pubkeys as fixed 32-byte fields while StakeHub returns dynamic `bytes`; both
+//! become a consensus [`PublicKey`] through [`to_public_key`]
+
+use alloy_primitives::U256;
+use malachitebft_eth_types::PublicKey;
+use thiserror::Error;
+
+/// Length of an Ed25519 public key
+pub const PUBLIC_KEY_LEN: usize = 32;
+
+/// Why bytes aren't a usable tendermint pubkey
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Error)]
+pub enum PublicKeyError {
+    #[error("expected a {PUBLIC_KEY_LEN}-byte Ed25519 public key, got {0} bytes")]
+    InvalidLength(usize),
+    #[error("not a valid Ed25519 public key")]
+    InvalidPoint,
+    #[error("not the canonical encoding of an Ed25519 public key")]
+    NonCanonical,
+}
+
+/// Validate `bytes` as an Ed25519 public key and convert it to a consensus [`PublicKey`].
+/// Points are decoded under ZIP-215 rules like signatures are verified, so non-canonical
+/// encodings of a point are accepted.
+pub fn to_public_key(bytes: &[u8]) -> Result<PublicKey, PublicKeyError> {
+    to_public_key_with(bytes, false)
+}
+
+/// Same as [`to_public_key`], also rejecting non-canonical encodings when `strict`, so every key
+/// has a single byte representation
+pub fn to_public_key_with(bytes: &[u8], strict: bool) -> Result<PublicKey, PublicKeyError> {
+    let bytes: [u8; PUBLIC_KEY_LEN] = bytes
+        .try_into()
+        .map_err(|_| PublicKeyError::InvalidLength(bytes.len()))?;
+    if strict && !is_canonical(&bytes) {
+        return Err(PublicKeyError::NonCanonical);
+    }
+    let key = ed25519_consensus::VerificationKey::try_from(bytes)
+        .map_err(|_| PublicKeyError::InvalidPoint)?;
+    Ok(PublicKey::new(key))
+}
+
+/// Whether `bytes` is the canonical encoding of its point: a `y` coordinate reduced modulo the
+/// field prime, and no sign bit on `x = 0`, which only occurs at `y = ±1`
+fn is_canonical(bytes: &[u8; PUBLIC_KEY_LEN]) -> bool {
+    let prime = (U256::MAX >> 1) - U256::from(18);
+    let mut y = *bytes;
+    y[PUBLIC_KEY_LEN - 1] &= 0x7f;
+    let y = U256::from_le_bytes(y);
+    let negative_x = bytes[PUBLIC_KEY_LEN - 1] & 0x80 != 0;
+    y < prime && !(negative_x && (y == U256::from(1) || y == prime - U256::from(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_public_key() {
+        let key = ed25519_consensus::SigningKey::from([1; 32]).verification_key();
+        assert_eq!(
+            to_public_key(key.as_bytes()).unwrap().as_bytes(),
+            key.as_bytes()
+        );
+
+        assert_eq!(
+            to_public_key(&key.as_bytes()[..31]),
+            Err(PublicKeyError::InvalidLength(31))
+        );
+        let mut long = key.as_bytes().to_vec();
+        long.push(0);
+        assert_eq!(to_public_key(&long), Err(PublicKeyError::InvalidLength(33)));
+        assert_eq!(to_public_key(&[]), Err(PublicKeyError::InvalidLength(0)));
+
+        let mut invalid = [0; 32];
+        invalid[0] = 2;
+        assert_eq!(to_public_key(&invalid), Err(PublicKeyError::InvalidPoint));
+    }
+
+    #[test]
+    fn test_strict_validation() {
+        let key = ed25519_consensus::SigningKey::from([1; 32]).verification_key();
+        assert_eq!(
+            to_public_key_with(key.as_bytes(), true).unwrap().as_bytes(),
+            key.as_bytes()
+        );
+
+        // Off-curve values fail either way
+        let mut off_curve = [0; 32];
+        off_curve[0] = 2;
+        assert_eq!(
+            to_public_key_with(&off_curve, true),
+            Err(PublicKeyError::InvalidPoint)
+        );
+
+        // y = p + 1 encodes the same point as y = 1
+        let mut unreduced = [0xff; 32];
+        unreduced[0] = 0xee;
+        unreduced[31] = 0x7f;
+        // y = 1 with the sign bit set, although x = 0 has no negative
+        let mut negative_zero = [0; 32];
+        negative_zero[0] = 1;
+        negative_zero[31] = 0x80;
+        for non_canonical in [unreduced, negative_zero] {
+            assert!(to_public_key(&non_canonical).is_ok());
+            assert_eq!(
+                to_public_key_with(&non_canonical, true),
+                Err(PublicKeyError::NonCanonical)
+            );
+        }
+    }
+}
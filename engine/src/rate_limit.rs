@@ -0,0 +1,97 @@
+//! Rate limiting of outgoing RPC requests
+//! A token bucket that makes callers wait for their turn instead of failing
+
+use color_eyre::eyre::{eyre, Result};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Token bucket allowing `burst` requests at once, refilled at `rate_per_sec` tokens per second
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate_per_sec: f64,
+    burst: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    /// Available tokens; negative when callers are queued for future tokens
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// Create a limiter starting with a full bucket
+    pub fn new(rate_per_sec: f64, burst: u32) -> Result<Self> {
+        if !rate_per_sec.is_finite() || rate_per_sec <= 0.0 {
+            return Err(eyre!("Rate limit must be positive, got {}", rate_per_sec));
+        }
+        if burst == 0 {
+            return Err(eyre!("Rate limit burst must be at least 1"));
+        }
+
+        Ok(Self {
+            rate_per_sec,
+            burst: burst as f64,
+            state: Mutex::new(BucketState {
+                tokens: burst as f64,
+                updated: Instant::now(),
+            }),
+        })
+    }
+
+    /// Wait until a request may be sent.
+    /// Each caller reserves its token before sleeping, so waiters are served in arrival order.
+    pub async fn acquire(&self) {
+        let wait = {
+            let mut state = self.state.lock().await;
+            let now = Instant::now();
+            let elapsed = now.duration_since(state.updated).as_secs_f64();
+            state.tokens = (state.tokens + elapsed * self.rate_per_sec).min(self.burst);
+            state.updated = now;
+            state.tokens -= 1.0;
+
+            if state.tokens >= 0.0 {
+                Duration::ZERO
+            } else {
+                Duration::from_secs_f64(-state.tokens / self.rate_per_sec)
+            }
+        };
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_requests_beyond_burst_are_spaced_out() {
+        tokio::time::pause();
+        let limiter = RateLimiter::new(20.0, 2).unwrap();
+        let start = Instant::now();
+
+        // The burst goes through immediately
+        limiter.acquire().await;
+        limiter.acquire().await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // The next three wait 50ms each
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(149) && elapsed <= Duration::from_millis(151));
+    }
+
+    #[test]
+    fn test_invalid_rate_is_rejected() {
+        assert!(RateLimiter::new(0.0, 1).is_err());
+        assert!(RateLimiter::new(f64::NAN, 1).is_err());
+        assert!(RateLimiter::new(1.0, 0).is_err());
+    }
+}
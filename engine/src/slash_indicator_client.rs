@@ -0,0 +1,204 @@
+//! Slash history
+//! Slashes are decided by the SlashIndicator system contract and applied by StakeHub, whose
+//! `ValidatorSlashed` event records the penalised validator, the amount and the reason
+
+use crate::ethereum_rpc::{ContractCaller, Log};
+use crate::logs::{get_logs_chunked, LogFilter, LogQueryOptions};
+use crate::stake_hub_client::load_stake_hub_abi;
+use alloy_dyn_abi::{DecodedEvent, DynSolValue, EventExt};
+use alloy_json_abi::Event;
+use alloy_primitives::{Address, B256, U256};
+use color_eyre::eyre::{eyre, Result};
+use std::sync::Arc;
+
+/// Why a validator was slashed, as reported by the `slashType` of `ValidatorSlashed`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SlashReason {
+    DoubleSign,
+    Downtime,
+    MaliciousVote,
+    /// A slash type this client doesn't know about
+    Unknown(u8),
+}
+
+impl From<u8> for SlashReason {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => SlashReason::DoubleSign,
+            1 => SlashReason::Downtime,
+            2 => SlashReason::MaliciousVote,
+            other => SlashReason::Unknown(other),
+        }
+    }
+}
+
+/// One slash of a validator
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SlashEvent {
+    /// Operator address of the slashed validator
+    pub validator: Address,
+    pub block_number: u64,
+    pub reason: SlashReason,
+    /// Stake taken from the validator
+    pub amount: U256,
+    /// Timestamp until which the validator is jailed
+    pub jail_until: U256,
+}
+
+/// Reads slash history from the StakeHub event logs
+pub struct SlashIndicatorClient {
+    eth_rpc: Arc<dyn ContractCaller>,
+    stake_hub_address: Address,
+    slashed_event: Event,
+    log_query_options: LogQueryOptions,
+}
+
+impl SlashIndicatorClient {
+    /// Read the slashes applied by the StakeHub at `stake_hub_address`
+    pub fn new(eth_rpc: Arc<dyn ContractCaller>, stake_hub_address: Address) -> Result<Self> {
+        let slashed_event = load_stake_hub_abi()?
+            .event("ValidatorSlashed")
+            .and_then(|events| events.first())
+            .cloned()
+            .ok_or_else(|| eyre!("Event ValidatorSlashed not found in StakeHub ABI"))?;
+
+        Ok(Self {
+            eth_rpc,
+            stake_hub_address,
+            slashed_event,
+            log_query_options: LogQueryOptions::default(),
+        })
+    }
+
+    /// Set how event queries are split into `eth_getLogs` requests
+    pub fn with_log_query_options(mut self, options: LogQueryOptions) -> Self {
+        self.log_query_options = options;
+        self
+    }
+
+    /// Slashes in `from_block..=to_block`, in block order
+    pub async fn get_slash_events(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<SlashEvent>> {
+        let filter = LogFilter {
+            address: self.stake_hub_address.to_string(),
+            event_signatures: vec![self.slashed_event.selector().to_string()],
+        };
+        let logs = get_logs_chunked(
+            self.eth_rpc.as_ref(),
+            &filter,
+            from_block,
+            to_block,
+            self.log_query_options,
+        )
+        .await?;
+
+        logs.iter()
+            .map(|log| self.decode_slash_event(log))
+            .collect()
+    }
+
+    fn decode_slash_event(&self, log: &Log) -> Result<SlashEvent> {
+        let topics = log
+            .topics
+            .iter()
+            .map(|topic| topic.parse::<B256>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let data = hex::decode(log.data.trim_start_matches("0x"))?;
+        let DecodedEvent { indexed, body, .. } =
+            self.slashed_event.decode_log_parts(topics, &data, true)?;
+
+        let invalid = || {
+            eyre!(
+                "Invalid ValidatorSlashed log in tx {}",
+                log.transaction_hash
+            )
+        };
+        let uint = |value: Option<&DynSolValue>| {
+            value
+                .and_then(DynSolValue::as_uint)
+                .map(|(value, _)| value)
+                .ok_or_else(invalid)
+        };
+        let validator = indexed
+            .first()
+            .and_then(DynSolValue::as_address)
+            .ok_or_else(invalid)?;
+        let slash_type: u8 = uint(body.get(2))?.try_into().map_err(|_| invalid())?;
+
+        Ok(SlashEvent {
+            validator,
+            block_number: u64::from_str_radix(log.block_number.trim_start_matches("0x"), 16)?,
+            reason: SlashReason::from(slash_type),
+            amount: uint(body.get(1))?,
+            jail_until: uint(body.first())?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockCaller;
+
+    fn slash_log(event: &Event, operator: u8, block: u64, amount: u64, slash_type: u8) -> Log {
+        let data = DynSolValue::Tuple(vec![
+            DynSolValue::from(U256::from(block * 10)),
+            DynSolValue::from(U256::from(amount)),
+            DynSolValue::Uint(U256::from(slash_type), 8),
+        ])
+        .abi_encode_params();
+        Log {
+            address: "0x0000000000000000000000000000000000002002".to_string(),
+            topics: vec![
+                event.selector().to_string(),
+                B256::left_padding_from(Address::repeat_byte(operator).as_slice()).to_string(),
+            ],
+            data: format!("0x{}", hex::encode(data)),
+            block_number: format!("0x{:x}", block),
+            transaction_hash: format!("0x{:064x}", block),
+            transaction_index: "0x0".to_string(),
+            block_hash: format!("0x{:064x}", block),
+            log_index: "0x0".to_string(),
+            removed: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slash_events_decode_in_order() {
+        let caller = Arc::new(MockCaller::new());
+        let client = SlashIndicatorClient::new(caller.clone(), Address::ZERO).unwrap();
+        let event = &client.slashed_event;
+        caller.set_logs(vec![
+            slash_log(event, 0x81, 10, 100, 1),
+            slash_log(event, 0x82, 20, 500, 0),
+            slash_log(event, 0x81, 30, 100, 7),
+        ]);
+
+        let events = client.get_slash_events(0, 25).await.unwrap();
+        assert_eq!(
+            events,
+            vec![
+                SlashEvent {
+                    validator: Address::repeat_byte(0x81),
+                    block_number: 10,
+                    reason: SlashReason::Downtime,
+                    amount: U256::from(100),
+                    jail_until: U256::from(100),
+                },
+                SlashEvent {
+                    validator: Address::repeat_byte(0x82),
+                    block_number: 20,
+                    reason: SlashReason::DoubleSign,
+                    amount: U256::from(500),
+                    jail_until: U256::from(200),
+                },
+            ]
+        );
+
+        let events = client.get_slash_events(30, 30).await.unwrap();
+        assert_eq!(events[0].reason, SlashReason::Unknown(7));
+    }
+}
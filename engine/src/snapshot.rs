@@ -0,0 +1,217 @@
+//! Validator set snapshots
+//! Serializable copy of an elected validator set, stored as JSON or CBOR
+
+use crate::stake_hub_client::ElectedValidators;
+use alloy_primitives::{Address, Bytes, U256};
+use color_eyre::eyre::{eyre, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Format version written into every [`ValidatorSnapshot`]. Bump on any incompatible change to
+/// the serialized layout.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// One validator of a [`ValidatorSnapshot`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotValidator {
+    pub consensus_address: Address,
+    pub operator_address: Address,
+    pub voting_power: u64,
+    pub raw_stake: U256,
+    pub tendermint_pub_key: Bytes,
+}
+
+/// Validator set elected for `epoch` from the state at `block_number`.
+///
+/// In JSON, addresses, stakes and pubkeys are hex strings. In CBOR they are byte strings
+/// (stakes big-endian without leading zeros), which keeps snapshots compact and readable by any
+/// CBOR library.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorSnapshot {
+    /// Format version, [`SNAPSHOT_VERSION`] for snapshots written by this crate. Snapshots from
+    /// before versioning have the same layout and decode as version 1.
+    #[serde(default = "legacy_version")]
+    pub version: u32,
+    pub epoch: u64,
+    pub block_number: u64,
+    /// In election order
+    pub validators: Vec<SnapshotValidator>,
+}
+
+impl ValidatorSnapshot {
+    /// Snapshot `elected` as the set of `epoch`, read at `block_number`
+    pub fn new(epoch: u64, block_number: u64, elected: &ElectedValidators) -> Self {
+        let validators = elected
+            .iter()
+            .enumerate()
+            .map(|(i, v)| SnapshotValidator {
+                consensus_address: v.consensus_address,
+                operator_address: v.operator_address,
+                voting_power: v.voting_power,
+                raw_stake: elected.raw_stakes.get(i).copied().unwrap_or_default(),
+                tendermint_pub_key: v.tendermint_pub_key.into(),
+            })
+            .collect();
+
+        Self {
+            version: SNAPSHOT_VERSION,
+            epoch,
+            block_number,
+            validators,
+        }
+    }
+
+    /// The snapshotted validators, in election order
+    pub fn elected_validators(&self) -> ElectedValidators {
+        let mut elected = ElectedValidators::default();
+        for v in &self.validators {
+            elected.consensus_addrs.push(v.consensus_address);
+            elected.operator_addrs.push(v.operator_address);
+            elected.voting_powers.push(v.voting_power);
+            elected.raw_stakes.push(v.raw_stake);
+            elected
+                .tendermint_pub_keys
+                .push(v.tendermint_pub_key.to_vec());
+        }
+        elected
+    }
+
+    /// Encode as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Decode a snapshot written by [`Self::to_json`], rejecting unknown format versions
+    pub fn from_json(json: &str) -> Result<Self> {
+        check_version(serde_json::from_str(json)?)?;
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Encode as CBOR
+    pub fn to_cbor(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(self, &mut bytes)
+            .map_err(|e| eyre!("Failed to encode validator snapshot as CBOR: {}", e))?;
+        Ok(bytes)
+    }
+
+    /// Decode a snapshot written by [`Self::to_cbor`], rejecting unknown format versions
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self> {
+        fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+            ciborium::from_reader(bytes)
+                .map_err(|e| eyre!("Failed to decode CBOR validator snapshot: {}", e))
+        }
+        check_version(decode(bytes)?)?;
+        decode(bytes)
+    }
+}
+
+/// Just the version of a serialized snapshot, read before the rest so a newer layout fails with
+/// a version error rather than whatever its fields happen to decode as
+#[derive(Deserialize)]
+struct SnapshotHeader {
+    #[serde(default = "legacy_version")]
+    version: u32,
+}
+
+fn legacy_version() -> u32 {
+    1
+}
+
+fn check_version(header: SnapshotHeader) -> Result<()> {
+    if header.version != SNAPSHOT_VERSION {
+        return Err(eyre!(
+            "Unsupported validator snapshot version {} (this build reads version {})",
+            header.version,
+            SNAPSHOT_VERSION
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stake_hub_client::{get_top_validators_by_voting_power, ElectionConfig};
+    use crate::test_utils::validator_candidate;
+    use ciborium::Value;
+
+    fn snapshot() -> ValidatorSnapshot {
+        let candidates = (1..=3)
+            .map(|i| validator_candidate(i, 10 * i as u64))
+            .collect();
+        let elected = get_top_validators_by_voting_power(
+            candidates,
+            U256::from(3),
+            &ElectionConfig::default(),
+        );
+        ValidatorSnapshot::new(7, 699, &elected)
+    }
+
+    #[test]
+    fn test_cbor_round_trip() {
+        let snapshot = snapshot();
+
+        let cbor = snapshot.to_cbor().unwrap();
+        let decoded = ValidatorSnapshot::from_cbor(&cbor).unwrap();
+        assert_eq!(decoded, snapshot);
+        assert_eq!(
+            decoded.elected_validators().raw_stakes,
+            snapshot.elected_validators().raw_stakes
+        );
+
+        // Addresses and stakes are byte strings, not hex text
+        let value: Value = ciborium::from_reader(cbor.as_slice()).unwrap();
+        let validator = &value.as_map().unwrap()[3].1.as_array().unwrap()[0];
+        let field = |name: &str| {
+            validator
+                .as_map()
+                .unwrap()
+                .iter()
+                .find(|(key, _)| key.as_text() == Some(name))
+                .map(|(_, value)| value.clone())
+                .unwrap()
+        };
+        assert_eq!(field("consensus_address").as_bytes().unwrap().len(), 20);
+        assert!(field("raw_stake").is_bytes());
+        assert_eq!(field("tendermint_pub_key").as_bytes().unwrap().len(), 32);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let snapshot = snapshot();
+
+        let json = snapshot.to_json().unwrap();
+        assert!(json.contains("\"consensus_address\": \"0x"));
+        assert_eq!(ValidatorSnapshot::from_json(&json).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn test_version_is_checked_on_decode() {
+        let snapshot = snapshot();
+        assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+
+        // The version leads the encoding
+        let json = snapshot.to_json().unwrap();
+        assert!(json.starts_with("{\n  \"version\": 1,"));
+        let cbor = snapshot.to_cbor().unwrap();
+        let value: Value = ciborium::from_reader(cbor.as_slice()).unwrap();
+        assert_eq!(value.as_map().unwrap()[0].0.as_text(), Some("version"));
+
+        // Snapshots from before versioning read as version 1
+        let legacy = json.replacen("\"version\": 1,", "", 1);
+        assert_eq!(ValidatorSnapshot::from_json(&legacy).unwrap(), snapshot);
+
+        let future = ValidatorSnapshot {
+            version: SNAPSHOT_VERSION + 1,
+            ..snapshot
+        };
+        for err in [
+            ValidatorSnapshot::from_json(&future.to_json().unwrap()).unwrap_err(),
+            ValidatorSnapshot::from_cbor(&future.to_cbor().unwrap()).unwrap_err(),
+        ] {
+            assert!(err
+                .to_string()
+                .contains("Unsupported validator snapshot version 2"));
+        }
+    }
+}
@@ -1,14 +1,30 @@
 //! StakeHub Client module
 //! Handles interaction with StakeHub contract for validator election and information retrieval
 
-use crate::ethereum_rpc::EthereumRPC;
-use alloy_dyn_abi::{DynSolValue, FunctionExt, JsonAbiExt};
-use alloy_json_abi::JsonAbi;
-use alloy_primitives::{Address, U256};
-use color_eyre::eyre::Result;
+use crate::ethereum_rpc::{
+    decode_unprefixed_call_result, BlockTag, ContractCaller, EthereumRPC, Log, RpcError,
+    UnprefixedCallResult,
+};
+use crate::genesis::GenesisValidatorInfo;
+use crate::logs::{get_logs_chunked, LogFilter, LogQueryOptions};
+use crate::merkle::{self, MerkleProof};
+use crate::network::Network;
+use crate::rate_limit::RateLimiter;
+use alloy_dyn_abi::{DynSolValue, Eip712Domain, FunctionExt, JsonAbiExt, TypedData};
+use alloy_json_abi::{Function, JsonAbi, Param};
+use alloy_primitives::{keccak256, Address, B256, U256};
+use color_eyre::eyre::{eyre, Result};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
-use std::sync::Arc;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::{debug, warn};
 
 /// Validator election information from StakeHub contract
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -17,6 +33,37 @@ pub struct ValidatorElectionInfo {
     pub voting_power: U256,
     pub operator_address: Address,
     pub tendermint_pub_key: Vec<u8>,
+    /// Status reported by contracts whose election info carries one; `None` otherwise
+    pub status: Option<ValidatorStatus>,
+    /// Separate active flag of contracts letting validators pause themselves, independent of
+    /// the status. `None` for contracts without one, which counts as active.
+    pub active: Option<bool>,
+    /// Whether the validator is unbonding, on contracts reporting it. `None` counts as bonded.
+    pub unbonding: Option<bool>,
+    /// Stake bonded by the operator itself, filled in by the client for
+    /// [`ElectionConfig::min_self_stake`]. `None` when not read.
+    pub self_stake: Option<U256>,
+}
+
+/// Validator status reported alongside the election info by some StakeHub variants
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ValidatorStatus {
+    Active,
+    Inactive,
+    Jailed,
+    /// A status value this client doesn't know about
+    Unknown(u8),
+}
+
+impl From<u8> for ValidatorStatus {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => ValidatorStatus::Active,
+            1 => ValidatorStatus::Inactive,
+            2 => ValidatorStatus::Jailed,
+            other => ValidatorStatus::Unknown(other),
+        }
+    }
 }
 
 /// Elected validators result
@@ -26,6 +73,395 @@ pub struct ElectedValidators {
     pub voting_powers: Vec<u64>,
     pub operator_addrs: Vec<Address>,
     pub tendermint_pub_keys: Vec<Vec<u8>>,
+    /// Stake reported by the contract, before scaling to the consensus voting power
+    pub raw_stakes: Vec<U256>,
+}
+
+/// A single elected validator, as a view over one row of [`ElectedValidators`].
+/// Serializes the pubkey as a `0x` hex string.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ElectedValidator {
+    pub consensus_address: Address,
+    pub operator_address: Address,
+    pub voting_power: u64,
+    #[serde(with = "alloy_primitives::hex")]
+    pub tendermint_pub_key: Vec<u8>,
+}
+
+/// Columnar copy of [`ElectedValidators`] with plain serde-friendly values, one row per
+/// validator in election order, for loading into dataframe tools
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElectionColumns {
+    /// Checksummed `0x` addresses
+    pub consensus_address: Vec<String>,
+    /// Checksummed `0x` addresses
+    pub operator_address: Vec<String>,
+    pub voting_power: Vec<u64>,
+    /// Decimal strings, since the stakes don't fit in 64 bits
+    pub raw_stake: Vec<String>,
+    /// `0x`-prefixed hex
+    pub tendermint_pub_key: Vec<String>,
+}
+
+impl ElectedValidator {
+    /// Merkle leaf committing to every field of the validator:
+    /// consensusAddr(20) + operatorAddr(20) + votingPower(8, big-endian) + tendermintPubKey
+    pub fn leaf_hash(&self) -> B256 {
+        let mut data = Vec::with_capacity(48 + self.tendermint_pub_key.len());
+        data.extend_from_slice(self.consensus_address.as_slice());
+        data.extend_from_slice(self.operator_address.as_slice());
+        data.extend_from_slice(&self.voting_power.to_be_bytes());
+        data.extend_from_slice(&self.tendermint_pub_key);
+        merkle::hash_leaf(&data)
+    }
+}
+
+/// Verify that `validator` is the leaf at `index` under `root`, an
+/// [`ElectedValidators::merkle_root`], given the sibling hashes of an
+/// [`ElectedValidators::inclusion_proof`]. Lets a light client check a single validator without
+/// the full set.
+pub fn verify_validator_membership(
+    root: B256,
+    validator: &ElectedValidator,
+    proof: &[B256],
+    index: usize,
+) -> bool {
+    let proof = MerkleProof {
+        index,
+        siblings: proof.to_vec(),
+    };
+    merkle::verify_inclusion_proof(root, validator.leaf_hash(), &proof)
+}
+
+/// Elected validators indexed in election order and by consensus address, for vote bitmaps
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndexedValidatorSet {
+    validators: Vec<ElectedValidator>,
+    indices: HashMap<Address, usize>,
+}
+
+impl IndexedValidatorSet {
+    /// Number of validators
+    pub fn len(&self) -> usize {
+        self.validators.len()
+    }
+
+    /// Check if the set has no validator
+    pub fn is_empty(&self) -> bool {
+        self.validators.is_empty()
+    }
+
+    /// The validator at `index`, in election order
+    pub fn by_index(&self, index: usize) -> Option<&ElectedValidator> {
+        self.validators.get(index)
+    }
+
+    /// Index of the validator with `consensus_address`
+    pub fn index_of(&self, consensus_address: &Address) -> Option<usize> {
+        self.indices.get(consensus_address).copied()
+    }
+}
+
+impl From<&ElectedValidators> for IndexedValidatorSet {
+    fn from(elected: &ElectedValidators) -> Self {
+        let validators: Vec<_> = elected.iter().collect();
+        let indices = validators
+            .iter()
+            .enumerate()
+            .map(|(index, v)| (v.consensus_address, index))
+            .collect();
+        Self {
+            validators,
+            indices,
+        }
+    }
+}
+
+impl ElectedValidators {
+    /// Number of elected validators
+    pub fn len(&self) -> usize {
+        self.consensus_addrs.len()
+    }
+
+    /// Check if no validator was elected
+    pub fn is_empty(&self) -> bool {
+        self.consensus_addrs.is_empty()
+    }
+
+    /// Get the validator at `index`, in election order
+    pub fn get(&self, index: usize) -> Option<ElectedValidator> {
+        Some(ElectedValidator {
+            consensus_address: *self.consensus_addrs.get(index)?,
+            operator_address: *self.operator_addrs.get(index)?,
+            voting_power: *self.voting_powers.get(index)?,
+            tendermint_pub_key: self.tendermint_pub_keys.get(index)?.clone(),
+        })
+    }
+
+    /// Iterate over the validators in election order
+    pub fn iter(&self) -> impl Iterator<Item = ElectedValidator> + '_ {
+        (0..self.len()).filter_map(|i| self.get(i))
+    }
+
+    /// Validators keyed by consensus address, as used for block verification
+    pub fn consensus_map(&self) -> HashMap<Address, ElectedValidator> {
+        self.iter().map(|v| (v.consensus_address, v)).collect()
+    }
+
+    /// Validators keyed by operator address, as used for staking and rewards
+    pub fn operator_map(&self) -> HashMap<Address, ElectedValidator> {
+        self.iter().map(|v| (v.operator_address, v)).collect()
+    }
+
+    /// Whether each validator, in election order, was in the `genesis` set, by consensus address
+    pub fn mark_genesis_validators(&self, genesis: &[GenesisValidatorInfo]) -> Vec<bool> {
+        let genesis: HashSet<Address> = genesis.iter().map(|v| v.consensus_address).collect();
+        self.consensus_addrs
+            .iter()
+            .map(|address| genesis.contains(address))
+            .collect()
+    }
+
+    /// Tendermint pubkeys used by more than one consensus address, each with those addresses in
+    /// election order. Empty pubkeys are missing rather than shared and are skipped.
+    pub fn find_duplicate_pubkeys(&self) -> Vec<(Vec<u8>, Vec<Address>)> {
+        let mut groups: Vec<(Vec<u8>, Vec<Address>)> = Vec::new();
+        let mut group_of: HashMap<&[u8], usize> = HashMap::new();
+        for (pubkey, address) in self.tendermint_pub_keys.iter().zip(&self.consensus_addrs) {
+            if pubkey.is_empty() {
+                continue;
+            }
+            let group = *group_of.entry(pubkey).or_insert_with(|| {
+                groups.push((pubkey.clone(), Vec::new()));
+                groups.len() - 1
+            });
+            if !groups[group].1.contains(address) {
+                groups[group].1.push(*address);
+            }
+        }
+        groups.retain(|(_, addresses)| addresses.len() > 1);
+        groups
+    }
+
+    /// Copy the elected validators into an [`ElectionColumns`] table
+    pub fn to_columns(&self) -> ElectionColumns {
+        ElectionColumns {
+            consensus_address: self
+                .consensus_addrs
+                .iter()
+                .map(Address::to_string)
+                .collect(),
+            operator_address: self.operator_addrs.iter().map(Address::to_string).collect(),
+            voting_power: self.voting_powers.clone(),
+            raw_stake: self.raw_stakes.iter().map(U256::to_string).collect(),
+            tendermint_pub_key: self
+                .tendermint_pub_keys
+                .iter()
+                .map(|key| format!("0x{}", hex::encode(key)))
+                .collect(),
+        }
+    }
+
+    /// Consensus addresses of the fewest validators whose combined voting power is more than
+    /// 2/3 of the total, taking the highest-power validators first
+    pub fn minimal_quorum_set(&self) -> Vec<Address> {
+        let total: u128 = self.voting_powers.iter().map(|&p| p as u128).sum();
+        let mut validators: Vec<_> = self.iter().collect();
+        validators.sort_by_key(|v| std::cmp::Reverse(v.voting_power));
+
+        let mut power = 0u128;
+        let mut quorum = Vec::new();
+        for v in validators {
+            if power * 3 > total * 2 {
+                break;
+            }
+            power += v.voting_power as u128;
+            quorum.push(v.consensus_address);
+        }
+        quorum
+    }
+
+    /// Each validator's fraction of the total voting power, in election order.
+    ///
+    /// Floating point, for display only: results may differ across platforms, so never feed
+    /// them into consensus decisions. Use [`Self::power_shares_bps`] there.
+    pub fn power_shares(&self) -> Vec<f64> {
+        let total: u128 = self.voting_powers.iter().map(|&p| p as u128).sum();
+        self.voting_powers
+            .iter()
+            .map(|&power| {
+                if total == 0 {
+                    0.0
+                } else {
+                    power as f64 / total as f64
+                }
+            })
+            .collect()
+    }
+
+    /// Each validator's share of the total voting power in basis points (1/10000), rounded
+    /// down, in election order. Exact integer arithmetic, so deterministic everywhere.
+    pub fn power_shares_bps(&self) -> Vec<u64> {
+        let total: u128 = self.voting_powers.iter().map(|&p| p as u128).sum();
+        self.voting_powers
+            .iter()
+            .map(|&power| {
+                (power as u128 * 10_000)
+                    .checked_div(total)
+                    .unwrap_or_default() as u64
+            })
+            .collect()
+    }
+
+    /// Check that voting powers never increase along the election order, as the selection
+    /// guarantees unless a custom [`ElectionConfig::score_fn`] ranks by something else
+    pub fn validate_ordering(&self) -> Result<()> {
+        match self.voting_powers.windows(2).position(|pair| pair[0] < pair[1]) {
+            None => Ok(()),
+            Some(index) => Err(eyre!(
+                "Elected validators out of order at index {}: {} has voting power {}, more than {} of the validator before it ({})",
+                index + 1,
+                self.consensus_addrs[index + 1],
+                self.voting_powers[index + 1],
+                self.voting_powers[index],
+                self.consensus_addrs[index]
+            )),
+        }
+    }
+
+    /// Validators sorted by consensus address, the canonical leaf order of the set commitment
+    fn sorted_by_consensus_address(&self) -> Vec<ElectedValidator> {
+        let mut validators: Vec<_> = self.iter().collect();
+        validators.sort_by_key(|v| v.consensus_address);
+        validators
+    }
+
+    /// Consensus addresses of the proposers of the next `rounds` heights under CometBFT's
+    /// weighted round-robin, starting from zeroed proposer priorities.
+    ///
+    /// Before each height the priorities are rescaled to a spread of at most twice the total
+    /// power and centered on their average. Every validator then gains its voting power, and the
+    /// highest priority (ties to the lowest address) proposes and loses the total power.
+    pub fn proposer_schedule(&self, rounds: usize) -> Vec<Address> {
+        let powers: Vec<i128> = self.voting_powers.iter().map(|&p| i128::from(p)).collect();
+        let total: i128 = powers.iter().sum();
+        if total == 0 {
+            return Vec::new();
+        }
+
+        let mut priorities = vec![0i128; powers.len()];
+        let mut schedule = Vec::with_capacity(rounds);
+        for _ in 0..rounds {
+            let max_diff = 2 * total;
+            let diff = priorities.iter().max().unwrap() - priorities.iter().min().unwrap();
+            if diff > max_diff {
+                let ratio = (diff + max_diff - 1) / max_diff;
+                priorities.iter_mut().for_each(|p| *p /= ratio);
+            }
+            let average = priorities
+                .iter()
+                .sum::<i128>()
+                .div_euclid(priorities.len() as i128);
+            for (priority, power) in priorities.iter_mut().zip(&powers) {
+                *priority += power - average;
+            }
+
+            let proposer = (0..priorities.len())
+                .max_by(|&a, &b| {
+                    priorities[a]
+                        .cmp(&priorities[b])
+                        .then_with(|| self.consensus_addrs[b].cmp(&self.consensus_addrs[a]))
+                })
+                .unwrap();
+            priorities[proposer] -= total;
+            schedule.push(self.consensus_addrs[proposer]);
+        }
+        schedule
+    }
+
+    /// Proposer of `round` at `height`, picked with probability proportional to voting power.
+    ///
+    /// The pick is `keccak256(height || round)` (both big-endian) modulo the total power, walked
+    /// over the cumulative powers in consensus address order, so every node holding the same set
+    /// picks the same proposer. [`Address::ZERO`] if the set has no voting power.
+    pub fn select_proposer(&self, height: u64, round: u32) -> Address {
+        let total: u128 = self.voting_powers.iter().map(|&p| u128::from(p)).sum();
+        if total == 0 {
+            return Address::ZERO;
+        }
+
+        let mut seed = [0u8; 12];
+        seed[..8].copy_from_slice(&height.to_be_bytes());
+        seed[8..].copy_from_slice(&round.to_be_bytes());
+        let mut pick = (U256::from_be_bytes(keccak256(seed).0) % U256::from(total)).to::<u128>();
+        for validator in self.sorted_by_consensus_address() {
+            let power = u128::from(validator.voting_power);
+            if pick < power {
+                return validator.consensus_address;
+            }
+            pick -= power;
+        }
+        unreachable!("pick is below the total power")
+    }
+
+    /// Merkle root over the validators sorted by consensus address
+    pub fn merkle_root(&self) -> B256 {
+        let leaves: Vec<B256> = self
+            .sorted_by_consensus_address()
+            .iter()
+            .map(ElectedValidator::leaf_hash)
+            .collect();
+        merkle::merkle_root(&leaves)
+    }
+
+    /// Proof that the validator run by `operator` is part of [`Self::merkle_root`]
+    pub fn inclusion_proof(&self, operator: Address) -> Option<MerkleProof> {
+        let validators = self.sorted_by_consensus_address();
+        let index = validators
+            .iter()
+            .position(|v| v.operator_address == operator)?;
+        let leaves: Vec<B256> = validators.iter().map(ElectedValidator::leaf_hash).collect();
+        merkle::merkle_proof(&leaves, index)
+    }
+
+    /// EIP-712 typed data attesting to the set under `domain`, for off-chain signing.
+    ///
+    /// The primary type is
+    /// `ValidatorSet(Validator[] validators)` with
+    /// `Validator(address consensusAddress,address operatorAddress,uint64 votingPower,bytes tendermintPubKey)`,
+    /// and validators are listed in canonical order (sorted by consensus address).
+    pub fn to_eip712(&self, domain: Eip712Domain) -> Result<TypedData> {
+        let validators: Vec<serde_json::Value> = self
+            .sorted_by_consensus_address()
+            .iter()
+            .map(|v| {
+                json!({
+                    "consensusAddress": v.consensus_address.to_string(),
+                    "operatorAddress": v.operator_address.to_string(),
+                    "votingPower": v.voting_power,
+                    "tendermintPubKey": format!("0x{}", hex::encode(&v.tendermint_pub_key)),
+                })
+            })
+            .collect();
+
+        let mut typed_data: TypedData = serde_json::from_value(json!({
+            "types": {
+                "ValidatorSet": [
+                    { "name": "validators", "type": "Validator[]" },
+                ],
+                "Validator": [
+                    { "name": "consensusAddress", "type": "address" },
+                    { "name": "operatorAddress", "type": "address" },
+                    { "name": "votingPower", "type": "uint64" },
+                    { "name": "tendermintPubKey", "type": "bytes" },
+                ],
+            },
+            "primaryType": "ValidatorSet",
+            "message": { "validators": validators },
+        }))?;
+        typed_data.domain = domain;
+        Ok(typed_data)
+    }
 }
 
 impl Ord for ValidatorElectionInfo {
@@ -47,207 +483,4408 @@ impl PartialOrd for ValidatorElectionInfo {
     }
 }
 
+/// StakeHub events that can change the outcome of the next election
+pub const ELECTION_CHANGE_EVENTS: &[&str] = &[
+    "ValidatorCreated",
+    "ConsensusAddressEdited",
+    "Delegated",
+    "Undelegated",
+    "Redelegated",
+    "ValidatorJailed",
+    "ValidatorUnjailed",
+    "ValidatorEmptyJailed",
+    "ValidatorSlashed",
+];
+
+/// A StakeHub event that can change the elected set
+#[derive(Clone, Debug)]
+pub struct ElectionChangeEvent {
+    /// Event name, one of [`ELECTION_CHANGE_EVENTS`]
+    pub name: String,
+    pub block_number: u64,
+    pub log: Log,
+}
+
+/// View called by [`StakeHubClient::check_compatibility`]
+pub const COMPATIBILITY_PROBE: &str = "maxElectedValidators";
+
+/// The contract at the StakeHub address doesn't answer like the StakeHub ABI
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+pub enum CompatibilityError {
+    #[error("Contract at {address} is incompatible with the StakeHub ABI: {function}() {details}")]
+    Incompatible {
+        address: Address,
+        function: &'static str,
+        details: String,
+    },
+}
+
+/// An optional StakeHub view the contract doesn't implement: it is missing from the ABI, or
+/// calling it reverted or returned no data
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+#[error("StakeHub contract doesn't implement {0}")]
+pub struct MissingView(pub &'static str);
+
+/// Default time-to-live of the cached `maxElectedValidators` value
+pub const DEFAULT_MAX_ELECTED_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// The consensus voting power is the contract's stake divided by this value
+pub const VOTING_POWER_DIVISOR: u64 = 10u64.pow(10);
+
+/// Default number of historical elections fetched at the same time
+pub const DEFAULT_HISTORY_CONCURRENCY: usize = 4;
+
+/// Default upper bound accepted for `maxElectedValidators`
+pub const DEFAULT_MAX_ELECTED_CAP: u64 = 1000;
+
+/// Default lower bound accepted for `epochLength`. Shorter epochs would change the set almost
+/// every block and most likely come from a corrupted or misconfigured contract.
+pub const DEFAULT_MIN_EPOCH_LENGTH: u64 = 10;
+
+/// Default upper bound on the candidates considered by an election
+pub const DEFAULT_MAX_CANDIDATES: usize = 100_000;
+
+/// Shape of the `getValidatorElectionInfo` return data.
+///
+/// Whatever the shape, outputs (and struct fields) named after the four base fields in the ABI are
+/// matched by name, so their order doesn't matter. Without such names they are taken in the
+/// order below. The optional fields after them are only recognized by name.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ElectionInfoLayout {
+    /// Detect the layout from the output types declared in the ABI
+    #[default]
+    Auto,
+    /// Four parallel arrays plus the total length:
+    /// `(address[] consensusAddrs, uint256[] votingPowers, address[] operatorAddrs, bytes[] tendermintPubKeys, uint256 totalLength)`.
+    /// Contracts reporting statuses add a `uint8[] statuses` array before the total length,
+    /// contracts with an active flag a `bool[] active` array and contracts tracking unbonding a
+    /// `bool[] unbonding` array, in any order.
+    ParallelArrays,
+    /// A single array of structs, optionally followed by the total length:
+    /// `((address consensusAddr, uint256 votingPower, address operatorAddr, bytes tendermintPubKey)[], uint256 totalLength)`.
+    /// Contracts reporting statuses add a trailing `uint8 status` struct field, contracts with an
+    /// active flag a `bool active` field and contracts tracking unbonding a `bool unbonding`
+    /// field, in any order.
+    PackedStructs,
+}
+
+impl ElectionInfoLayout {
+    /// Resolve [`ElectionInfoLayout::Auto`] against the ABI of `getValidatorElectionInfo`
+    fn resolve(self, function: &Function) -> Self {
+        match self {
+            ElectionInfoLayout::Auto => {
+                let packed = function.outputs.iter().any(|param| param.ty == "tuple[]");
+                if packed {
+                    ElectionInfoLayout::PackedStructs
+                } else {
+                    ElectionInfoLayout::ParallelArrays
+                }
+            }
+            layout => layout,
+        }
+    }
+}
+
+/// How the stake of an elected validator is turned into its consensus voting power
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PowerModel {
+    /// `power = stake / VOTING_POWER_DIVISOR` (10^10), rounded down
+    #[default]
+    Scaled,
+    /// BSC Parlia compatibility: `power = 1` for every elected validator. Parlia selects
+    /// validators by stake, but its vote attestation weighs each elected validator equally,
+    /// with a quorum of `ceil(2n / 3)` of the `n` validators. The stake only decides who is
+    /// elected and stays available in [`ElectedValidators::raw_stakes`].
+    BscParlia,
+}
+
+impl PowerModel {
+    /// Consensus voting power of an elected validator with `stake`
+    pub fn voting_power(self, stake: U256) -> u64 {
+        match self {
+            PowerModel::Scaled => (stake / U256::from(VOTING_POWER_DIVISOR)).to::<u64>(),
+            PowerModel::BscParlia => 1,
+        }
+    }
+}
+
+/// Ranking score of a candidate; the election keeps the highest scores
+pub type ScoreFn = Arc<dyn Fn(&ValidatorElectionInfo) -> U256 + Send + Sync>;
+
+/// Options of the validator election
+#[derive(Clone)]
+pub struct ElectionConfig {
+    /// Keep candidates without voting power, ranked below every staked candidate.
+    /// Meant for diagnostics, e.g. to see validators that registered but haven't staked yet.
+    pub include_zero_power: bool,
+    /// What to do with candidates the contract returned without a tendermint pubkey
+    pub missing_pubkey: CandidatePolicy,
+    /// What to do with candidates decoded with a zero consensus or operator address, which
+    /// usually means the contract output was misread. Skipped with a warning by default, so
+    /// one bad entry doesn't stall the election.
+    pub zero_address: CandidatePolicy,
+    /// What to do with candidates sharing a consensus address, operator address or tendermint
+    /// pubkey with another one. Skipping leaves out every candidate of a collision, since the
+    /// genuine one can't be told apart.
+    pub duplicates: CandidatePolicy,
+    /// Rank candidates by this score instead of their raw voting power, e.g. to weigh power by
+    /// reputation. Equal scores fall back to the voting power, then the consensus address.
+    pub score_fn: Option<ScoreFn>,
+    /// Accept candidates using the same address as consensus and operator address.
+    /// When disabled, the election fails if any candidate does.
+    pub allow_same_address: bool,
+    /// How the elected validators' stakes become consensus voting powers
+    pub power_model: PowerModel,
+    /// Rank every candidate by the summed voting power of all eligible candidates sharing its
+    /// operator, for operators running several consensus keys. Each elected entry still
+    /// reports its own voting power. A [`Self::score_fn`] sees the summed power.
+    pub aggregate_by_operator: bool,
+    /// Drop candidates whose operator self-delegates less than this. The client reads each
+    /// candidate's [`ValidatorElectionInfo::self_stake`] from its StakeCredit contract, and
+    /// candidates whose self-stake is unknown are dropped too. Zero disables the check and the
+    /// extra calls.
+    pub min_self_stake: U256,
+    /// Only the first this many candidates, in contract order, are kept by the client right
+    /// after decoding, so a broken or malicious contract returning a huge candidate list can't
+    /// make it call out per candidate or exhaust memory. The election functions apply it to
+    /// their input as well.
+    pub max_candidates: usize,
+    /// Drop candidates that didn't opt into the current epoch, as read from `hasOptedIn`.
+    /// Costs one extra call per candidate. The view isn't part of the embedded StakeHub ABI, so
+    /// this needs a custom ABI declaring it.
+    pub require_opt_in: bool,
+    /// Keep candidates the contract flags as not active (e.g. voluntarily paused), for
+    /// auditing. Candidates without the flag are always active.
+    pub include_paused: bool,
+    /// Drop candidates the contract reports as unbonding, even while they still have power
+    pub exclude_unbonding: bool,
+}
+
+impl Default for ElectionConfig {
+    fn default() -> Self {
+        Self {
+            include_zero_power: false,
+            missing_pubkey: CandidatePolicy::default(),
+            zero_address: CandidatePolicy::Skip,
+            duplicates: CandidatePolicy::default(),
+            score_fn: None,
+            allow_same_address: true,
+            power_model: PowerModel::default(),
+            aggregate_by_operator: false,
+            min_self_stake: U256::ZERO,
+            max_candidates: DEFAULT_MAX_CANDIDATES,
+            require_opt_in: false,
+            include_paused: false,
+            exclude_unbonding: false,
+        }
+    }
+}
+
+impl fmt::Debug for ElectionConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ElectionConfig")
+            .field("include_zero_power", &self.include_zero_power)
+            .field("missing_pubkey", &self.missing_pubkey)
+            .field("zero_address", &self.zero_address)
+            .field("duplicates", &self.duplicates)
+            .field("score_fn", &self.score_fn.as_ref().map(|_| "<custom>"))
+            .field("allow_same_address", &self.allow_same_address)
+            .field("power_model", &self.power_model)
+            .field("aggregate_by_operator", &self.aggregate_by_operator)
+            .field("min_self_stake", &self.min_self_stake)
+            .field("max_candidates", &self.max_candidates)
+            .field("require_opt_in", &self.require_opt_in)
+            .field("include_paused", &self.include_paused)
+            .field("exclude_unbonding", &self.exclude_unbonding)
+            .finish()
+    }
+}
+
+/// Candidate in the selection heap, ordered by score and then like [`ValidatorElectionInfo`]
+struct ScoredCandidate {
+    score: U256,
+    /// Position in the contract's output; not part of the ordering
+    index: usize,
+    validator: ValidatorElectionInfo,
+}
+
+impl Ord for ScoredCandidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .cmp(&other.score)
+            .then_with(|| self.validator.cmp(&other.validator))
+    }
+}
+
+impl PartialOrd for ScoredCandidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for ScoredCandidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ScoredCandidate {}
+
+/// How the election treats a malformed candidate
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CandidatePolicy {
+    /// Fail the whole election
+    #[default]
+    Reject,
+    /// Leave the candidate out of the election and log a warning
+    Skip,
+}
+
+/// Why a validator isn't elected, as reported by [`StakeHubClient::explain_eligibility`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EligibilityReason {
+    /// Not among the StakeHub candidates
+    NotRegistered,
+    /// No voting power, so not eligible
+    BelowMinPower,
+    /// Reported as jailed by the contract
+    Jailed,
+    /// Left out for another reason, e.g. an inactive status, a missing tendermint pubkey or
+    /// too little self-stake
+    Excluded,
+    /// Eligible, but ranked below the `maxElectedValidators` seats
+    BelowThreshold,
+}
+
+/// Election outcome of one validator
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EligibilityReport {
+    pub is_candidate: bool,
+    /// Stake reported by the contract, zero for non-candidates
+    pub voting_power: U256,
+    /// 1-based position among the eligible candidates, if eligible
+    pub rank: Option<usize>,
+    pub elected: bool,
+    /// Empty when elected
+    pub reasons: Vec<EligibilityReason>,
+}
+
+/// A delegator's part of a validator's election power
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DelegatorContribution {
+    /// Power contributed by the delegator, in contract stake units
+    pub delegator_power: U256,
+    /// Total election power of the validator, in contract stake units
+    pub validator_power: U256,
+}
+
+impl DelegatorContribution {
+    /// The delegator's share of the validator's power in basis points, rounded down
+    pub fn share_bps(&self) -> u64 {
+        (self.delegator_power.saturating_mul(U256::from(10_000)))
+            .checked_div(self.validator_power)
+            .unwrap_or_default()
+            .saturating_to()
+    }
+}
+
+/// Composition of a validator's stake, as held by its StakeCredit contract
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DelegationBreakdown {
+    /// Stake bonded by the operator itself
+    pub self_delegation: U256,
+    /// All stake of the validator, self-bond included
+    pub total_delegation: U256,
+}
+
+impl DelegationBreakdown {
+    /// Stake delegated by others
+    pub fn delegated(&self) -> U256 {
+        self.total_delegation.saturating_sub(self.self_delegation)
+    }
+
+    /// Share of the stake bonded by the operator, in basis points rounded down.
+    /// `None` if the validator has no stake at all.
+    pub fn self_bond_bps(&self) -> Option<u64> {
+        self.self_delegation
+            .saturating_mul(U256::from(10_000))
+            .checked_div(self.total_delegation)
+            .map(|bps| bps.saturating_to())
+    }
+}
+
+/// Views offered by some StakeHub deployments but missing from the embedded ABI.
+/// Calling one of them fails with a contract error on deployments that don't implement it.
+const OPTIONAL_STAKE_HUB_VIEWS: &[&str] = &[
+    "function getOperatorConsensusPairs() view returns (address[] operatorAddrs, address[] consensusAddrs)",
+    "function pendingReward(address operatorAddress) view returns (uint256)",
+    "function validatorSetChangePending() view returns (bool)",
+    "function getValidatorCount() view returns (uint256)",
+    "function validatorSetHash() view returns (bytes32)",
+];
+
+/// Views of the StakeCredit contract pooling a validator's stake, which StakeHub deploys per
+/// validator (see `getValidatorCreditContract`). Stake is held as shares of the pool.
+const STAKE_CREDIT_VIEWS: &[&str] = &[
+    "function balanceOf(address account) view returns (uint256)",
+    "function totalSupply() view returns (uint256)",
+    "function getPooledMETISByShares(uint256 shares) view returns (uint256)",
+];
+
+/// Load the embedded StakeHub ABI, extended with [`OPTIONAL_STAKE_HUB_VIEWS`]
+pub(crate) fn load_stake_hub_abi() -> Result<JsonAbi> {
+    // Load StakeHub ABI from embedded JSON
+    let abi_str = include_str!("system_contracts/abis/StakeHub.json");
+    let mut stake_hub_abi: JsonAbi = serde_json::from_str(abi_str)?;
+
+    for signature in OPTIONAL_STAKE_HUB_VIEWS {
+        let function = Function::parse(signature)?;
+        stake_hub_abi
+            .functions
+            .entry(function.name.clone())
+            .or_insert_with(|| vec![function]);
+    }
+    Ok(stake_hub_abi)
+}
+
 /// Client for interacting with StakeHub contract
 pub struct StakeHubClient {
-    eth_rpc: Arc<EthereumRPC>,
+    eth_rpc: Arc<dyn ContractCaller>,
     stake_hub_address: Address,
     stake_hub_abi: JsonAbi,
+    /// Last `maxElectedValidators` value and when it was fetched
+    max_elected_cache: Mutex<Option<(Instant, U256)>>,
+    max_elected_cache_ttl: Duration,
+    /// Largest `maxElectedValidators` value trusted from the contract
+    max_elected_cap: u64,
+    /// Reject values above the cap instead of clamping them
+    strict_max_elected: bool,
+    /// Smallest `epochLength` value trusted from the contract
+    min_epoch_length: u64,
+    log_query_options: LogQueryOptions,
+    election_info_layout: ElectionInfoLayout,
+    election_config: ElectionConfig,
+    /// Cross-check the decoded election info against `getOperatorConsensusPairs`
+    verify_operator_pairing: bool,
+    /// Throttles outgoing `eth_call`s when set
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Number of historical elections fetched at the same time
+    history_concurrency: usize,
 }
 
 impl StakeHubClient {
     /// Create a new StakeHubClient
     pub fn new(eth_rpc: Arc<EthereumRPC>, stake_hub_address: Address) -> Result<Self> {
-        // Load StakeHub ABI from embedded JSON
-        let abi_str = include_str!("system_contracts/abis/StakeHub.json");
-        let stake_hub_abi: JsonAbi = serde_json::from_str(abi_str)?;
+        Self::with_caller(eth_rpc, stake_hub_address)
+    }
+
+    /// Create a client for the StakeHub of a known [`Network`], reading the chain id from
+    /// `eth_rpc` unless it is given. Fails for chain ids missing from the registry.
+    pub async fn for_chain(eth_rpc: Arc<EthereumRPC>, chain_id: Option<u64>) -> Result<Self> {
+        let chain_id = match chain_id {
+            Some(chain_id) => chain_id,
+            None => {
+                let chain_id = eth_rpc.get_chain_id().await?;
+                u64::from_str_radix(chain_id.trim_start_matches("0x"), 16)?
+            }
+        };
+        let network = Network::from_chain_id(chain_id);
+        if let Network::Custom(chain_id) = network {
+            return Err(eyre!(
+                "No StakeHub deployment known for chain id {}",
+                chain_id
+            ));
+        }
+
+        Ok(Self::new(eth_rpc, network.stake_hub_address())?
+            .with_election_info_layout(network.election_info_layout()))
+    }
+
+    /// Create a new StakeHubClient on top of any [`ContractCaller`]
+    pub fn with_caller(
+        eth_rpc: Arc<dyn ContractCaller>,
+        stake_hub_address: Address,
+    ) -> Result<Self> {
+        let stake_hub_abi = load_stake_hub_abi()?;
 
         Ok(Self {
             eth_rpc,
             stake_hub_address,
             stake_hub_abi,
+            max_elected_cache: Mutex::new(None),
+            max_elected_cache_ttl: DEFAULT_MAX_ELECTED_CACHE_TTL,
+            max_elected_cap: DEFAULT_MAX_ELECTED_CAP,
+            strict_max_elected: false,
+            min_epoch_length: DEFAULT_MIN_EPOCH_LENGTH,
+            log_query_options: LogQueryOptions::default(),
+            election_info_layout: ElectionInfoLayout::default(),
+            election_config: ElectionConfig::default(),
+            verify_operator_pairing: false,
+            rate_limiter: None,
+            history_concurrency: DEFAULT_HISTORY_CONCURRENCY,
         })
     }
 
-    /// Get epoch length from StakeHub contract
-    pub async fn get_epoch_length(&self) -> Result<u64> {
-        let function = self
-            .stake_hub_abi
-            .function("epochLength")
-            .unwrap()
-            .first()
-            .unwrap();
-
-        let call_data = function.abi_encode_input(&[])?;
-        let result = self
-            .eth_rpc
-            .eth_call(&self.stake_hub_address.to_string(), &call_data)
-            .await?;
-
-        let output = function.abi_decode_output(&result, false)?;
-        let epoch_length: U256 = output[0].as_uint().unwrap().0;
-        Ok(epoch_length.to::<u64>())
+    /// Set how long a fetched `maxElectedValidators` value is reused before querying again
+    pub fn with_max_elected_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.max_elected_cache_ttl = ttl;
+        self
     }
 
-    /// Get max elected validators from StakeHub contract
-    pub async fn get_max_elected_validators(&self) -> Result<U256> {
-        let function = self
-            .stake_hub_abi
-            .function("maxElectedValidators")
-            .unwrap()
-            .first()
-            .unwrap();
-
-        let call_data = function.abi_encode_input(&[])?;
-        let result = self
-            .eth_rpc
-            .eth_call(&self.stake_hub_address.to_string(), &call_data)
-            .await?;
+    /// Set the largest `maxElectedValidators` value accepted from the contract
+    pub fn with_max_elected_cap(mut self, cap: u64) -> Self {
+        self.max_elected_cap = cap;
+        self
+    }
 
-        let output = function.abi_decode_output(&result, false)?;
-        let max_elected = output[0].as_uint().unwrap().0;
+    /// When enabled, a `maxElectedValidators` value above the cap is an error instead of
+    /// being clamped
+    pub fn with_strict_max_elected(mut self, strict: bool) -> Self {
+        self.strict_max_elected = strict;
+        self
+    }
 
-        Ok(max_elected)
+    /// Set the smallest `epochLength` value accepted from the contract. Zero accepts any value.
+    pub fn with_min_epoch_length(mut self, min: u64) -> Self {
+        self.min_epoch_length = min;
+        self
     }
 
-    /// Get validator election info from StakeHub contract
-    pub async fn get_validator_election_info(
-        &self,
-    ) -> Result<(Vec<Address>, Vec<U256>, Vec<Address>, Vec<Vec<u8>>, U256)> {
-        let function = self
-            .stake_hub_abi
-            .function("getValidatorElectionInfo")
-            .unwrap()
-            .first()
-            .unwrap();
+    /// Set how event queries are split into `eth_getLogs` requests
+    pub fn with_log_query_options(mut self, options: LogQueryOptions) -> Self {
+        self.log_query_options = options;
+        self
+    }
 
-        let call_data = function.abi_encode_input(&[
-            DynSolValue::from(U256::from(0)),
-            DynSolValue::from(U256::from(0)),
-        ])?;
+    /// Use `abi` instead of the embedded StakeHub ABI, for contract variants with a different
+    /// interface
+    pub fn with_stake_hub_abi(mut self, abi: JsonAbi) -> Self {
+        self.stake_hub_abi = abi;
+        self
+    }
 
-        let result = self
-            .eth_rpc
-            .eth_call(&self.stake_hub_address.to_string(), &call_data)
-            .await?;
-        let output = function.abi_decode_output(&result, false)?;
+    /// Set how `getValidatorElectionInfo` return data is decoded
+    pub fn with_election_info_layout(mut self, layout: ElectionInfoLayout) -> Self {
+        self.election_info_layout = layout;
+        self
+    }
 
-        let consensus_addresses = output[0]
-            .as_array()
-            .unwrap()
-            .iter()
-            .map(|val| val.as_address().unwrap())
-            .collect::<Vec<_>>();
+    /// Set the options of the validator election. Fails if they need a view missing from the
+    /// StakeHub ABI, so set a custom ABI with [`Self::with_stake_hub_abi`] first.
+    pub fn with_election_config(mut self, config: ElectionConfig) -> Result<Self> {
+        if config.require_opt_in && self.function("hasOptedIn").is_err() {
+            return Err(eyre!(
+                "require_opt_in needs a StakeHub ABI with a `hasOptedIn(address)` view"
+            ));
+        }
+        self.election_config = config;
+        Ok(self)
+    }
 
-        let voting_powers = output[1]
-            .as_array()
-            .unwrap()
-            .iter()
-            .map(|val| val.as_uint().unwrap().0)
-            .collect();
+    /// When enabled, the election info is checked against the `getOperatorConsensusPairs` view
+    /// at the same block, so a contract returning its arrays in different orders is caught
+    /// instead of mis-associating operators and consensus addresses. Costs one extra call.
+    pub fn with_operator_pairing_check(mut self, verify: bool) -> Self {
+        self.verify_operator_pairing = verify;
+        self
+    }
 
-        let operator_addresses = output[2]
-            .as_array()
-            .unwrap()
-            .iter()
-            .map(|val| val.as_address().unwrap())
-            .collect();
+    /// Throttle contract calls through `limiter`, which may be shared with other clients of the
+    /// same RPC provider. Calls over the limit wait for their turn rather than failing.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
 
-        let tendermint_pub_keys = output[3]
-            .as_array()
-            .unwrap()
-            .iter()
-            .map(|val| val.as_bytes().unwrap().to_vec())
-            .collect();
+    /// Set how many historical elections [`Self::get_top_validators_at_blocks`] fetches at the
+    /// same time (at least one)
+    pub fn with_history_concurrency(mut self, concurrency: usize) -> Self {
+        self.history_concurrency = concurrency.max(1);
+        self
+    }
 
-        let total_length = output[4].as_uint().unwrap().0;
+    /// Drop the cached `maxElectedValidators` value so the next read hits the contract
+    pub fn invalidate_max_elected_cache(&self) {
+        *self.max_elected_cache.lock().unwrap() = None;
+    }
 
-        Ok((
-            consensus_addresses,
-            voting_powers,
-            operator_addresses,
-            tendermint_pub_keys,
-            total_length,
-        ))
+    /// Call a view function of the StakeHub contract and decode its outputs
+    async fn call_function(&self, name: &str, args: &[DynSolValue]) -> Result<Vec<DynSolValue>> {
+        self.call_function_at(name, args, BlockTag::Latest).await
     }
 
-    /// Get top validators by voting power from StakeHub contract
-    pub async fn get_top_validators_by_voting_power(&self) -> Result<ElectedValidators> {
-        // Get max elected validators
-        let max_elected = self.get_max_elected_validators().await?;
+    /// Call a view function of the StakeHub contract against the state at `block`
+    async fn call_function_at(
+        &self,
+        name: &str,
+        args: &[DynSolValue],
+        block: BlockTag,
+    ) -> Result<Vec<DynSolValue>> {
+        let function = self.function(name)?;
+        let result = self.call_raw_at(function, args, block).await?;
 
-        // Get all validator election info
-        let (
-            consensus_addresses,
-            voting_powers,
-            operator_addresses,
-            tendermint_pub_keys,
-            _total_length,
-        ) = self.get_validator_election_info().await?;
-
-        // Convert to ValidatorElectionInfo
-        let validators: Vec<ValidatorElectionInfo> = consensus_addresses
-            .into_iter()
-            .zip(voting_powers.into_iter())
-            .zip(operator_addresses.into_iter())
-            .zip(tendermint_pub_keys.into_iter())
-            .map(
-                |(((consensus_address, voting_power), operator_address), tendermint_pub_key)| {
-                    ValidatorElectionInfo {
-                        consensus_address,
-                        voting_power,
-                        operator_address,
-                        tendermint_pub_key,
-                    }
-                },
-            )
-            .collect();
+        Ok(function.abi_decode_output(&result, false)?)
+    }
 
-        // Apply the selection algorithm
-        let result = get_top_validators_by_voting_power(validators, max_elected);
+    /// Call `function` against the state at `block` and return the undecoded return data
+    async fn call_raw_at(
+        &self,
+        function: &Function,
+        args: &[DynSolValue],
+        block: BlockTag,
+    ) -> Result<Vec<u8>> {
+        let call_data = function.abi_encode_input(args)?;
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+        let result = self
+            .eth_rpc
+            .eth_call_at(&self.stake_hub_address.to_string(), &call_data, block)
+            .await;
+        recover_unprefixed_result(function, result)
+    }
 
-        Ok(result)
+    fn function(&self, name: &str) -> Result<&Function> {
+        self.stake_hub_abi
+            .function(name)
+            .and_then(|overloads| overloads.first())
+            .ok_or_else(|| eyre!("Function {} not found in StakeHub ABI", name))
     }
-}
 
-/// Get top validators by voting power using binary heap
-fn get_top_validators_by_voting_power(
-    validators: Vec<ValidatorElectionInfo>,
-    max_elected: U256,
-) -> ElectedValidators {
-    let mut validator_heap: BinaryHeap<ValidatorElectionInfo> = BinaryHeap::new();
+    /// Check that the contract answers [`COMPATIBILITY_PROBE`] with exactly the return data its
+    /// ABI declares, to catch a client pointed at the wrong contract before it decodes garbage.
+    ///
+    /// Fails with [`CompatibilityError::Incompatible`] if the return data is missing, has the
+    /// wrong number of words or holds invalid values, and with the RPC error if the call fails.
+    pub async fn check_compatibility(&self) -> Result<()> {
+        let function = self.function(COMPATIBILITY_PROBE)?;
+        let result = self.call_raw_at(function, &[], BlockTag::Latest).await?;
+        let incompatible = |details: String| CompatibilityError::Incompatible {
+            address: self.stake_hub_address,
+            function: COMPATIBILITY_PROBE,
+            details,
+        };
 
-    for validator in validators {
-        if validator.voting_power > U256::ZERO {
-            validator_heap.push(validator);
+        if result.is_empty() {
+            return Err(incompatible("no return data, is a contract deployed?".to_string()).into());
+        }
+        let values = function
+            .abi_decode_output(&result, true)
+            .map_err(|e| incompatible(e.to_string()))?;
+        let expected = function.abi_encode_output(&values)?;
+        if expected.len() != result.len() {
+            return Err(incompatible(format!(
+                "returned {} words, the ABI declares {}",
+                result.len().div_ceil(32),
+                expected.len() / 32
+            ))
+            .into());
         }
+        Ok(())
     }
 
-    let top_n = max_elected.to::<u64>() as usize;
-    let top_n = if top_n > validator_heap.len() {
-        validator_heap.len()
-    } else {
-        top_n
+    /// Call the view `fn_name` of the StakeHub ABI with `args` and return its decoded outputs,
+    /// for inspecting the contract without a typed method. Overloads resolve to the first
+    /// declaration.
+    pub async fn call_view(
+        &self,
+        fn_name: &str,
+        args: Vec<DynSolValue>,
+    ) -> Result<Vec<DynSolValue>> {
+        self.call_function(fn_name, &args).await
+    }
+
+    /// Get epoch length from StakeHub contract. Values below the configured minimum are
+    /// rejected rather than rotating the set every few blocks.
+    pub async fn get_epoch_length(&self) -> Result<u64> {
+        let epoch_length = self.get_epoch_length_at(BlockTag::Latest).await?;
+        if epoch_length < self.min_epoch_length {
+            return Err(eyre!(
+                "epochLength {} is below the configured minimum of {}. Check the StakeHub configuration!",
+                epoch_length,
+                self.min_epoch_length
+            ));
+        }
+        Ok(epoch_length)
+    }
+
+    /// Number of the latest block the node considers finalized
+    pub async fn get_finalized_block_number(&self) -> Result<u64> {
+        self.eth_rpc.block_number_at(BlockTag::Finalized).await
+    }
+
+    /// Get the epoch length from the StakeHub state at `block`, as stored without the minimum
+    /// check
+    pub async fn get_epoch_length_at(&self, block: BlockTag) -> Result<u64> {
+        let output = self.call_function_at("epochLength", &[], block).await?;
+        let epoch_length = output
+            .first()
+            .and_then(DynSolValue::as_uint)
+            .map(|(epoch_length, _)| epoch_length)
+            .ok_or_else(|| eyre!("Invalid epochLength output"))?;
+        u64::try_from(epoch_length)
+            .map_err(|_| eyre!("epochLength {} doesn't fit in a u64", epoch_length))
+    }
+
+    /// Read `epochLength` at each of `blocks`, normally ascending, and report where it changed:
+    /// the first block with its value, then every block whose value differs from the block
+    /// before it. Fetches up to the configured history concurrency at once; needs an archive
+    /// node for old blocks.
+    pub async fn epoch_length_history(&self, blocks: &[u64]) -> Result<Vec<(u64, u64)>> {
+        let epoch_lengths: Vec<u64> = stream::iter(blocks.iter().copied())
+            .map(|block| self.get_epoch_length_at(BlockTag::Number(block)))
+            .buffered(self.history_concurrency)
+            .try_collect()
+            .await?;
+
+        let mut history: Vec<(u64, u64)> = Vec::new();
+        for (&block, epoch_length) in blocks.iter().zip(epoch_lengths) {
+            if history.last().map(|&(_, last)| last) != Some(epoch_length) {
+                history.push((block, epoch_length));
+            }
+        }
+        Ok(history)
+    }
+
+    /// Get max elected validators from StakeHub contract.
+    /// The value is cached for the configured TTL since it only changes through governance,
+    /// and bounded by the configured cap so a misconfigured contract can't trigger a huge
+    /// allocation.
+    pub async fn get_max_elected_validators(&self) -> Result<U256> {
+        self.get_max_elected_validators_at(BlockTag::Latest).await
+    }
+
+    /// Get max elected validators at `block`. Only the latest value is cached.
+    pub async fn get_max_elected_validators_at(&self, block: BlockTag) -> Result<U256> {
+        if block != BlockTag::Latest {
+            let output = self
+                .call_function_at("maxElectedValidators", &[], block)
+                .await?;
+            return self.apply_max_elected_cap(decode_max_elected(&output)?);
+        }
+
+        let cached = *self.max_elected_cache.lock().unwrap();
+        let max_elected = match cached {
+            Some((fetched_at, max_elected))
+                if fetched_at.elapsed() < self.max_elected_cache_ttl =>
+            {
+                max_elected
+            }
+            _ => {
+                let output = self.call_function("maxElectedValidators", &[]).await?;
+                let max_elected = decode_max_elected(&output)?;
+                *self.max_elected_cache.lock().unwrap() = Some((Instant::now(), max_elected));
+                max_elected
+            }
+        };
+
+        self.apply_max_elected_cap(max_elected)
+    }
+
+    fn apply_max_elected_cap(&self, max_elected: U256) -> Result<U256> {
+        let cap = U256::from(self.max_elected_cap);
+        if max_elected <= cap {
+            return Ok(max_elected);
+        }
+
+        if self.strict_max_elected {
+            return Err(eyre!(
+                "maxElectedValidators {} exceeds the configured cap of {}",
+                max_elected,
+                cap
+            ));
+        }
+
+        warn!(
+            "⚠️ maxElectedValidators {} exceeds the configured cap of {}, clamping. Check the StakeHub configuration!",
+            max_elected, cap
+        );
+        Ok(cap)
+    }
+
+    /// Get validator election info from StakeHub contract, together with the total number of
+    /// candidates reported by the contract
+    pub async fn get_validator_election_info(&self) -> Result<(Vec<ValidatorElectionInfo>, U256)> {
+        self.get_validator_election_info_at(BlockTag::Latest).await
+    }
+
+    /// Every registered validator from the latest state, highest voting power first, including
+    /// those below the `maxElectedValidators` cut. Unlike the elected set, no candidate is
+    /// filtered out.
+    pub async fn get_all_registered_validators(&self) -> Result<Vec<ValidatorElectionInfo>> {
+        let (mut validators, _) = self.get_validator_election_info().await?;
+        validators.sort_by(|a, b| b.cmp(a));
+        Ok(validators)
+    }
+
+    /// [`candidates_fingerprint`] of the latest candidate list, elected or not, to skip
+    /// re-processing when nothing changed between polls
+    pub async fn candidates_fingerprint(&self) -> Result<B256> {
+        let (candidates, _) = self.get_validator_election_info().await?;
+        Ok(candidates_fingerprint(&candidates))
+    }
+
+    /// Get validator election info from the StakeHub state at `block`
+    pub async fn get_validator_election_info_at(
+        &self,
+        block: BlockTag,
+    ) -> Result<(Vec<ValidatorElectionInfo>, U256)> {
+        let function = self
+            .stake_hub_abi
+            .function("getValidatorElectionInfo")
+            .and_then(|overloads| overloads.first())
+            .ok_or_else(|| eyre!("Function getValidatorElectionInfo not found in StakeHub ABI"))?;
+        let layout = self.election_info_layout.resolve(function);
+
+        let output = self
+            .call_function_at(
+                "getValidatorElectionInfo",
+                &[
+                    DynSolValue::from(U256::from(0)),
+                    DynSolValue::from(U256::from(0)),
+                ],
+                block,
+            )
+            .await?;
+
+        let (output, extra_names) = order_election_info_by_name(function, layout, output);
+        let (mut validators, total_length) =
+            decode_validator_election_info(&output, layout, &extra_names)?;
+        cap_candidates(
+            &mut validators,
+            total_length,
+            self.election_config.max_candidates,
+        );
+        let validators = apply_zero_address_policy(validators, self.election_config.zero_address)?;
+        if self.verify_operator_pairing {
+            let pairs = self.get_operator_consensus_pairs_at(block).await?;
+            check_operator_pairing(&validators, &pairs)?;
+        }
+        Ok((validators, total_length))
+    }
+
+    /// Get every (operator, consensus) address pair in one call, through the optional
+    /// `getOperatorConsensusPairs` view. Fails with [`MissingView`] if the contract doesn't
+    /// implement it.
+    pub async fn get_operator_consensus_pairs(&self) -> Result<Vec<(Address, Address)>> {
+        self.get_operator_consensus_pairs_at(BlockTag::Latest).await
+    }
+
+    /// Get every (operator, consensus) address pair from the StakeHub state at `block`
+    pub async fn get_operator_consensus_pairs_at(
+        &self,
+        block: BlockTag,
+    ) -> Result<Vec<(Address, Address)>> {
+        let output = self
+            .call_optional_view_at("getOperatorConsensusPairs", &[], block)
+            .await?;
+        let addresses = |index: usize| -> Result<Vec<Address>> {
+            output
+                .get(index)
+                .and_then(|value| value.as_array())
+                .ok_or_else(|| eyre!("Expected an address array output"))?
+                .iter()
+                .map(as_address)
+                .collect()
+        };
+        let operators = addresses(0)?;
+        let consensus = addresses(1)?;
+        if operators.len() != consensus.len() {
+            return Err(eyre!(
+                "getOperatorConsensusPairs returned {} operators but {} consensus addresses",
+                operators.len(),
+                consensus.len()
+            ));
+        }
+
+        Ok(operators.into_iter().zip(consensus).collect())
+    }
+
+    /// Find the candidate registered with the tendermint `pubkey`, e.g. to map a gossip peer
+    /// back to its consensus and operator addresses
+    pub async fn get_validator_by_tendermint_pubkey(
+        &self,
+        pubkey: &[u8],
+    ) -> Result<Option<ValidatorElectionInfo>> {
+        let (validators, _) = self.get_validator_election_info().await?;
+
+        Ok(validators
+            .into_iter()
+            .find(|v| v.tendermint_pub_key == pubkey))
+    }
+
+    /// Map every consensus address to its operator.
+    /// Uses `getOperatorConsensusPairs` when the contract implements it, and falls back to
+    /// scanning the full election info on a [`MissingView`]. Other errors are returned.
+    pub async fn get_consensus_to_operator_index(&self) -> Result<HashMap<Address, Address>> {
+        let pairs = match self.get_operator_consensus_pairs().await {
+            Ok(pairs) => pairs,
+            Err(e) if e.downcast_ref::<MissingView>().is_some() => {
+                warn!("{}, falling back to election info", e);
+                let (validators, _) = self.get_validator_election_info().await?;
+                validators
+                    .into_iter()
+                    .map(|v| (v.operator_address, v.consensus_address))
+                    .collect()
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(pairs
+            .into_iter()
+            .map(|(operator, consensus)| (consensus, operator))
+            .collect())
+    }
+
+    /// Get the StakeHub events in `from_block..=to_block` that can change the elected set,
+    /// in block order
+    pub async fn get_election_change_events(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<ElectionChangeEvent>> {
+        let signatures: HashMap<B256, &str> = ELECTION_CHANGE_EVENTS
+            .iter()
+            .filter_map(|name| {
+                let event = self.stake_hub_abi.event(name)?.first()?;
+                Some((event.selector(), *name))
+            })
+            .collect();
+
+        let filter = LogFilter {
+            address: self.stake_hub_address.to_string(),
+            event_signatures: signatures.keys().map(|s| s.to_string()).collect(),
+        };
+        let logs = get_logs_chunked(
+            self.eth_rpc.as_ref(),
+            &filter,
+            from_block,
+            to_block,
+            self.log_query_options,
+        )
+        .await?;
+
+        logs.into_iter()
+            .map(|log| {
+                let topic0: B256 = log
+                    .topics
+                    .first()
+                    .ok_or_else(|| eyre!("Log without topics in StakeHub events"))?
+                    .parse()?;
+                let name = signatures
+                    .get(&topic0)
+                    .ok_or_else(|| eyre!("Unexpected StakeHub event topic {}", topic0))?;
+                let block_number =
+                    u64::from_str_radix(log.block_number.trim_start_matches("0x"), 16)?;
+                Ok(ElectionChangeEvent {
+                    name: name.to_string(),
+                    block_number,
+                    log,
+                })
+            })
+            .collect()
+    }
+
+    /// Get top validators by voting power from StakeHub contract
+    pub async fn get_top_validators_by_voting_power(&self) -> Result<ElectedValidators> {
+        self.get_top_validators_by_voting_power_at(BlockTag::Latest)
+            .await
+    }
+
+    /// Get top validators by voting power from the StakeHub state at `block`
+    pub async fn get_top_validators_by_voting_power_at(
+        &self,
+        block: BlockTag,
+    ) -> Result<ElectedValidators> {
+        let (validators, max_elected) = self.get_election_candidates_at(block).await?;
+
+        // Apply the selection algorithm
+        Ok(self.elect(validators, max_elected))
+    }
+
+    /// Get top validators from the StakeHub state with `root`, for verification against a
+    /// trusted state root. Needs a node that accepts state roots as the call's block parameter.
+    pub async fn get_top_validators_at_state_root(&self, root: B256) -> Result<ElectedValidators> {
+        self.get_top_validators_by_voting_power_at(BlockTag::StateRoot(root))
+            .await
+    }
+
+    /// Run the configured election over `validators` for `max_elected` seats
+    pub fn elect(
+        &self,
+        validators: Vec<ValidatorElectionInfo>,
+        max_elected: U256,
+    ) -> ElectedValidators {
+        get_top_validators_by_voting_power(validators, max_elected, &self.election_config)
+    }
+
+    /// Get the top validators from the StakeHub state at each of `blocks`, in the same order,
+    /// fetching up to the configured history concurrency at once.
+    ///
+    /// The fetches are driven by the returned future itself rather than spawned tasks, so
+    /// dropping it cancels every request still in flight.
+    pub async fn get_top_validators_at_blocks(
+        &self,
+        blocks: &[u64],
+    ) -> Result<Vec<ElectedValidators>> {
+        stream::iter(blocks.iter().copied())
+            .map(|block| self.get_top_validators_by_voting_power_at(BlockTag::Number(block)))
+            .buffered(self.history_concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Whether the contract flagged a validator set change for the next epoch. When it didn't,
+    /// the next epoch keeps the current set and its fetch can be skipped.
+    /// Only available on deployments exposing `validatorSetChangePending`.
+    pub async fn is_set_change_pending(&self) -> Result<bool> {
+        let output = self.call_function("validatorSetChangePending", &[]).await?;
+        output
+            .first()
+            .and_then(DynSolValue::as_bool)
+            .ok_or_else(|| eyre!("Invalid validatorSetChangePending output"))
+    }
+
+    /// Number of registered validators, read from the cheap `getValidatorCount` view on
+    /// deployments exposing it. Falls back to the `totalLength` reported with the whole
+    /// `getValidatorElectionInfo` on a [`MissingView`]; other errors are returned.
+    pub async fn get_validator_count(&self) -> Result<u64> {
+        let count = match self
+            .call_optional_view_at("getValidatorCount", &[], BlockTag::Latest)
+            .await
+        {
+            Ok(output) => output
+                .first()
+                .and_then(DynSolValue::as_uint)
+                .map(|(count, _)| count)
+                .ok_or_else(|| eyre!("Invalid getValidatorCount output"))?,
+            Err(e) if e.downcast_ref::<MissingView>().is_some() => {
+                debug!("{}, counting through getValidatorElectionInfo", e);
+                self.get_validator_election_info().await?.1
+            }
+            Err(e) => return Err(e),
+        };
+        u64::try_from(count).map_err(|_| eyre!("Validator count {} doesn't fit in a u64", count))
+    }
+
+    /// Hash of the current validator set as reported by the contract.
+    /// Only available on deployments exposing `validatorSetHash`.
+    pub async fn get_onchain_set_hash(&self) -> Result<B256> {
+        let output = self.call_function("validatorSetHash", &[]).await?;
+        match output.first().and_then(DynSolValue::as_fixed_bytes) {
+            Some((hash, 32)) => Ok(B256::from_slice(hash)),
+            _ => Err(eyre!("Invalid validatorSetHash output")),
+        }
+    }
+
+    /// Whether the contract's [`Self::get_onchain_set_hash`] matches the
+    /// [`ElectedValidators::merkle_root`] of `elected`, for deployments hashing their set the
+    /// same way. A mismatch is logged.
+    pub async fn verify_onchain_set_hash(&self, elected: &ElectedValidators) -> Result<bool> {
+        let onchain = self.get_onchain_set_hash().await?;
+        let local = elected.merkle_root();
+        if onchain != local {
+            warn!(
+                "Validator set hash mismatch: contract reports {}, local set hashes to {}",
+                onchain, local
+            );
+        }
+        Ok(onchain == local)
+    }
+
+    /// Rewards accumulated by the validator run by `operator` and not yet distributed.
+    /// Fails with [`MissingView`] on deployments without a `pendingReward` view.
+    pub async fn get_pending_reward(&self, operator: Address) -> Result<U256> {
+        let output = self
+            .call_optional_view_at(
+                "pendingReward",
+                &[DynSolValue::from(operator)],
+                BlockTag::Latest,
+            )
+            .await?;
+        output
+            .first()
+            .and_then(DynSolValue::as_uint)
+            .map(|(reward, _)| reward)
+            .ok_or_else(|| eyre!("Invalid pendingReward output for {}", operator))
+    }
+
+    /// Stake `delegator` holds with the validator run by `operator`: its shares of the
+    /// validator's StakeCredit pool, valued in METIS
+    pub async fn get_delegator_power(&self, operator: Address, delegator: Address) -> Result<U256> {
+        let block = BlockTag::Latest;
+        let credit = self.get_credit_contract_at(operator, block).await?;
+        let shares = self
+            .call_stake_credit_at(credit, "balanceOf", &[DynSolValue::from(delegator)], block)
+            .await?;
+        self.pooled_by_shares_at(credit, shares, block).await
+    }
+
+    /// Self-bonded and total stake of the validator run by `operator`, read from its StakeCredit
+    /// contract: the operator's shares and all shares of the pool, valued in METIS
+    pub async fn get_delegation_breakdown(&self, operator: Address) -> Result<DelegationBreakdown> {
+        self.get_delegation_breakdown_at(operator, BlockTag::Latest)
+            .await
+    }
+
+    /// Whether the validator with `consensus` opted into the current epoch.
+    /// Only available with a StakeHub ABI declaring a `hasOptedIn(address)` view.
+    pub async fn has_opted_in(&self, consensus: Address) -> Result<bool> {
+        self.has_opted_in_at(consensus, BlockTag::Latest).await
+    }
+
+    async fn has_opted_in_at(&self, consensus: Address, block: BlockTag) -> Result<bool> {
+        let output = self
+            .call_function_at("hasOptedIn", &[DynSolValue::from(consensus)], block)
+            .await?;
+        output
+            .first()
+            .and_then(DynSolValue::as_bool)
+            .ok_or_else(|| eyre!("Invalid hasOptedIn output for {}", consensus))
+    }
+
+    async fn get_delegation_breakdown_at(
+        &self,
+        operator: Address,
+        block: BlockTag,
+    ) -> Result<DelegationBreakdown> {
+        let credit = self.get_credit_contract_at(operator, block).await?;
+        let holder = [DynSolValue::from(operator)];
+        let (self_shares, total_shares) = futures::try_join!(
+            self.call_stake_credit_at(credit, "balanceOf", &holder, block),
+            self.call_stake_credit_at(credit, "totalSupply", &[], block),
+        )?;
+        let (self_delegation, total_delegation) = futures::try_join!(
+            self.pooled_by_shares_at(credit, self_shares, block),
+            self.pooled_by_shares_at(credit, total_shares, block),
+        )?;
+
+        Ok(DelegationBreakdown {
+            self_delegation,
+            total_delegation,
+        })
+    }
+
+    /// StakeCredit contract of the validator run by `operator` at `block`
+    async fn get_credit_contract_at(&self, operator: Address, block: BlockTag) -> Result<Address> {
+        let output = self
+            .call_function_at(
+                "getValidatorCreditContract",
+                &[DynSolValue::from(operator)],
+                block,
+            )
+            .await?;
+        let credit = output
+            .first()
+            .and_then(DynSolValue::as_address)
+            .ok_or_else(|| eyre!("Invalid getValidatorCreditContract output for {}", operator))?;
+        if credit.is_zero() {
+            return Err(eyre!("{} has no StakeCredit contract", operator));
+        }
+        Ok(credit)
+    }
+
+    /// METIS worth of `shares` of the pool of StakeCredit contract `credit`
+    async fn pooled_by_shares_at(
+        &self,
+        credit: Address,
+        shares: U256,
+        block: BlockTag,
+    ) -> Result<U256> {
+        if shares.is_zero() {
+            return Ok(U256::ZERO);
+        }
+        self.call_stake_credit_at(
+            credit,
+            "getPooledMETISByShares",
+            &[DynSolValue::from(shares)],
+            block,
+        )
+        .await
+    }
+
+    /// Call the [`OPTIONAL_STAKE_HUB_VIEWS`] function `name` against the state at `block`.
+    /// Fails with [`MissingView`] if it's missing from the ABI, reverts or returns no data.
+    async fn call_optional_view_at(
+        &self,
+        name: &'static str,
+        args: &[DynSolValue],
+        block: BlockTag,
+    ) -> Result<Vec<DynSolValue>> {
+        let function = self.function(name).map_err(|_| MissingView(name))?;
+        let result = match self.call_raw_at(function, args, block).await {
+            Ok(result) if result.is_empty() => return Err(MissingView(name).into()),
+            Ok(result) => result,
+            Err(e) => match e.downcast_ref::<RpcError>() {
+                Some(error) if error.is_revert() => return Err(MissingView(name).into()),
+                _ => return Err(e),
+            },
+        };
+
+        Ok(function.abi_decode_output(&result, false)?)
+    }
+
+    /// Call a [`STAKE_CREDIT_VIEWS`] function returning a single amount on contract `credit`
+    async fn call_stake_credit_at(
+        &self,
+        credit: Address,
+        name: &str,
+        args: &[DynSolValue],
+        block: BlockTag,
+    ) -> Result<U256> {
+        let function = STAKE_CREDIT_VIEWS
+            .iter()
+            .map(|signature| Function::parse(signature))
+            .collect::<std::result::Result<Vec<_>, _>>()?
+            .into_iter()
+            .find(|function| function.name == name)
+            .ok_or_else(|| eyre!("Function {} not found in StakeCredit ABI", name))?;
+        let call_data = function.abi_encode_input(args)?;
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+        let result = self
+            .eth_rpc
+            .eth_call_at(&credit.to_string(), &call_data, block)
+            .await;
+        let result = recover_unprefixed_result(&function, result)?;
+        function
+            .abi_decode_output(&result, false)?
+            .first()
+            .and_then(DynSolValue::as_uint)
+            .map(|(amount, _)| amount)
+            .ok_or_else(|| eyre!("Invalid {} output from StakeCredit {}", name, credit))
+    }
+
+    /// What `delegator` contributes to the election power of the validator run by `operator`.
+    /// Fails if `operator` isn't a candidate.
+    pub async fn get_delegator_contribution(
+        &self,
+        operator: Address,
+        delegator: Address,
+    ) -> Result<DelegatorContribution> {
+        let delegator_power = self.get_delegator_power(operator, delegator).await?;
+        let (validators, _) = self.get_validator_election_info().await?;
+        let validator = validators
+            .iter()
+            .find(|v| v.operator_address == operator)
+            .ok_or_else(|| eyre!("Operator {} is not a validator candidate", operator))?;
+
+        Ok(DelegatorContribution {
+            delegator_power,
+            validator_power: validator.voting_power,
+        })
+    }
+
+    /// [`Self::get_pending_reward`] for each of `operators`, in the same order, fetched
+    /// concurrently like [`Self::get_top_validators_at_blocks`]
+    pub async fn get_pending_rewards(&self, operators: &[Address]) -> Result<Vec<U256>> {
+        stream::iter(operators.iter().copied())
+            .map(|operator| self.get_pending_reward(operator))
+            .buffered(self.history_concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Explain whether the validator with `consensus` address is elected from the latest state,
+    /// and if not, why. Works on the candidates the election sees, so a candidate the client
+    /// leaves out (see [`ElectionConfig`]) is reported as [`EligibilityReason::Excluded`].
+    pub async fn explain_eligibility(&self, consensus: Address) -> Result<EligibilityReport> {
+        let (candidates, max_elected) = self.get_election_candidates_at(BlockTag::Latest).await?;
+        let Some(validator) = candidates
+            .iter()
+            .find(|v| v.consensus_address == consensus)
+            .cloned()
+        else {
+            // Tell the candidates left out by the client from unknown addresses
+            let (validators, _) = self.get_validator_election_info().await?;
+            let report = match validators.iter().find(|v| v.consensus_address == consensus) {
+                Some(validator) => EligibilityReport {
+                    is_candidate: true,
+                    voting_power: validator.voting_power,
+                    reasons: vec![EligibilityReason::Excluded],
+                    ..Default::default()
+                },
+                None => EligibilityReport {
+                    reasons: vec![EligibilityReason::NotRegistered],
+                    ..Default::default()
+                },
+            };
+            return Ok(report);
+        };
+
+        let mut reasons = Vec::new();
+        match validator.status {
+            Some(ValidatorStatus::Jailed) => reasons.push(EligibilityReason::Jailed),
+            None | Some(ValidatorStatus::Active) => {}
+            _ => reasons.push(EligibilityReason::Excluded),
+        }
+        if validator.voting_power.is_zero() && !self.election_config.include_zero_power {
+            reasons.push(EligibilityReason::BelowMinPower);
+        }
+
+        // Rank among every eligible candidate, the way the election orders them
+        let ranking =
+            get_top_validators_by_voting_power(candidates, U256::MAX, &self.election_config);
+        let rank = ranking
+            .consensus_addrs
+            .iter()
+            .position(|address| *address == consensus)
+            .map(|index| index + 1);
+        // Paused, unbonding or short of self-stake
+        if rank.is_none() && reasons.is_empty() {
+            reasons.push(EligibilityReason::Excluded);
+        }
+        let elected = rank.is_some_and(|rank| U256::from(rank) <= max_elected);
+        if rank.is_some() && !elected {
+            reasons.push(EligibilityReason::BelowThreshold);
+        }
+
+        Ok(EligibilityReport {
+            is_candidate: true,
+            voting_power: validator.voting_power,
+            rank,
+            elected,
+            reasons,
+        })
+    }
+
+    /// Get the elected validators in the order the contract returned them, e.g. to match
+    /// on-chain event ordering
+    pub async fn get_elected_in_contract_order(&self) -> Result<ElectedValidators> {
+        let (validators, max_elected) = self.get_election_candidates_at(BlockTag::Latest).await?;
+
+        Ok(get_top_validators_in_contract_order(
+            validators,
+            max_elected,
+            &self.election_config,
+        ))
+    }
+
+    /// Candidates eligible for the election at `block`, with the number of seats
+    pub async fn get_election_candidates_at(
+        &self,
+        block: BlockTag,
+    ) -> Result<(Vec<ValidatorElectionInfo>, U256)> {
+        // Get max elected validators
+        let max_elected = self.get_max_elected_validators_at(block).await?;
+
+        // Get all validator election info
+        let (validators, _total_length) = self.get_validator_election_info_at(block).await?;
+        let validators =
+            apply_missing_pubkey_policy(validators, self.election_config.missing_pubkey)?;
+        if !self.election_config.allow_same_address {
+            reject_same_address(&validators)?;
+        }
+        let mut collisions = find_collisions(&validators);
+        if self.election_config.aggregate_by_operator {
+            // Operators running several consensus keys are what aggregation is for
+            collisions.retain(|c| c.kind != CollisionKind::OperatorAddress);
+        }
+        let validators =
+            apply_uniqueness_policy(validators, &collisions, self.election_config.duplicates)?;
+        let validators = self.read_self_stakes(validators, block).await;
+        let validators = self.apply_opt_in(validators, block).await?;
+
+        Ok((validators, max_elected))
+    }
+
+    /// Drop the candidates that didn't opt in at `block` when [`ElectionConfig::require_opt_in`]
+    async fn apply_opt_in(
+        &self,
+        validators: Vec<ValidatorElectionInfo>,
+        block: BlockTag,
+    ) -> Result<Vec<ValidatorElectionInfo>> {
+        if !self.election_config.require_opt_in {
+            return Ok(validators);
+        }
+
+        let consensus: Vec<Address> = validators.iter().map(|v| v.consensus_address).collect();
+        let opted_in: Vec<bool> = stream::iter(consensus)
+            .map(|consensus| self.has_opted_in_at(consensus, block))
+            .buffered(self.history_concurrency)
+            .try_collect()
+            .await?;
+
+        Ok(validators
+            .into_iter()
+            .zip(opted_in)
+            .filter_map(|(validator, opted_in)| {
+                if opted_in {
+                    return Some(validator);
+                }
+                warn!(
+                    "Excluding validator {}: not opted into the current epoch",
+                    validator.consensus_address
+                );
+                None
+            })
+            .collect())
+    }
+
+    /// Fill in the [`ValidatorElectionInfo::self_stake`] of every candidate at `block` when
+    /// [`ElectionConfig::min_self_stake`] is set. A candidate whose self-stake can't be read
+    /// keeps `None`, so only it is left out of the election.
+    async fn read_self_stakes(
+        &self,
+        mut validators: Vec<ValidatorElectionInfo>,
+        block: BlockTag,
+    ) -> Vec<ValidatorElectionInfo> {
+        if self.election_config.min_self_stake.is_zero() {
+            return validators;
+        }
+
+        let operators: Vec<Address> = validators.iter().map(|v| v.operator_address).collect();
+        let breakdowns: Vec<Result<DelegationBreakdown>> = stream::iter(operators)
+            .map(|operator| self.get_delegation_breakdown_at(operator, block))
+            .buffered(self.history_concurrency)
+            .collect()
+            .await;
+
+        for (validator, breakdown) in validators.iter_mut().zip(breakdowns) {
+            match breakdown {
+                Ok(breakdown) => validator.self_stake = Some(breakdown.self_delegation),
+                Err(e) => warn!(
+                    "Failed to read the self-stake of validator {} (operator {}): {:#}",
+                    validator.consensus_address, validator.operator_address, e
+                ),
+            }
+        }
+        validators
+    }
+}
+
+/// Names accepted for each base election info field, in positional order, compared lowercase
+/// without underscores
+const ELECTION_INFO_FIELD_NAMES: [&[&str]; 4] = [
+    &[
+        "consensusaddr",
+        "consensusaddrs",
+        "consensusaddress",
+        "consensusaddresses",
+    ],
+    &["votingpower", "votingpowers"],
+    &[
+        "operatoraddr",
+        "operatoraddrs",
+        "operatoraddress",
+        "operatoraddresses",
+    ],
+    &["tendermintpubkey", "tendermintpubkeys"],
+];
+
+fn normalized_name(param: &Param) -> String {
+    param.name.replace('_', "").to_lowercase()
+}
+
+/// Order of `params` putting the four base fields first, by name, then the remaining arrays,
+/// the total length and anything else in ABI order. `None` unless all four base fields are
+/// named.
+fn election_info_field_order(params: &[Param]) -> Option<Vec<usize>> {
+    let base = ELECTION_INFO_FIELD_NAMES
+        .iter()
+        .map(|names| {
+            params
+                .iter()
+                .position(|param| names.contains(&normalized_name(param).as_str()))
+        })
+        .collect::<Option<Vec<usize>>>()?;
+    let total = params
+        .iter()
+        .position(|param| normalized_name(param) == "totallength");
+
+    let (arrays, others): (Vec<usize>, Vec<usize>) = (0..params.len())
+        .filter(|i| !base.contains(i) && Some(*i) != total)
+        .partition(|&i| params[i].ty.ends_with(']'));
+    Some(
+        base.into_iter()
+            .chain(arrays)
+            .chain(total)
+            .chain(others)
+            .collect(),
+    )
+}
+
+/// Values rearranged so position `i` holds `values[order[i]]`, `order` being a permutation
+fn reorder(values: Vec<DynSolValue>, order: &[usize]) -> Vec<DynSolValue> {
+    let mut values: Vec<Option<DynSolValue>> = values.into_iter().map(Some).collect();
+    order.iter().map(|&i| values[i].take().unwrap()).collect()
+}
+
+/// Rearrange `getValidatorElectionInfo` outputs into the positional order of `layout`, matching
+/// fields by the names `function` gives them, along with the normalized names of the fields
+/// following the four base ones. Outputs the ABI doesn't name, or that don't match it, are left
+/// in place for positional decoding, without names.
+fn order_election_info_by_name(
+    function: &Function,
+    layout: ElectionInfoLayout,
+    output: Vec<DynSolValue>,
+) -> (Vec<DynSolValue>, Vec<String>) {
+    if output.len() != function.outputs.len() {
+        return (output, Vec::new());
+    }
+    let extra_names = |params: &[Param], order: &[usize]| {
+        order[4..]
+            .iter()
+            .map(|&i| normalized_name(&params[i]))
+            .collect()
     };
+    match layout {
+        ElectionInfoLayout::PackedStructs => {
+            // The struct array leads, followed by the total length
+            let Some(position) = function
+                .outputs
+                .iter()
+                .position(|param| param.ty == "tuple[]")
+            else {
+                return (output, Vec::new());
+            };
+            let order: Vec<usize> = std::iter::once(position)
+                .chain((0..output.len()).filter(|&i| i != position))
+                .collect();
+            let mut output = reorder(output, &order);
 
-    let mut elected_validators = Vec::with_capacity(top_n);
-    let mut elected_voting_powers = Vec::with_capacity(top_n);
-    let mut elected_operator_addrs = Vec::with_capacity(top_n);
-    let mut elected_tendermint_pub_keys = Vec::with_capacity(top_n);
+            let components = &function.outputs[position].components;
+            let Some(field_order) = election_info_field_order(components) else {
+                return (output, Vec::new());
+            };
+            if let DynSolValue::Array(entries) = &mut output[0] {
+                for entry in entries {
+                    if let DynSolValue::Tuple(fields)
+                    | DynSolValue::CustomStruct { tuple: fields, .. } = entry
+                    {
+                        if fields.len() == components.len() {
+                            *fields = reorder(std::mem::take(fields), &field_order);
+                        }
+                    }
+                }
+            }
+            (output, extra_names(components, &field_order))
+        }
+        ElectionInfoLayout::Auto | ElectionInfoLayout::ParallelArrays => {
+            match election_info_field_order(&function.outputs) {
+                Some(order) => (
+                    reorder(output, &order),
+                    extra_names(&function.outputs, &order),
+                ),
+                None => (output, Vec::new()),
+            }
+        }
+    }
+}
 
-    for _ in 0..top_n {
-        if let Some(validator) = validator_heap.pop() {
-            elected_validators.push(validator.consensus_address);
-            elected_voting_powers
-                .push((validator.voting_power / U256::from(10u64.pow(10))).to::<u64>());
-            elected_operator_addrs.push(validator.operator_address);
-            elected_tendermint_pub_keys.push(validator.tendermint_pub_key);
+/// Decode `getValidatorElectionInfo` outputs laid out as `layout`, `extra_names` naming the
+/// fields after the four base ones when the ABI does
+fn decode_validator_election_info(
+    output: &[DynSolValue],
+    layout: ElectionInfoLayout,
+    extra_names: &[String],
+) -> Result<(Vec<ValidatorElectionInfo>, U256)> {
+    match layout {
+        ElectionInfoLayout::PackedStructs => {
+            let entries = output
+                .first()
+                .and_then(|value| value.as_array())
+                .ok_or_else(|| eyre!("Expected an array of validator structs"))?;
+            let validators = entries
+                .iter()
+                .map(|entry| {
+                    // Structs named in the ABI decode as custom structs
+                    let fields = match entry {
+                        DynSolValue::Tuple(fields)
+                        | DynSolValue::CustomStruct { tuple: fields, .. } => Some(fields),
+                        _ => None,
+                    }
+                    .filter(|fields| fields.len() >= 4)
+                    .ok_or_else(|| eyre!("Expected a validator struct with 4 fields"))?;
+                    decode_election_info_fields(
+                        &fields[0],
+                        &fields[1],
+                        &fields[2],
+                        Some(&fields[3]),
+                        &fields[4..],
+                        extra_names,
+                    )
+                })
+                .collect::<Result<Vec<_>>>()?;
+            // The total length is optional in this layout
+            let total_length = match output.get(1) {
+                Some(value) => as_u256(value)?,
+                None => U256::from(validators.len()),
+            };
+            Ok((validators, total_length))
+        }
+        ElectionInfoLayout::Auto | ElectionInfoLayout::ParallelArrays => {
+            // The arrays carry no join key: entry `i` of every array is assumed to describe the
+            // same validator. Only equal lengths can be checked here; enable
+            // `StakeHubClient::with_operator_pairing_check` to verify the association itself.
+            // Status and active flag arrays sit between the four base arrays and the total length
+            let array_count = output.iter().take_while(|v| v.as_array().is_some()).count();
+            if array_count < 4 {
+                return Err(eyre!(
+                    "Expected 4 validator arrays, got {} outputs",
+                    output.len()
+                ));
+            }
+            let arrays: Vec<_> = output[..array_count]
+                .iter()
+                .filter_map(DynSolValue::as_array)
+                .collect();
+            let count = arrays[0].len();
+            if arrays
+                .iter()
+                .enumerate()
+                .any(|(index, array)| index != 3 && array.len() != count)
+            {
+                return Err(eyre!("Validator election info arrays differ in length"));
+            }
+            // Missing pubkeys are decoded as empty and handled by the election config
+            if arrays[3].len() > count {
+                return Err(eyre!(
+                    "Got {} tendermint pubkeys for {} validators",
+                    arrays[3].len(),
+                    count
+                ));
+            }
+            let validators = (0..count)
+                .map(|i| {
+                    decode_election_info_fields(
+                        &arrays[0][i],
+                        &arrays[1][i],
+                        &arrays[2][i],
+                        arrays[3].get(i),
+                        &arrays[4..]
+                            .iter()
+                            .map(|extra| extra[i].clone())
+                            .collect::<Vec<_>>(),
+                        extra_names,
+                    )
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let total_length = match output.get(array_count) {
+                Some(value) => as_u256(value)?,
+                None => U256::from(validators.len()),
+            };
+            Ok((validators, total_length))
         }
     }
+}
 
-    ElectedValidators {
-        consensus_addrs: elected_validators,
-        voting_powers: elected_voting_powers,
-        operator_addrs: elected_operator_addrs,
-        tendermint_pub_keys: elected_tendermint_pub_keys,
+/// Check every decoded (consensus, operator) association against the contract's pairs.
+/// Validators missing from `pairs` are reported too, since they can't be verified.
+fn check_operator_pairing(
+    validators: &[ValidatorElectionInfo],
+    pairs: &[(Address, Address)],
+) -> Result<()> {
+    let operators: HashMap<Address, Address> = pairs
+        .iter()
+        .map(|(operator, consensus)| (*consensus, *operator))
+        .collect();
+
+    let mismatches: Vec<String> = validators
+        .iter()
+        .filter(|v| operators.get(&v.consensus_address) != Some(&v.operator_address))
+        .map(|v| {
+            format!(
+                "{} -> {} (expected {})",
+                v.consensus_address,
+                v.operator_address,
+                operators
+                    .get(&v.consensus_address)
+                    .map_or_else(|| "none".to_string(), |operator| operator.to_string())
+            )
+        })
+        .collect();
+    if !mismatches.is_empty() {
+        return Err(eyre!(
+            "Validator election info arrays are mis-associated for {} validator(s): {}",
+            mismatches.len(),
+            mismatches.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+fn decode_election_info_fields(
+    consensus_address: &DynSolValue,
+    voting_power: &DynSolValue,
+    operator_address: &DynSolValue,
+    tendermint_pub_key: Option<&DynSolValue>,
+    extra: &[DynSolValue],
+    extra_names: &[String],
+) -> Result<ValidatorElectionInfo> {
+    // Optional trailing fields, by name. Unnamed or unknown ones are ignored.
+    let mut status = None;
+    let mut active = None;
+    let mut unbonding = None;
+    for (value, name) in extra.iter().zip(extra_names) {
+        match name.as_str() {
+            "status" | "statuses" => {
+                status = Some(ValidatorStatus::from(u8::try_from(as_u256(value)?)?))
+            }
+            "active" | "isactive" => active = Some(as_bool(value)?),
+            "unbonding" | "isunbonding" => unbonding = Some(as_bool(value)?),
+            _ => {}
+        }
+    }
+
+    Ok(ValidatorElectionInfo {
+        consensus_address: as_address(consensus_address)?,
+        voting_power: as_u256(voting_power)?,
+        operator_address: as_address(operator_address)?,
+        tendermint_pub_key: match tendermint_pub_key {
+            Some(value) => value
+                .as_bytes()
+                .ok_or_else(|| eyre!("Expected bytes, got {:?}", value))?
+                .to_vec(),
+            None => Vec::new(),
+        },
+        status,
+        active,
+        unbonding,
+        self_stake: None,
+    })
+}
+
+/// Decode an [`UnprefixedCallResult`] from a call to `function` by its output type, so a decimal
+/// string only counts as such for a single uint output
+fn recover_unprefixed_result(function: &Function, result: Result<Vec<u8>>) -> Result<Vec<u8>> {
+    let error = match result {
+        Ok(data) => return Ok(data),
+        Err(error) => error,
+    };
+    let Some(UnprefixedCallResult(raw)) = error.downcast_ref::<UnprefixedCallResult>() else {
+        return Err(error);
+    };
+    let single_uint = matches!(
+        function.outputs.as_slice(),
+        [output] if output.ty.starts_with("uint") && !output.ty.ends_with(']')
+    );
+    decode_unprefixed_call_result(raw, single_uint)
+}
+
+fn as_address(value: &DynSolValue) -> Result<Address> {
+    value
+        .as_address()
+        .ok_or_else(|| eyre!("Expected an address, got {:?}", value))
+}
+
+fn as_bool(value: &DynSolValue) -> Result<bool> {
+    value
+        .as_bool()
+        .ok_or_else(|| eyre!("Expected a bool, got {:?}", value))
+}
+
+fn as_u256(value: &DynSolValue) -> Result<U256> {
+    value
+        .as_uint()
+        .map(|(value, _)| value)
+        .ok_or_else(|| eyre!("Expected an unsigned integer, got {:?}", value))
+}
+
+/// Handle candidates without a tendermint pubkey according to `policy`.
+/// Such a validator could never sign, so it must not silently take a seat.
+fn apply_missing_pubkey_policy(
+    validators: Vec<ValidatorElectionInfo>,
+    policy: CandidatePolicy,
+) -> Result<Vec<ValidatorElectionInfo>> {
+    let (valid, missing): (Vec<_>, Vec<_>) = validators
+        .into_iter()
+        .partition(|v| !v.tendermint_pub_key.is_empty());
+    if missing.is_empty() {
+        return Ok(valid);
+    }
+
+    let addresses: Vec<String> = missing
+        .iter()
+        .map(|v| v.consensus_address.to_string())
+        .collect();
+    match policy {
+        CandidatePolicy::Reject => Err(eyre!(
+            "{} validator(s) have no tendermint pubkey and can't participate in consensus: {}",
+            missing.len(),
+            addresses.join(", ")
+        )),
+        CandidatePolicy::Skip => {
+            warn!(
+                "⚠️ Skipping {} validator(s) without a tendermint pubkey: {}",
+                missing.len(),
+                addresses.join(", ")
+            );
+            Ok(valid)
+        }
+    }
+}
+
+/// Handle candidates with a zero consensus or operator address according to `policy`, so they
+/// never reach a validator set
+fn apply_zero_address_policy(
+    validators: Vec<ValidatorElectionInfo>,
+    policy: CandidatePolicy,
+) -> Result<Vec<ValidatorElectionInfo>> {
+    let (valid, zero): (Vec<_>, Vec<_>) = validators
+        .into_iter()
+        .partition(|v| !v.consensus_address.is_zero() && !v.operator_address.is_zero());
+    if zero.is_empty() {
+        return Ok(valid);
+    }
+
+    let addresses: Vec<String> = zero
+        .iter()
+        .map(|v| format!("{}/{}", v.consensus_address, v.operator_address))
+        .collect();
+    match policy {
+        CandidatePolicy::Reject => Err(eyre!(
+            "{} validator(s) decoded with a zero consensus or operator address: {}",
+            zero.len(),
+            addresses.join(", ")
+        )),
+        CandidatePolicy::Skip => {
+            warn!(
+                "⚠️ Skipping {} validator(s) with a zero consensus or operator address: {}",
+                zero.len(),
+                addresses.join(", ")
+            );
+            Ok(valid)
+        }
+    }
+}
+
+/// Number of collisions listed in the error of [`validate_uniqueness`]
+const MAX_REPORTED_COLLISIONS: usize = 5;
+
+/// Validator field that must be unique across a set
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CollisionKind {
+    ConsensusAddress,
+    OperatorAddress,
+    TendermintPubKey,
+}
+
+/// Validators `first` and `duplicate` (indices into the checked slice) share a `kind` field
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Collision {
+    pub kind: CollisionKind,
+    pub first: usize,
+    pub duplicate: usize,
+}
+
+/// Find every validator sharing its consensus address, operator address or tendermint pubkey
+/// with an earlier one, in a single hashed pass. Collisions are ordered by `duplicate`.
+/// Empty pubkeys are missing rather than shared and are skipped.
+pub fn find_collisions(validators: &[ValidatorElectionInfo]) -> Vec<Collision> {
+    let mut consensus_addresses: HashMap<Address, usize> = HashMap::with_capacity(validators.len());
+    let mut operator_addresses: HashMap<Address, usize> = HashMap::with_capacity(validators.len());
+    let mut pubkeys: HashMap<&[u8], usize> = HashMap::with_capacity(validators.len());
+    let mut collisions = Vec::new();
+
+    for (index, validator) in validators.iter().enumerate() {
+        let mut record = |kind, first: Option<usize>| {
+            if let Some(first) = first {
+                collisions.push(Collision {
+                    kind,
+                    first,
+                    duplicate: index,
+                });
+            }
+        };
+        record(
+            CollisionKind::ConsensusAddress,
+            first_index(&mut consensus_addresses, validator.consensus_address, index),
+        );
+        record(
+            CollisionKind::OperatorAddress,
+            first_index(&mut operator_addresses, validator.operator_address, index),
+        );
+        if !validator.tendermint_pub_key.is_empty() {
+            record(
+                CollisionKind::TendermintPubKey,
+                first_index(&mut pubkeys, &validator.tendermint_pub_key, index),
+            );
+        }
+    }
+    collisions
+}
+
+/// Index of the first validator seen with `key`, recording `index` if there was none
+fn first_index<K: std::hash::Hash + Eq>(
+    seen: &mut HashMap<K, usize>,
+    key: K,
+    index: usize,
+) -> Option<usize> {
+    match seen.entry(key) {
+        Entry::Occupied(entry) => Some(*entry.get()),
+        Entry::Vacant(entry) => {
+            entry.insert(index);
+            None
+        }
+    }
+}
+
+/// Fail if two validators share a consensus address, operator address or tendermint pubkey,
+/// listing the first few collisions
+pub fn validate_uniqueness(validators: &[ValidatorElectionInfo]) -> Result<()> {
+    collisions_to_result(&find_collisions(validators))
+}
+
+/// Fail listing the first few of `collisions`, if any
+fn collisions_to_result(collisions: &[Collision]) -> Result<()> {
+    if collisions.is_empty() {
+        return Ok(());
+    }
+
+    let listed: Vec<String> = collisions
+        .iter()
+        .take(MAX_REPORTED_COLLISIONS)
+        .map(|c| format!("#{} and #{} share {:?}", c.first, c.duplicate, c.kind))
+        .collect();
+    Err(eyre!(
+        "{} validator collision(s): {}{}",
+        collisions.len(),
+        listed.join(", "),
+        if collisions.len() > MAX_REPORTED_COLLISIONS {
+            ", ..."
+        } else {
+            ""
+        }
+    ))
+}
+
+/// Handle the candidates of `collisions` according to `policy`
+fn apply_uniqueness_policy(
+    validators: Vec<ValidatorElectionInfo>,
+    collisions: &[Collision],
+    policy: CandidatePolicy,
+) -> Result<Vec<ValidatorElectionInfo>> {
+    if collisions.is_empty() {
+        return Ok(validators);
+    }
+    if policy == CandidatePolicy::Reject {
+        return collisions_to_result(collisions).map(|()| validators);
+    }
+
+    let colliding: BTreeSet<usize> = collisions
+        .iter()
+        .flat_map(|c| [c.first, c.duplicate])
+        .collect();
+    let addresses: Vec<String> = colliding
+        .iter()
+        .map(|&index| validators[index].consensus_address.to_string())
+        .collect();
+    warn!(
+        "⚠️ Skipping {} validator(s) sharing an address or pubkey with another: {}",
+        colliding.len(),
+        addresses.join(", ")
+    );
+    Ok(validators
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| !colliding.contains(index))
+        .map(|(_, validator)| validator)
+        .collect())
+}
+
+/// Fail if any candidate uses the same consensus and operator address
+fn reject_same_address(validators: &[ValidatorElectionInfo]) -> Result<()> {
+    let addresses: Vec<String> = validators
+        .iter()
+        .filter(|v| v.consensus_address == v.operator_address)
+        .map(|v| v.consensus_address.to_string())
+        .collect();
+    if !addresses.is_empty() {
+        return Err(eyre!(
+            "{} validator(s) use the same consensus and operator address: {}",
+            addresses.len(),
+            addresses.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Subtract `deduction` (an unbonding amount, a penalty, ...) from a validator's voting power.
+///
+/// The subtraction saturates at zero instead of underflowing. A validator brought down to zero
+/// is dropped by the election, which only considers candidates with non-zero voting power.
+pub fn deduct_voting_power(validator: &mut ValidatorElectionInfo, deduction: U256) {
+    if deduction >= validator.voting_power && !validator.voting_power.is_zero() {
+        warn!(
+            "Deduction of {} exceeds voting power {} of validator {}, saturating to zero; validator will be excluded from election",
+            deduction, validator.voting_power, validator.consensus_address
+        );
+    }
+    validator.voting_power = validator.voting_power.saturating_sub(deduction);
+}
+
+/// Hash of the full candidate list, independent of the order the contract returns it in.
+///
+/// Each candidate is encoded as
+/// `consensusAddr(20) + operatorAddr(20) + votingPower(32) + hasStatus(1) + status(1) + active(1) + unbonding(1) + hasSelfStake(1) [+ selfStake(32)] + pubkeyLen(4) + pubkey`,
+/// with integers big-endian and:
+/// - `hasStatus` 0 and `status` 0 without a status, otherwise 1 and the status code
+///   (active 0, inactive 1, jailed 2, or the unknown code itself)
+/// - `active` and `unbonding` 0 when unknown, 1 for false and 2 for true
+/// - `hasSelfStake` 0 without a self-stake, otherwise 1 followed by the 32-byte `selfStake`
+///
+/// The records are sorted and the hash is keccak256 over their concatenation.
+pub fn candidates_fingerprint(candidates: &[ValidatorElectionInfo]) -> B256 {
+    let mut records: Vec<Vec<u8>> = candidates
+        .iter()
+        .map(|c| {
+            let mut record = Vec::with_capacity(113 + c.tendermint_pub_key.len());
+            record.extend_from_slice(c.consensus_address.as_slice());
+            record.extend_from_slice(c.operator_address.as_slice());
+            record.extend_from_slice(&c.voting_power.to_be_bytes::<32>());
+            record.extend_from_slice(&match c.status {
+                None => [0, 0],
+                Some(ValidatorStatus::Active) => [1, 0],
+                Some(ValidatorStatus::Inactive) => [1, 1],
+                Some(ValidatorStatus::Jailed) => [1, 2],
+                Some(ValidatorStatus::Unknown(code)) => [1, code],
+            });
+            for flag in [c.active, c.unbonding] {
+                record.push(match flag {
+                    None => 0,
+                    Some(false) => 1,
+                    Some(true) => 2,
+                });
+            }
+            match c.self_stake {
+                None => record.push(0),
+                Some(self_stake) => {
+                    record.push(1);
+                    record.extend_from_slice(&self_stake.to_be_bytes::<32>());
+                }
+            }
+            record.extend_from_slice(&(c.tendermint_pub_key.len() as u32).to_be_bytes());
+            record.extend_from_slice(&c.tendermint_pub_key);
+            record
+        })
+        .collect();
+    records.sort_unstable();
+    keccak256(records.concat())
+}
+
+/// Apply per-validator deductions, keyed by consensus address, with [`deduct_voting_power`]
+pub fn apply_voting_power_deductions(
+    validators: &mut [ValidatorElectionInfo],
+    deductions: &HashMap<Address, U256>,
+) {
+    for validator in validators.iter_mut() {
+        if let Some(deduction) = deductions.get(&validator.consensus_address) {
+            deduct_voting_power(validator, *deduction);
+        }
+    }
+}
+
+/// Get top validators by voting power using binary heap.
+/// Pure selection step of [`StakeHubClient::get_top_validators_by_voting_power`], exposed for
+/// benchmarks.
+pub fn get_top_validators_by_voting_power(
+    validators: Vec<ValidatorElectionInfo>,
+    max_elected: U256,
+    config: &ElectionConfig,
+) -> ElectedValidators {
+    let elected = collect_elected(
+        select_candidates(validators, max_elected, config),
+        config.power_model,
+    );
+    // A custom score or aggregated power may rank a validator above one with more power
+    if config.score_fn.is_none() && !config.aggregate_by_operator {
+        debug_assert!(
+            elected.validate_ordering().is_ok(),
+            "{:?}",
+            elected.validate_ordering()
+        );
+    }
+    elected
+}
+
+/// Elect the same validators as [`get_top_validators_by_voting_power`], but keep them in the
+/// order the contract returned them instead of by descending power
+pub fn get_top_validators_in_contract_order(
+    validators: Vec<ValidatorElectionInfo>,
+    max_elected: U256,
+    config: &ElectionConfig,
+) -> ElectedValidators {
+    let mut selected = select_candidates(validators, max_elected, config);
+    selected.sort_by_key(|candidate| candidate.index);
+    collect_elected(selected, config.power_model)
+}
+
+/// Whether `validator` self-stakes at least `min_self_stake`, logging it when it doesn't
+fn meets_min_self_stake(validator: &ValidatorElectionInfo, min_self_stake: U256) -> bool {
+    if min_self_stake.is_zero() {
+        return true;
+    }
+    match validator.self_stake {
+        Some(self_stake) if self_stake >= min_self_stake => true,
+        Some(self_stake) => {
+            warn!(
+                "Excluding validator {} (operator {}): self-stake {} is below the minimum {}",
+                validator.consensus_address, validator.operator_address, self_stake, min_self_stake
+            );
+            false
+        }
+        None => {
+            warn!(
+                "Excluding validator {} (operator {}): self-stake unknown, minimum is {}",
+                validator.consensus_address, validator.operator_address, min_self_stake
+            );
+            false
+        }
+    }
+}
+
+/// The value of a decoded `maxElectedValidators` output
+fn decode_max_elected(output: &[DynSolValue]) -> Result<U256> {
+    output
+        .first()
+        .and_then(DynSolValue::as_uint)
+        .map(|(max_elected, _)| max_elected)
+        .ok_or_else(|| eyre!("Invalid maxElectedValidators output"))
+}
+
+/// Keep only the first `max_candidates` of `validators`, warning if the contract reported more
+/// through either the decoded list or `total_length`
+fn cap_candidates(
+    validators: &mut Vec<ValidatorElectionInfo>,
+    total_length: U256,
+    max_candidates: usize,
+) {
+    let reported = U256::from(validators.len()).max(total_length);
+    if reported <= U256::from(max_candidates) {
+        return;
+    }
+    warn!(
+        "⚠️ StakeHub returned {} candidates, more than the cap of {}; only the first {} are considered. Check the StakeHub contract!",
+        reported, max_candidates, max_candidates
+    );
+    validators.truncate(max_candidates);
+}
+
+/// The `max_elected` best eligible candidates, best first
+fn select_candidates(
+    mut validators: Vec<ValidatorElectionInfo>,
+    max_elected: U256,
+    config: &ElectionConfig,
+) -> Vec<ScoredCandidate> {
+    // Also caps input that didn't come through the client
+    cap_candidates(&mut validators, U256::ZERO, config.max_candidates);
+    let mut validator_heap: BinaryHeap<ScoredCandidate> = BinaryHeap::new();
+
+    // Zero-power candidates sort below every staked one, so they only fill leftover seats.
+    // Candidates with a reported status are only eligible while active.
+    let eligible: Vec<(usize, ValidatorElectionInfo)> = validators
+        .into_iter()
+        .enumerate()
+        .filter(|(_, validator)| {
+            let active = matches!(validator.status, None | Some(ValidatorStatus::Active));
+            let unpaused = config.include_paused || validator.active != Some(false);
+            let bonded = !config.exclude_unbonding || validator.unbonding != Some(true);
+            active
+                && unpaused
+                && bonded
+                && (config.include_zero_power || validator.voting_power > U256::ZERO)
+                && meets_min_self_stake(validator, config.min_self_stake)
+        })
+        .collect();
+
+    let mut operator_powers: HashMap<Address, U256> = HashMap::new();
+    if config.aggregate_by_operator {
+        for (_, validator) in &eligible {
+            let power = operator_powers
+                .entry(validator.operator_address)
+                .or_default();
+            *power = power.saturating_add(validator.voting_power);
+        }
+    }
+
+    for (index, validator) in eligible {
+        let score = match operator_powers.get(&validator.operator_address) {
+            Some(&aggregated) => match &config.score_fn {
+                Some(score_fn) => score_fn(&ValidatorElectionInfo {
+                    voting_power: aggregated,
+                    ..validator.clone()
+                }),
+                None => aggregated,
+            },
+            None => match &config.score_fn {
+                Some(score_fn) => score_fn(&validator),
+                None => validator.voting_power,
+            },
+        };
+        validator_heap.push(ScoredCandidate {
+            score,
+            index,
+            validator,
+        });
+    }
+
+    // Never size anything by `max_elected` itself, which may be as large as `U256::MAX`
+    let top_n = max_elected
+        .saturating_to::<usize>()
+        .min(validator_heap.len());
+
+    let mut selected = Vec::with_capacity(top_n);
+    for _ in 0..top_n {
+        if let Some(candidate) = validator_heap.pop() {
+            selected.push(candidate);
+        }
+    }
+    selected
+}
+
+/// Convert selected candidates to [`ElectedValidators`], keeping their order
+fn collect_elected(selected: Vec<ScoredCandidate>, power_model: PowerModel) -> ElectedValidators {
+    let mut elected_validators = Vec::with_capacity(selected.len());
+    let mut elected_voting_powers = Vec::with_capacity(selected.len());
+    let mut elected_operator_addrs = Vec::with_capacity(selected.len());
+    let mut elected_tendermint_pub_keys = Vec::with_capacity(selected.len());
+    let mut elected_raw_stakes = Vec::with_capacity(selected.len());
+
+    for ScoredCandidate { validator, .. } in selected {
+        elected_validators.push(validator.consensus_address);
+        elected_voting_powers.push(power_model.voting_power(validator.voting_power));
+        elected_operator_addrs.push(validator.operator_address);
+        elected_tendermint_pub_keys.push(validator.tendermint_pub_key);
+        elected_raw_stakes.push(validator.voting_power);
+    }
+
+    ElectedValidators {
+        consensus_addrs: elected_validators,
+        voting_powers: elected_voting_powers,
+        operator_addrs: elected_operator_addrs,
+        tendermint_pub_keys: elected_tendermint_pub_keys,
+        raw_stakes: elected_raw_stakes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::DEVNET_CHAIN_ID;
+    use crate::test_utils::{
+        encode_stake_hub_output, mock_stake_hub, set_candidates, set_epoch_length, set_max_elected,
+        stake_hub_abi, stake_hub_selector, validator_candidate, MockCaller,
+    };
+
+    fn candidate(byte: u8, voting_power: U256) -> ValidatorElectionInfo {
+        ValidatorElectionInfo {
+            consensus_address: Address::repeat_byte(byte),
+            voting_power,
+            operator_address: Address::repeat_byte(byte.wrapping_add(0x80)),
+            tendermint_pub_key: vec![byte; 32],
+            status: None,
+            active: None,
+            unbonding: None,
+            self_stake: None,
+        }
+    }
+
+    fn elected_set(count: u8) -> ElectedValidators {
+        let candidates = (1..=count)
+            .map(|i| candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10))))
+            .collect();
+        get_top_validators_by_voting_power(
+            candidates,
+            U256::from(count as u64),
+            &ElectionConfig::default(),
+        )
+    }
+
+    #[test]
+    fn test_inclusion_proof_verifies_against_root() {
+        let elected = elected_set(5);
+        let root = elected.merkle_root();
+
+        for validator in elected.iter() {
+            let proof = elected.inclusion_proof(validator.operator_address).unwrap();
+            assert!(merkle::verify_inclusion_proof(
+                root,
+                validator.leaf_hash(),
+                &proof
+            ));
+        }
+
+        assert!(elected
+            .inclusion_proof(Address::repeat_byte(0xff))
+            .is_none());
+    }
+
+    #[test]
+    fn test_tampered_inclusion_proof_is_rejected() {
+        let elected = elected_set(5);
+        let root = elected.merkle_root();
+        let mut validator = elected.get(0).unwrap();
+        let proof = elected.inclusion_proof(validator.operator_address).unwrap();
+
+        let mut tampered_proof = proof.clone();
+        tampered_proof.siblings[0] = B256::repeat_byte(0xaa);
+        assert!(!merkle::verify_inclusion_proof(
+            root,
+            validator.leaf_hash(),
+            &tampered_proof
+        ));
+
+        let mut wrong_index = proof.clone();
+        wrong_index.index ^= 1;
+        assert!(!merkle::verify_inclusion_proof(
+            root,
+            validator.leaf_hash(),
+            &wrong_index
+        ));
+
+        validator.voting_power += 1;
+        assert!(!merkle::verify_inclusion_proof(
+            root,
+            validator.leaf_hash(),
+            &proof
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_epoch_length_below_minimum_is_rejected() {
+        let caller = Arc::new(MockCaller::new());
+        set_epoch_length(&caller, 1);
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+
+        let err = client.get_epoch_length().await.unwrap_err();
+        assert!(err.to_string().contains("below the configured minimum"));
+
+        let client = client.with_min_epoch_length(0);
+        assert_eq!(client.get_epoch_length().await.unwrap(), 1);
+
+        set_epoch_length(&caller, DEFAULT_MIN_EPOCH_LENGTH);
+        let client = client.with_min_epoch_length(DEFAULT_MIN_EPOCH_LENGTH);
+        assert_eq!(
+            client.get_epoch_length().await.unwrap(),
+            DEFAULT_MIN_EPOCH_LENGTH
+        );
+    }
+
+    #[tokio::test]
+    async fn test_top_validators_at_state_root() {
+        let candidates: Vec<_> = (1..=5).map(|i| validator_candidate(i, i as u64)).collect();
+        let caller = Arc::new(mock_stake_hub(3, &candidates));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+
+        let root = B256::repeat_byte(0xab);
+        let elected = client.get_top_validators_at_state_root(root).await.unwrap();
+        let latest = client.get_top_validators_by_voting_power().await.unwrap();
+        assert_eq!(elected.consensus_addrs, latest.consensus_addrs);
+        assert_eq!(elected.voting_powers, latest.voting_powers);
+
+        let blocks = caller.call_blocks();
+        let at_root = blocks
+            .iter()
+            .take_while(|block| **block == BlockTag::StateRoot(root))
+            .count();
+        assert!(at_root > 0);
+        assert!(blocks[at_root..]
+            .iter()
+            .all(|block| *block == BlockTag::Latest));
+    }
+
+    #[tokio::test]
+    async fn test_all_registered_validators_include_unelected() {
+        let candidates: Vec<_> = (1..=5).map(|i| validator_candidate(i, i as u64)).collect();
+        let caller = Arc::new(mock_stake_hub(3, &candidates));
+        let client = StakeHubClient::with_caller(caller, Address::ZERO).unwrap();
+
+        let elected = client.get_top_validators_by_voting_power().await.unwrap();
+        let registered = client.get_all_registered_validators().await.unwrap();
+        assert_eq!(elected.len(), 3);
+        assert_eq!(registered.len(), 5);
+        assert_eq!(
+            registered
+                .iter()
+                .map(|v| v.consensus_address)
+                .collect::<Vec<_>>(),
+            (1..=5).rev().map(Address::repeat_byte).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_change_pending() {
+        let caller = Arc::new(MockCaller::new());
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        for pending in [true, false] {
+            caller.set_response(
+                stake_hub_selector("validatorSetChangePending"),
+                encode_stake_hub_output("validatorSetChangePending", &[DynSolValue::Bool(pending)]),
+            );
+            assert_eq!(client.is_set_change_pending().await.unwrap(), pending);
+        }
+    }
+
+    /// Proxy answering every call with the same unprefixed result
+    struct UnprefixedProxy(String);
+
+    #[async_trait::async_trait]
+    impl ContractCaller for UnprefixedProxy {
+        async fn eth_call(&self, _to: &str, _data: &[u8]) -> Result<Vec<u8>> {
+            Err(UnprefixedCallResult(self.0.clone()).into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decimal_results_only_decode_for_a_single_uint() {
+        let client = |result: &str| {
+            StakeHubClient::with_caller(
+                Arc::new(UnprefixedProxy(result.to_string())),
+                Address::ZERO,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(client("200").get_epoch_length().await.unwrap(), 200);
+        // A whole ABI word of digits is hex
+        let word = format!("{:064x}", 0x200);
+        assert_eq!(client(&word).get_epoch_length().await.unwrap(), 0x200);
+        // Other outputs never read decimals
+        assert!(client("200").get_validator_election_info().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_epoch_length_history_reports_changes() {
+        let caller = Arc::new(MockCaller::new());
+        set_epoch_length(&caller, 100);
+        for (block, epoch_length) in [(300, 200), (400, 200), (500, 50)] {
+            caller.set_response_at(
+                block,
+                stake_hub_selector("epochLength"),
+                encode_stake_hub_output(
+                    "epochLength",
+                    &[DynSolValue::from(U256::from(epoch_length))],
+                ),
+            );
+        }
+        let client = StakeHubClient::with_caller(caller, Address::ZERO).unwrap();
+
+        let history = client
+            .epoch_length_history(&[100, 200, 300, 400, 500, 600])
+            .await
+            .unwrap();
+        assert_eq!(history, vec![(100, 100), (300, 200), (500, 50), (600, 100)]);
+        assert!(client.epoch_length_history(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_max_elected_output_is_an_error() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        // An ABI whose maxElectedValidators declares no output decodes to nothing
+        let mut abi = stake_hub_abi();
+        for function in abi.functions.get_mut("maxElectedValidators").unwrap() {
+            function.outputs.clear();
+        }
+        let client = StakeHubClient::with_caller(caller, Address::ZERO)
+            .unwrap()
+            .with_stake_hub_abi(abi);
+
+        for block in [BlockTag::Latest, BlockTag::Number(5)] {
+            let err = client
+                .get_max_elected_validators_at(block)
+                .await
+                .unwrap_err();
+            assert!(err
+                .to_string()
+                .contains("Invalid maxElectedValidators output"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validator_count() {
+        let candidates: Vec<_> = (1..=3).map(|i| validator_candidate(i, 10)).collect();
+        let caller = Arc::new(mock_stake_hub(10, &candidates));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let selector = stake_hub_selector("getValidatorCount");
+
+        // A failing count getter doesn't trigger the heavy download
+        assert!(client.get_validator_count().await.is_err());
+        assert_eq!(
+            caller.call_count(stake_hub_selector("getValidatorElectionInfo")),
+            0
+        );
+
+        // Without the count getter the election info is fetched
+        let reverted = Arc::new(mock_stake_hub(10, &candidates));
+        reverted.set_error(
+            selector,
+            RpcError {
+                code: -32000,
+                message: "execution reverted".to_string(),
+            },
+        );
+        let empty = Arc::new(mock_stake_hub(10, &candidates));
+        empty.set_response(selector, Vec::new());
+        for missing in [reverted, empty] {
+            let client = StakeHubClient::with_caller(missing.clone(), Address::ZERO).unwrap();
+            assert_eq!(client.get_validator_count().await.unwrap(), 3);
+            assert_eq!(
+                missing.call_count(stake_hub_selector("getValidatorElectionInfo")),
+                1
+            );
+        }
+
+        caller.set_response(
+            stake_hub_selector("getValidatorCount"),
+            encode_stake_hub_output("getValidatorCount", &[DynSolValue::from(U256::from(42))]),
+        );
+        assert_eq!(client.get_validator_count().await.unwrap(), 42);
+        assert_eq!(
+            caller.call_count(stake_hub_selector("getValidatorElectionInfo")),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_onchain_set_hash() {
+        let candidates: Vec<_> = (1..=3).map(|i| validator_candidate(i, 10)).collect();
+        let caller = Arc::new(mock_stake_hub(10, &candidates));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let elected = client.get_top_validators_by_voting_power().await.unwrap();
+
+        // Deployments without the view
+        assert!(client.get_onchain_set_hash().await.is_err());
+
+        let set_hash = |hash: B256| {
+            caller.set_response(
+                stake_hub_selector("validatorSetHash"),
+                encode_stake_hub_output("validatorSetHash", &[DynSolValue::FixedBytes(hash, 32)]),
+            )
+        };
+        set_hash(elected.merkle_root());
+        assert_eq!(
+            client.get_onchain_set_hash().await.unwrap(),
+            elected.merkle_root()
+        );
+        assert!(client.verify_onchain_set_hash(&elected).await.unwrap());
+
+        set_hash(B256::repeat_byte(0xab));
+        assert!(!client.verify_onchain_set_hash(&elected).await.unwrap());
+    }
+
+    #[test]
+    fn test_proposer_schedule_follows_weighted_round_robin() {
+        let elected = get_top_validators_by_voting_power(
+            (1..=3).map(|i| validator_candidate(i, i as u64)).collect(),
+            U256::from(3),
+            &ElectionConfig::default(),
+        );
+        assert_eq!(elected.voting_powers, vec![3, 2, 1]);
+
+        let schedule: Vec<u8> = elected
+            .proposer_schedule(12)
+            .iter()
+            .map(|address| address[0])
+            .collect();
+        // Each window of 6 heights gives every validator as many slots as its power
+        assert_eq!(schedule, vec![3, 2, 1, 3, 2, 3, 3, 2, 1, 3, 2, 3]);
+
+        // Equal powers rotate by address
+        let equal = get_top_validators_by_voting_power(
+            (1..=3).map(|i| validator_candidate(i, 1)).collect(),
+            U256::from(3),
+            &ElectionConfig::default(),
+        );
+        let schedule: Vec<u8> = equal
+            .proposer_schedule(6)
+            .iter()
+            .map(|address| address[0])
+            .collect();
+        assert_eq!(schedule, vec![1, 2, 3, 1, 2, 3]);
+        assert!(ElectedValidators::default().proposer_schedule(3).is_empty());
+    }
+
+    #[test]
+    fn test_select_proposer_tracks_voting_power() {
+        let elected = get_top_validators_by_voting_power(
+            (1..=3).map(|i| validator_candidate(i, i as u64)).collect(),
+            U256::from(3),
+            &ElectionConfig::default(),
+        );
+        // The election order doesn't matter
+        let mut reordered = elected.clone();
+        reordered.consensus_addrs.reverse();
+        reordered.voting_powers.reverse();
+        reordered.operator_addrs.reverse();
+        reordered.tendermint_pub_keys.reverse();
+        reordered.raw_stakes.reverse();
+
+        let mut counts = HashMap::new();
+        for height in 0..3000 {
+            for round in 0..2 {
+                let proposer = elected.select_proposer(height, round);
+                assert_eq!(proposer, elected.select_proposer(height, round));
+                assert_eq!(proposer, reordered.select_proposer(height, round));
+                *counts.entry(proposer[0]).or_insert(0u32) += 1;
+            }
+        }
+        // 6000 picks split 3:2:1, within 10%
+        for (byte, expected) in [(3, 3000), (2, 2000), (1, 1000)] {
+            let count = counts[&byte];
+            assert!(
+                count.abs_diff(expected) < expected / 10,
+                "validator {} proposed {} times, expected about {}",
+                byte,
+                count,
+                expected
+            );
+        }
+
+        assert_eq!(
+            ElectedValidators::default().select_proposer(1, 0),
+            Address::ZERO
+        );
+    }
+
+    #[test]
+    fn test_verify_validator_membership() {
+        let elected = elected_set(5);
+        let root = elected.merkle_root();
+        let mut validator = elected.get(2).unwrap();
+        let proof = elected.inclusion_proof(validator.operator_address).unwrap();
+
+        assert!(verify_validator_membership(
+            root,
+            &validator,
+            &proof.siblings,
+            proof.index
+        ));
+
+        validator.tendermint_pub_key[0] ^= 1;
+        assert!(!verify_validator_membership(
+            root,
+            &validator,
+            &proof.siblings,
+            proof.index
+        ));
+    }
+
+    #[test]
+    fn test_penalty_exceeding_power_saturates_and_drops_validator() {
+        let power = U256::from(5u64) * U256::from(10u64.pow(10));
+        let mut validators = vec![candidate(1, power), candidate(2, power)];
+
+        let deductions = HashMap::from([(Address::repeat_byte(1), power + U256::from(1u64))]);
+        apply_voting_power_deductions(&mut validators, &deductions);
+
+        assert_eq!(validators[0].voting_power, U256::ZERO);
+        assert_eq!(validators[1].voting_power, power);
+
+        let elected = get_top_validators_by_voting_power(
+            validators,
+            U256::from(10u64),
+            &ElectionConfig::default(),
+        );
+        assert_eq!(elected.consensus_addrs, vec![Address::repeat_byte(2)]);
+        assert_eq!(elected.voting_powers, vec![5]);
+    }
+
+    #[tokio::test]
+    async fn test_max_elected_is_cached_within_ttl() {
+        let candidates: Vec<_> = (1..=3)
+            .map(|i| candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10))))
+            .collect();
+        let caller = Arc::new(mock_stake_hub(2, &candidates));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO)
+            .unwrap()
+            .with_max_elected_cache_ttl(Duration::from_secs(3600));
+
+        for _ in 0..3 {
+            let elected = client.get_top_validators_by_voting_power().await.unwrap();
+            assert_eq!(elected.len(), 2);
+        }
+
+        let max_elected = stake_hub_selector("maxElectedValidators");
+        let election_info = stake_hub_selector("getValidatorElectionInfo");
+        assert_eq!(caller.call_count(max_elected), 1);
+        assert_eq!(caller.call_count(election_info), 3);
+
+        client.invalidate_max_elected_cache();
+        client.get_top_validators_by_voting_power().await.unwrap();
+        assert_eq!(caller.call_count(max_elected), 2);
+    }
+
+    #[tokio::test]
+    async fn test_absurd_max_elected_is_clamped_to_cap() {
+        let candidates: Vec<_> = (1..=3)
+            .map(|i| candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10))))
+            .collect();
+        let caller = Arc::new(mock_stake_hub(10u64.pow(18), &candidates));
+
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO)
+            .unwrap()
+            .with_max_elected_cap(2);
+        assert_eq!(
+            client.get_max_elected_validators().await.unwrap(),
+            U256::from(2)
+        );
+        let elected = client.get_top_validators_by_voting_power().await.unwrap();
+        assert_eq!(elected.len(), 2);
+
+        let strict = StakeHubClient::with_caller(caller, Address::ZERO)
+            .unwrap()
+            .with_strict_max_elected(true);
+        assert!(strict.get_max_elected_validators().await.is_err());
+    }
+
+    /// Client over a StakeHub variant returning election info as a `struct[]`, with a
+    /// trailing status field when `with_status` is set
+    fn packed_stake_hub(candidates: &[ValidatorElectionInfo], with_status: bool) -> StakeHubClient {
+        // Signatures can't name struct fields, so this one is spelled out as JSON
+        let param = |name: &str, ty: &str| json!({"name": name, "type": ty, "internalType": ty});
+        let mut components = vec![
+            param("consensusAddr", "address"),
+            param("votingPower", "uint256"),
+            param("operatorAddr", "address"),
+            param("tendermintPubKey", "bytes"),
+        ];
+        if with_status {
+            components.push(param("status", "uint8"));
+        }
+        let packed_function: Function = serde_json::from_value(json!({
+            "type": "function",
+            "name": "getValidatorElectionInfo",
+            "inputs": [param("offset", "uint256"), param("limit", "uint256")],
+            "outputs": [
+                {
+                    "name": "validators",
+                    "type": "tuple[]",
+                    "internalType": "struct StakeHub.ValidatorElectionInfo[]",
+                    "components": components,
+                },
+                param("totalLength", "uint256"),
+            ],
+            "stateMutability": "view",
+        }))
+        .unwrap();
+        let output = packed_function
+            .abi_encode_output(&[
+                DynSolValue::Array(
+                    candidates
+                        .iter()
+                        .map(|c| {
+                            let mut fields = vec![
+                                DynSolValue::from(c.consensus_address),
+                                DynSolValue::from(c.voting_power),
+                                DynSolValue::from(c.operator_address),
+                                DynSolValue::Bytes(c.tendermint_pub_key.clone()),
+                            ];
+                            if with_status {
+                                let status = match c.status.unwrap() {
+                                    ValidatorStatus::Active => 0u8,
+                                    ValidatorStatus::Inactive => 1,
+                                    ValidatorStatus::Jailed => 2,
+                                    ValidatorStatus::Unknown(status) => status,
+                                };
+                                fields.push(DynSolValue::Uint(U256::from(status), 8));
+                            }
+                            DynSolValue::Tuple(fields)
+                        })
+                        .collect(),
+                ),
+                DynSolValue::from(U256::from(candidates.len())),
+            ])
+            .unwrap();
+        let caller = MockCaller::new();
+        caller.set_response(stake_hub_selector("getValidatorElectionInfo"), output);
+        set_max_elected(&caller, 10);
+
+        let mut abi = stake_hub_abi();
+        abi.functions
+            .insert(packed_function.name.clone(), vec![packed_function]);
+        StakeHubClient::with_caller(Arc::new(caller), Address::ZERO)
+            .unwrap()
+            .with_stake_hub_abi(abi)
+    }
+
+    #[tokio::test]
+    async fn test_packed_struct_layout_decodes_like_parallel_arrays() {
+        let candidates: Vec<_> = (1..=3)
+            .map(|i| candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10))))
+            .collect();
+        let parallel =
+            StakeHubClient::with_caller(Arc::new(mock_stake_hub(3, &candidates)), Address::ZERO)
+                .unwrap();
+        let packed = packed_stake_hub(&candidates, false);
+
+        let expected = parallel.get_validator_election_info().await.unwrap();
+        assert_eq!(expected.0, candidates);
+        assert_eq!(
+            packed.get_validator_election_info().await.unwrap(),
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn test_election_info_fields_are_matched_by_name() {
+        let candidates: Vec<_> = (1..=3)
+            .map(|i| candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10))))
+            .collect();
+        let array = |f: fn(&ValidatorElectionInfo) -> DynSolValue| {
+            DynSolValue::Array(candidates.iter().map(f).collect())
+        };
+        let client = |function: Function, output: Vec<u8>| {
+            let caller = MockCaller::new();
+            caller.set_response(stake_hub_selector("getValidatorElectionInfo"), output);
+            let mut abi = stake_hub_abi();
+            abi.functions.insert(function.name.clone(), vec![function]);
+            StakeHubClient::with_caller(Arc::new(caller), Address::ZERO)
+                .unwrap()
+                .with_stake_hub_abi(abi)
+        };
+
+        let parallel = Function::parse(
+            "function getValidatorElectionInfo(uint256 offset, uint256 limit) view returns (uint256 totalLength, bytes[] tendermintPubKeys, address[] operatorAddrs, uint256[] votingPowers, address[] consensusAddrs)",
+        )
+        .unwrap();
+        let output = parallel
+            .abi_encode_output(&[
+                DynSolValue::from(U256::from(7)),
+                array(|c| DynSolValue::Bytes(c.tendermint_pub_key.clone())),
+                array(|c| DynSolValue::from(c.operator_address)),
+                array(|c| DynSolValue::from(c.voting_power)),
+                array(|c| DynSolValue::from(c.consensus_address)),
+            ])
+            .unwrap();
+        assert_eq!(
+            client(parallel, output)
+                .get_validator_election_info()
+                .await
+                .unwrap(),
+            (candidates.clone(), U256::from(7))
+        );
+
+        // Signatures can't name struct fields, so this one is spelled out as JSON
+        let param = |name: &str, ty: &str| json!({"name": name, "type": ty, "internalType": ty});
+        let packed: Function = serde_json::from_value(json!({
+            "type": "function",
+            "name": "getValidatorElectionInfo",
+            "inputs": [param("offset", "uint256"), param("limit", "uint256")],
+            "outputs": [
+                param("totalLength", "uint256"),
+                {
+                    "name": "validators",
+                    "type": "tuple[]",
+                    "internalType": "struct StakeHub.ValidatorElectionInfo[]",
+                    "components": [
+                        param("votingPower", "uint256"),
+                        param("tendermintPubKey", "bytes"),
+                        param("consensusAddr", "address"),
+                        param("operatorAddr", "address"),
+                    ],
+                },
+            ],
+            "stateMutability": "view",
+        }))
+        .unwrap();
+        let output = packed
+            .abi_encode_output(&[
+                DynSolValue::from(U256::from(7)),
+                DynSolValue::Array(
+                    candidates
+                        .iter()
+                        .map(|c| {
+                            DynSolValue::Tuple(vec![
+                                DynSolValue::from(c.voting_power),
+                                DynSolValue::Bytes(c.tendermint_pub_key.clone()),
+                                DynSolValue::from(c.consensus_address),
+                                DynSolValue::from(c.operator_address),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ])
+            .unwrap();
+        assert_eq!(
+            client(packed, output)
+                .get_validator_election_info()
+                .await
+                .unwrap(),
+            (candidates.clone(), U256::from(7))
+        );
+
+        // Optional fields are told apart by name only, and unknown ones are ignored
+        let extras = Function::parse(
+            "function getValidatorElectionInfo(uint256 offset, uint256 limit) view returns (address[] consensusAddrs, uint256[] votingPowers, address[] operatorAddrs, bytes[] tendermintPubKeys, bool[] slashed, uint8[] statuses, bool[] isActive, uint256[] commission, uint256 totalLength)",
+        )
+        .unwrap();
+        let extra_output = |function: &Function| {
+            function
+                .abi_encode_output(&[
+                    array(|c| DynSolValue::from(c.consensus_address)),
+                    array(|c| DynSolValue::from(c.voting_power)),
+                    array(|c| DynSolValue::from(c.operator_address)),
+                    array(|c| DynSolValue::Bytes(c.tendermint_pub_key.clone())),
+                    array(|_| DynSolValue::Bool(true)),
+                    array(|_| DynSolValue::Uint(U256::from(2), 8)),
+                    array(|_| DynSolValue::Bool(false)),
+                    array(|_| DynSolValue::from(U256::from(500))),
+                    DynSolValue::from(U256::from(3)),
+                ])
+                .unwrap()
+        };
+        let output = extra_output(&extras);
+        let (decoded, _) = client(extras, output)
+            .get_validator_election_info()
+            .await
+            .unwrap();
+        let expected: Vec<_> = candidates
+            .iter()
+            .map(|c| ValidatorElectionInfo {
+                status: Some(ValidatorStatus::Jailed),
+                active: Some(false),
+                ..c.clone()
+            })
+            .collect();
+        assert_eq!(decoded, expected);
+
+        let unnamed = Function::parse(
+            "function getValidatorElectionInfo(uint256, uint256) view returns (address[], uint256[], address[], bytes[], bool[], uint8[], bool[], uint256[], uint256)",
+        )
+        .unwrap();
+        let output = extra_output(&unnamed);
+        let (decoded, _) = client(unnamed, output)
+            .get_validator_election_info()
+            .await
+            .unwrap();
+        assert_eq!(decoded, candidates);
+    }
+
+    #[tokio::test]
+    async fn test_only_active_validators_are_elected() {
+        let statuses = [
+            ValidatorStatus::Active,
+            ValidatorStatus::Jailed,
+            ValidatorStatus::Active,
+            ValidatorStatus::Inactive,
+            ValidatorStatus::Unknown(7),
+        ];
+        let candidates: Vec<_> = statuses
+            .iter()
+            .zip(1u8..)
+            .map(|(status, i)| ValidatorElectionInfo {
+                status: Some(*status),
+                ..candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10)))
+            })
+            .collect();
+        let client = packed_stake_hub(&candidates, true);
+
+        let (decoded, _) = client.get_validator_election_info().await.unwrap();
+        assert_eq!(decoded, candidates);
+
+        let elected = client.get_top_validators_by_voting_power().await.unwrap();
+        assert_eq!(
+            elected.consensus_addrs,
+            vec![Address::repeat_byte(3), Address::repeat_byte(1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_paused_validators_are_excluded() {
+        let function = Function::parse(
+            "function getValidatorElectionInfo(uint256 offset, uint256 limit) view returns (address[] consensusAddrs, uint256[] votingPowers, address[] operatorAddrs, bytes[] tendermintPubKeys, bool[] active, uint256 totalLength)",
+        )
+        .unwrap();
+        let candidates: Vec<_> = [true, false, true]
+            .into_iter()
+            .zip(1u8..)
+            .map(|(active, i)| ValidatorElectionInfo {
+                active: Some(active),
+                ..candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10)))
+            })
+            .collect();
+        let column = |f: fn(&ValidatorElectionInfo) -> DynSolValue| {
+            DynSolValue::Array(candidates.iter().map(f).collect())
+        };
+        let output = function
+            .abi_encode_output(&[
+                column(|c| DynSolValue::from(c.consensus_address)),
+                column(|c| DynSolValue::from(c.voting_power)),
+                column(|c| DynSolValue::from(c.operator_address)),
+                column(|c| DynSolValue::Bytes(c.tendermint_pub_key.clone())),
+                column(|c| DynSolValue::Bool(c.active.unwrap())),
+                DynSolValue::from(U256::from(candidates.len())),
+            ])
+            .unwrap();
+        let caller = MockCaller::new();
+        caller.set_response(stake_hub_selector("getValidatorElectionInfo"), output);
+        set_max_elected(&caller, 10);
+        let mut abi = stake_hub_abi();
+        abi.functions.insert(function.name.clone(), vec![function]);
+        let client = StakeHubClient::with_caller(Arc::new(caller), Address::ZERO)
+            .unwrap()
+            .with_stake_hub_abi(abi);
+
+        let (decoded, _) = client.get_validator_election_info().await.unwrap();
+        assert_eq!(decoded, candidates);
+
+        let elected = client.get_top_validators_by_voting_power().await.unwrap();
+        assert_eq!(
+            elected.consensus_addrs,
+            vec![Address::repeat_byte(3), Address::repeat_byte(1)]
+        );
+
+        let config = ElectionConfig {
+            include_paused: true,
+            ..Default::default()
+        };
+        let audit = get_top_validators_by_voting_power(candidates, U256::from(10), &config);
+        assert_eq!(audit.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_unbonding_validators_can_be_excluded() {
+        let function = Function::parse(
+            "function getValidatorElectionInfo(uint256 offset, uint256 limit) view returns (address[] consensusAddrs, uint256[] votingPowers, address[] operatorAddrs, bytes[] tendermintPubKeys, bool[] active, bool[] unbonding, uint256 totalLength)",
+        )
+        .unwrap();
+        let candidates: Vec<_> = [false, true, false]
+            .into_iter()
+            .zip(1u8..)
+            .map(|(unbonding, i)| ValidatorElectionInfo {
+                active: Some(true),
+                unbonding: Some(unbonding),
+                ..candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10)))
+            })
+            .collect();
+        let column = |f: fn(&ValidatorElectionInfo) -> DynSolValue| {
+            DynSolValue::Array(candidates.iter().map(f).collect())
+        };
+        let output = function
+            .abi_encode_output(&[
+                column(|c| DynSolValue::from(c.consensus_address)),
+                column(|c| DynSolValue::from(c.voting_power)),
+                column(|c| DynSolValue::from(c.operator_address)),
+                column(|c| DynSolValue::Bytes(c.tendermint_pub_key.clone())),
+                column(|c| DynSolValue::Bool(c.active.unwrap())),
+                column(|c| DynSolValue::Bool(c.unbonding.unwrap())),
+                DynSolValue::from(U256::from(candidates.len())),
+            ])
+            .unwrap();
+        let caller = MockCaller::new();
+        caller.set_response(stake_hub_selector("getValidatorElectionInfo"), output);
+        set_max_elected(&caller, 10);
+        let mut abi = stake_hub_abi();
+        abi.functions.insert(function.name.clone(), vec![function]);
+        let client = StakeHubClient::with_caller(Arc::new(caller), Address::ZERO)
+            .unwrap()
+            .with_stake_hub_abi(abi);
+
+        let (decoded, _) = client.get_validator_election_info().await.unwrap();
+        assert_eq!(decoded, candidates);
+        // Unbonding validators keep their seat unless excluded
+        let elected = client.get_top_validators_by_voting_power().await.unwrap();
+        assert_eq!(elected.len(), 3);
+
+        let client = client
+            .with_election_config(ElectionConfig {
+                exclude_unbonding: true,
+                ..Default::default()
+            })
+            .unwrap();
+        let elected = client.get_top_validators_by_voting_power().await.unwrap();
+        assert_eq!(
+            elected.consensus_addrs,
+            vec![Address::repeat_byte(3), Address::repeat_byte(1)]
+        );
+
+        // Contracts without the flag elect everyone
+        let config = ElectionConfig {
+            exclude_unbonding: true,
+            ..Default::default()
+        };
+        let legacy: Vec<_> = (1..=3)
+            .map(|i| candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10))))
+            .collect();
+        assert_eq!(
+            get_top_validators_by_voting_power(legacy, U256::from(10), &config).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_zero_power_candidates_only_included_when_enabled() {
+        let candidates = vec![
+            candidate(1, U256::ZERO),
+            candidate(2, U256::from(2u64) * U256::from(10u64.pow(10))),
+            candidate(3, U256::ZERO),
+        ];
+        let zero_power = [Address::repeat_byte(1), Address::repeat_byte(3)];
+
+        let default = get_top_validators_by_voting_power(
+            candidates.clone(),
+            U256::from(10),
+            &ElectionConfig::default(),
+        );
+        assert_eq!(default.consensus_addrs, vec![Address::repeat_byte(2)]);
+
+        let config = ElectionConfig {
+            include_zero_power: true,
+            ..Default::default()
+        };
+        let diagnostic = get_top_validators_by_voting_power(candidates, U256::from(10), &config);
+        assert_eq!(diagnostic.len(), 3);
+        assert_eq!(diagnostic.consensus_addrs[0], Address::repeat_byte(2));
+        assert!(zero_power
+            .iter()
+            .all(|a| diagnostic.consensus_addrs[1..].contains(a)));
+        assert_eq!(diagnostic.voting_powers, vec![2, 0, 0]);
+    }
+
+    #[test]
+    fn test_eip712_struct_hash_is_stable() {
+        let elected = elected_set(3);
+        let domain = Eip712Domain::new(
+            Some("LazChain Validator Set".into()),
+            Some("1".into()),
+            Some(U256::from(714)),
+            Some(
+                "0x0000000000000000000000000000000000002002"
+                    .parse()
+                    .unwrap(),
+            ),
+            None,
+        );
+
+        let typed_data = elected.to_eip712(domain.clone()).unwrap();
+        assert_eq!(
+            typed_data.encode_type().unwrap(),
+            "ValidatorSet(Validator[] validators)Validator(address consensusAddress,address operatorAddress,uint64 votingPower,bytes tendermintPubKey)"
+        );
+        assert_eq!(
+            typed_data.hash_struct().unwrap(),
+            "0x9b53ba9c691f8d0a6bfde7b5f365545b376f91eb15857ac203e6a13742b36deb"
+                .parse::<B256>()
+                .unwrap()
+        );
+
+        // Election order doesn't matter, only set membership
+        let mut reordered = elected.clone();
+        reordered.consensus_addrs.reverse();
+        reordered.voting_powers.reverse();
+        reordered.operator_addrs.reverse();
+        reordered.tendermint_pub_keys.reverse();
+        assert_eq!(
+            reordered
+                .to_eip712(domain)
+                .unwrap()
+                .eip712_signing_hash()
+                .unwrap(),
+            typed_data.eip712_signing_hash().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_operator_consensus_pairs() {
+        let caller = MockCaller::new();
+        let operators = [Address::repeat_byte(0x81), Address::repeat_byte(0x82)];
+        let consensus = [Address::repeat_byte(0x01), Address::repeat_byte(0x02)];
+        caller.set_response(
+            stake_hub_selector("getOperatorConsensusPairs"),
+            encode_stake_hub_output(
+                "getOperatorConsensusPairs",
+                &[
+                    DynSolValue::Array(operators.iter().map(|a| DynSolValue::from(*a)).collect()),
+                    DynSolValue::Array(consensus.iter().map(|a| DynSolValue::from(*a)).collect()),
+                ],
+            ),
+        );
+        let client = StakeHubClient::with_caller(Arc::new(caller), Address::ZERO).unwrap();
+
+        let pairs = client.get_operator_consensus_pairs().await.unwrap();
+        assert_eq!(
+            pairs,
+            vec![(operators[0], consensus[0]), (operators[1], consensus[1])]
+        );
+
+        let index = client.get_consensus_to_operator_index().await.unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index[&consensus[1]], operators[1]);
+    }
+
+    #[tokio::test]
+    async fn test_operator_index_falls_back_only_on_a_missing_view() {
+        let candidates = [validator_candidate(1, 10), validator_candidate(2, 20)];
+        let selector = stake_hub_selector("getOperatorConsensusPairs");
+        let index_with = |caller: MockCaller| async move {
+            StakeHubClient::with_caller(Arc::new(caller), Address::ZERO)
+                .unwrap()
+                .get_consensus_to_operator_index()
+                .await
+        };
+
+        // Reverted, empty and undeclared calls fall back to the election info
+        let reverted = mock_stake_hub(2, &candidates);
+        reverted.set_error(
+            selector,
+            RpcError {
+                code: -32000,
+                message: "execution reverted".to_string(),
+            },
+        );
+        let empty = mock_stake_hub(2, &candidates);
+        empty.set_response(selector, Vec::new());
+        for caller in [reverted, empty] {
+            let index = index_with(caller).await.unwrap();
+            assert_eq!(
+                index[&candidates[1].consensus_address],
+                candidates[1].operator_address
+            );
+        }
+        let mut abi = stake_hub_abi();
+        abi.functions.remove("getOperatorConsensusPairs");
+        let client =
+            StakeHubClient::with_caller(Arc::new(mock_stake_hub(2, &candidates)), Address::ZERO)
+                .unwrap()
+                .with_stake_hub_abi(abi);
+        assert_eq!(
+            client
+                .get_consensus_to_operator_index()
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // Other failures are surfaced
+        let failing = mock_stake_hub(2, &candidates);
+        failing.set_error(
+            selector,
+            RpcError {
+                code: -32603,
+                message: "internal error".to_string(),
+            },
+        );
+        let err = index_with(failing).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<RpcError>().map(|e| e.code), Some(-32603));
+    }
+
+    #[tokio::test]
+    async fn test_missing_pubkeys_follow_policy() {
+        let candidates: Vec<_> = (1..=3)
+            .map(|i| candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10))))
+            .collect();
+        let caller = Arc::new(mock_stake_hub(10, &candidates));
+        // Same addresses and powers, but the contract only returns the first two pubkeys
+        let output = [
+            DynSolValue::Array(
+                candidates
+                    .iter()
+                    .map(|c| DynSolValue::from(c.consensus_address))
+                    .collect(),
+            ),
+            DynSolValue::Array(
+                candidates
+                    .iter()
+                    .map(|c| DynSolValue::from(c.voting_power))
+                    .collect(),
+            ),
+            DynSolValue::Array(
+                candidates
+                    .iter()
+                    .map(|c| DynSolValue::from(c.operator_address))
+                    .collect(),
+            ),
+            DynSolValue::Array(
+                candidates[..2]
+                    .iter()
+                    .map(|c| DynSolValue::Bytes(c.tendermint_pub_key.clone()))
+                    .collect(),
+            ),
+            DynSolValue::from(U256::from(candidates.len())),
+        ];
+        caller.set_response(
+            stake_hub_selector("getValidatorElectionInfo"),
+            encode_stake_hub_output("getValidatorElectionInfo", &output),
+        );
+
+        let strict = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let err = strict
+            .get_top_validators_by_voting_power()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no tendermint pubkey"));
+        assert!(err
+            .to_string()
+            .contains(&Address::repeat_byte(3).to_string()));
+
+        let lenient = StakeHubClient::with_caller(caller, Address::ZERO)
+            .unwrap()
+            .with_election_config(ElectionConfig {
+                missing_pubkey: CandidatePolicy::Skip,
+                ..Default::default()
+            })
+            .unwrap();
+        let elected = lenient.get_top_validators_by_voting_power().await.unwrap();
+        assert_eq!(
+            elected.consensus_addrs,
+            vec![Address::repeat_byte(2), Address::repeat_byte(1)]
+        );
+    }
+
+    #[test]
+    fn test_custom_score_reorders_selection() {
+        let candidates: Vec<_> = (1..=4)
+            .map(|i| candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10))))
+            .collect();
+        // Validator 1 has a strong reputation, validator 4 a poor one
+        let weights: HashMap<Address, u64> = [
+            (Address::repeat_byte(1), 10),
+            (Address::repeat_byte(2), 1),
+            (Address::repeat_byte(3), 1),
+            (Address::repeat_byte(4), 0),
+        ]
+        .into_iter()
+        .collect();
+        let config = ElectionConfig {
+            score_fn: Some(Arc::new(move |v: &ValidatorElectionInfo| {
+                v.voting_power * U256::from(weights[&v.consensus_address])
+            })),
+            ..Default::default()
+        };
+
+        let by_power = get_top_validators_by_voting_power(
+            candidates.clone(),
+            U256::from(3),
+            &ElectionConfig::default(),
+        );
+        assert_eq!(
+            by_power.consensus_addrs,
+            vec![
+                Address::repeat_byte(4),
+                Address::repeat_byte(3),
+                Address::repeat_byte(2)
+            ]
+        );
+
+        let by_score =
+            get_top_validators_by_voting_power(candidates.clone(), U256::from(3), &config);
+        assert_eq!(
+            by_score.consensus_addrs,
+            vec![
+                Address::repeat_byte(1),
+                Address::repeat_byte(3),
+                Address::repeat_byte(2)
+            ]
+        );
+        // Voting powers are still reported unweighted
+        assert_eq!(by_score.voting_powers, vec![1, 3, 2]);
+
+        // Equal scores keep the deterministic power/address tiebreak
+        let flat = ElectionConfig {
+            score_fn: Some(Arc::new(|_: &ValidatorElectionInfo| U256::from(1))),
+            ..Default::default()
+        };
+        let mut shuffled = candidates;
+        shuffled.reverse();
+        assert_eq!(
+            get_top_validators_by_voting_power(shuffled, U256::from(3), &flat).consensus_addrs,
+            by_power.consensus_addrs
+        );
+    }
+
+    fn set_operator_pairs(caller: &MockCaller, pairs: &[(Address, Address)]) {
+        let column = |f: fn(&(Address, Address)) -> Address| {
+            DynSolValue::Array(pairs.iter().map(|p| DynSolValue::from(f(p))).collect())
+        };
+        caller.set_response(
+            stake_hub_selector("getOperatorConsensusPairs"),
+            encode_stake_hub_output(
+                "getOperatorConsensusPairs",
+                &[column(|p| p.0), column(|p| p.1)],
+            ),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_operator_pairing_check_catches_mis_association() {
+        let candidates: Vec<_> = (1..=3)
+            .map(|i| candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10))))
+            .collect();
+        let pairs: Vec<_> = candidates
+            .iter()
+            .map(|v| (v.operator_address, v.consensus_address))
+            .collect();
+
+        // Operator array in the same order as the others
+        let caller = mock_stake_hub(3, &candidates);
+        set_operator_pairs(&caller, &pairs);
+        let client = StakeHubClient::with_caller(Arc::new(caller), Address::ZERO)
+            .unwrap()
+            .with_operator_pairing_check(true);
+        let (validators, _) = client.get_validator_election_info().await.unwrap();
+        assert_eq!(validators, candidates);
+
+        // Operator array rotated relative to the consensus array
+        let mut shuffled = candidates.clone();
+        for (i, v) in shuffled.iter_mut().enumerate() {
+            v.operator_address = candidates[(i + 1) % candidates.len()].operator_address;
+        }
+        let caller = mock_stake_hub(3, &shuffled);
+        set_operator_pairs(&caller, &pairs);
+        let client = StakeHubClient::with_caller(Arc::new(caller), Address::ZERO)
+            .unwrap()
+            .with_operator_pairing_check(true);
+        let err = client.get_validator_election_info().await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("mis-associated for 3 validator(s)"));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_calls_are_spaced_out() {
+        let limiter = Arc::new(RateLimiter::new(20.0, 1).unwrap());
+        let client = StakeHubClient::with_caller(Arc::new(mock_stake_hub(1, &[])), Address::ZERO)
+            .unwrap()
+            .with_rate_limiter(limiter);
+
+        let start = Instant::now();
+        for _ in 0..4 {
+            assert_eq!(client.get_epoch_length().await.unwrap(), 100);
+        }
+        // The first call uses the burst token, the other three wait 50ms each
+        assert!(start.elapsed() >= Duration::from_millis(145));
+    }
+
+    #[tokio::test]
+    async fn test_contract_order_keeps_input_order_of_elected() {
+        let powers = [5u64, 20, 10, 40, 30];
+        let candidates: Vec<_> = powers
+            .iter()
+            .zip(1u8..)
+            .map(|(power, byte)| candidate(byte, U256::from(*power) * U256::from(10u64.pow(10))))
+            .collect();
+        let client =
+            StakeHubClient::with_caller(Arc::new(mock_stake_hub(3, &candidates)), Address::ZERO)
+                .unwrap();
+
+        let by_power = client.get_top_validators_by_voting_power().await.unwrap();
+        assert_eq!(by_power.voting_powers, vec![40, 30, 20]);
+
+        let in_order = client.get_elected_in_contract_order().await.unwrap();
+        assert_eq!(in_order.voting_powers, vec![20, 40, 30]);
+        assert_eq!(
+            in_order.consensus_addrs,
+            vec![
+                candidates[1].consensus_address,
+                candidates[3].consensus_address,
+                candidates[4].consensus_address,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_same_address_follows_policy() {
+        let mut same_address = candidate(2, U256::from(10u64.pow(12)));
+        same_address.operator_address = same_address.consensus_address;
+        let candidates = [candidate(1, U256::from(10u64.pow(12))), same_address];
+        let client = |allow_same_address| {
+            StakeHubClient::with_caller(Arc::new(mock_stake_hub(2, &candidates)), Address::ZERO)
+                .unwrap()
+                .with_election_config(ElectionConfig {
+                    allow_same_address,
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+
+        let elected = client(true)
+            .get_top_validators_by_voting_power()
+            .await
+            .unwrap();
+        assert_eq!(elected.len(), 2);
+
+        let err = client(false)
+            .get_top_validators_by_voting_power()
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(&candidates[1].consensus_address.to_string()));
+    }
+
+    #[test]
+    fn test_consensus_and_operator_maps() {
+        let set = elected_set(4);
+
+        let by_consensus = set.consensus_map();
+        let by_operator = set.operator_map();
+        assert_eq!(by_consensus.len(), set.len());
+        assert_eq!(by_operator.len(), set.len());
+        for validator in set.iter() {
+            assert_eq!(by_consensus[&validator.consensus_address], validator);
+            assert_eq!(by_operator[&validator.operator_address], validator);
+        }
+    }
+
+    /// Log sink for a test subscriber
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_candidates_beyond_cap_are_truncated() {
+        let candidates: Vec<_> = (1..=5)
+            .map(|i| candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10))))
+            .collect();
+        let caller = Arc::new(mock_stake_hub(10, &candidates));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO)
+            .unwrap()
+            .with_election_config(ElectionConfig {
+                max_candidates: 3,
+                min_self_stake: U256::from(1),
+                ..ElectionConfig::default()
+            })
+            .unwrap();
+
+        let logs = CapturedLogs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let (decoded, total_length) = client.get_validator_election_info().await.unwrap();
+        assert_eq!(decoded, candidates[..3]);
+        assert_eq!(total_length, U256::from(5));
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains("WARN"));
+        assert!(logs.contains("returned 5 candidates, more than the cap of 3"));
+
+        // Per-candidate reads only happen for the kept ones
+        client
+            .get_election_candidates_at(BlockTag::Latest)
+            .await
+            .unwrap();
+        assert_eq!(
+            caller.call_count(stake_hub_selector("getValidatorCreditContract")),
+            3
+        );
+
+        // The pure election caps its input too, so the strongest candidate 5 is ignored
+        let config = ElectionConfig {
+            max_candidates: 3,
+            ..ElectionConfig::default()
+        };
+        let expected: Vec<_> = [3, 2, 1]
+            .map(|i| candidates[i - 1].consensus_address)
+            .into();
+        assert_eq!(
+            get_top_validators_by_voting_power(candidates.clone(), U256::from(10), &config)
+                .consensus_addrs,
+            expected
+        );
+        assert_eq!(
+            get_top_validators_in_contract_order(candidates.clone(), U256::from(10), &config)
+                .consensus_addrs
+                .len(),
+            3
+        );
+
+        // A total length beyond the cap counts as well
+        let mut capped = candidates.clone();
+        cap_candidates(&mut capped, U256::from(4), 4);
+        assert_eq!(capped.len(), 4);
+        cap_candidates(&mut capped, U256::from(7), 4);
+        assert_eq!(capped.len(), 4);
+        cap_candidates(&mut capped, U256::from(7), 2);
+        assert_eq!(capped.len(), 2);
+    }
+
+    #[test]
+    fn test_mark_genesis_validators() {
+        let set = elected_set(4);
+        let genesis: Vec<_> = [1, 3, 9]
+            .map(|byte| GenesisValidatorInfo {
+                consensus_address: Address::repeat_byte(byte),
+                operator_address: Address::repeat_byte(byte.wrapping_add(0x80)),
+                tendermint_pubkey: vec![byte; 32],
+                voting_power: 10,
+            })
+            .into();
+
+        let flags = set.mark_genesis_validators(&genesis);
+        assert_eq!(flags.len(), set.len());
+        for (address, flag) in set.consensus_addrs.iter().zip(flags) {
+            assert_eq!(flag, [1, 3].contains(&address[0]), "{}", address);
+        }
+        assert_eq!(set.mark_genesis_validators(&[]), vec![false; 4]);
+    }
+
+    #[test]
+    fn test_find_duplicate_pubkeys() {
+        let mut set = elected_set(4);
+        assert!(set.find_duplicate_pubkeys().is_empty());
+
+        set.tendermint_pub_keys[3] = set.tendermint_pub_keys[1].clone();
+        set.tendermint_pub_keys[2].clear();
+        set.tendermint_pub_keys[0].clear();
+        assert_eq!(
+            set.find_duplicate_pubkeys(),
+            vec![(
+                set.tendermint_pub_keys[1].clone(),
+                vec![set.consensus_addrs[1], set.consensus_addrs[3]]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_indexed_validator_set_lookups_agree() {
+        let set = elected_set(4);
+        let indexed = IndexedValidatorSet::from(&set);
+
+        assert_eq!(indexed.len(), set.len());
+        for (index, validator) in set.iter().enumerate() {
+            assert_eq!(indexed.by_index(index), Some(&validator));
+            assert_eq!(indexed.index_of(&validator.consensus_address), Some(index));
+        }
+        assert_eq!(indexed.by_index(set.len()), None);
+        assert_eq!(indexed.index_of(&Address::repeat_byte(0xee)), None);
+        assert!(IndexedValidatorSet::default().is_empty());
+    }
+
+    #[test]
+    fn test_deep_duplicate_is_found_in_large_set() {
+        let mut validators: Vec<_> = (0..20_000u64)
+            .map(|i| {
+                let mut address = [0u8; 20];
+                address[12..].copy_from_slice(&i.to_be_bytes());
+                let mut operator = address;
+                operator[0] = 0xff;
+                let mut pubkey = vec![0u8; 32];
+                pubkey[..8].copy_from_slice(&i.to_be_bytes());
+                ValidatorElectionInfo {
+                    consensus_address: Address::from(address),
+                    voting_power: U256::from(1),
+                    operator_address: Address::from(operator),
+                    tendermint_pub_key: pubkey,
+                    status: None,
+                    active: None,
+                    unbonding: None,
+                    self_stake: None,
+                }
+            })
+            .collect();
+        assert!(validate_uniqueness(&validators).is_ok());
+
+        let last = validators.len() - 1;
+        validators[last].tendermint_pub_key = validators[17].tendermint_pub_key.clone();
+        let start = Instant::now();
+        let collisions = find_collisions(&validators);
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(
+            collisions,
+            vec![Collision {
+                kind: CollisionKind::TendermintPubKey,
+                first: 17,
+                duplicate: last,
+            }]
+        );
+        let err = validate_uniqueness(&validators).unwrap_err();
+        assert!(err.to_string().starts_with("1 validator collision(s)"));
+    }
+
+    #[test]
+    fn test_raw_stakes_are_kept_alongside_scaled_power() {
+        // Stakes that aren't whole multiples of the divisor
+        let candidates: Vec<_> = (1..=3u8)
+            .map(|i| {
+                candidate(
+                    i,
+                    U256::from(i as u64) * U256::from(10u64.pow(18)) + U256::from(7),
+                )
+            })
+            .collect();
+
+        let elected = get_top_validators_by_voting_power(
+            candidates.clone(),
+            U256::from(3),
+            &Default::default(),
+        );
+
+        assert_eq!(elected.raw_stakes.len(), elected.len());
+        for (i, address) in elected.consensus_addrs.iter().enumerate() {
+            let input = candidates
+                .iter()
+                .find(|c| c.consensus_address == *address)
+                .unwrap();
+            assert_eq!(elected.raw_stakes[i], input.voting_power);
+            assert_eq!(
+                U256::from(elected.voting_powers[i]),
+                elected.raw_stakes[i] / U256::from(VOTING_POWER_DIVISOR)
+            );
+        }
+    }
+
+    #[test]
+    fn test_bsc_parlia_power_model_weighs_validators_equally() {
+        let candidates: Vec<_> = [4u64, 1, 3, 2]
+            .iter()
+            .zip(1u8..)
+            .map(|(stake, byte)| candidate(byte, U256::from(*stake) * U256::from(10u64.pow(18))))
+            .collect();
+        let bsc = ElectionConfig {
+            power_model: PowerModel::BscParlia,
+            ..Default::default()
+        };
+
+        let scaled = get_top_validators_by_voting_power(
+            candidates.clone(),
+            U256::from(3),
+            &Default::default(),
+        );
+        let parlia = get_top_validators_by_voting_power(candidates, U256::from(3), &bsc);
+
+        // Same validators elected in the same order, only the powers differ
+        assert_eq!(parlia.consensus_addrs, scaled.consensus_addrs);
+        assert_eq!(parlia.raw_stakes, scaled.raw_stakes);
+        assert_eq!(
+            scaled.voting_powers,
+            vec![4 * 10u64.pow(8), 3 * 10u64.pow(8), 2 * 10u64.pow(8)]
+        );
+        assert_eq!(parlia.voting_powers, vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn test_columns_align_and_are_hex_encoded() {
+        let set = elected_set(3);
+
+        let columns = set.to_columns();
+
+        assert_eq!(columns.consensus_address.len(), set.len());
+        assert_eq!(columns.operator_address.len(), set.len());
+        assert_eq!(columns.voting_power, set.voting_powers);
+        assert_eq!(columns.raw_stake.len(), set.len());
+        assert_eq!(columns.tendermint_pub_key.len(), set.len());
+
+        assert_eq!(
+            columns.consensus_address[0].parse::<Address>().unwrap(),
+            set.consensus_addrs[0]
+        );
+        assert_eq!(
+            columns.operator_address[2].to_lowercase(),
+            format!("0x{}", "81".repeat(20))
+        );
+        assert_eq!(columns.raw_stake[0], "30000000000");
+        assert_eq!(
+            columns.tendermint_pub_key[0],
+            format!("0x{}", "03".repeat(32))
+        );
+
+        let json = serde_json::to_value(&columns).unwrap();
+        assert_eq!(json["voting_power"], json!([3, 2, 1]));
+    }
+
+    #[tokio::test]
+    async fn test_u256_max_elected_is_bounded() {
+        let candidates: Vec<_> = (1..=3)
+            .map(|i| candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10))))
+            .collect();
+
+        // The pure selection clamps to the candidate count
+        let elected = get_top_validators_by_voting_power(
+            candidates.clone(),
+            U256::MAX,
+            &ElectionConfig::default(),
+        );
+        assert_eq!(elected.len(), 3);
+
+        let caller = Arc::new(mock_stake_hub(3, &candidates));
+        caller.set_response(
+            stake_hub_selector("maxElectedValidators"),
+            encode_stake_hub_output("maxElectedValidators", &[DynSolValue::from(U256::MAX)]),
+        );
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        assert_eq!(
+            client.get_max_elected_validators().await.unwrap(),
+            U256::from(DEFAULT_MAX_ELECTED_CAP)
+        );
+        assert_eq!(
+            client
+                .get_top_validators_by_voting_power()
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+
+        let strict = StakeHubClient::with_caller(caller, Address::ZERO)
+            .unwrap()
+            .with_strict_max_elected(true);
+        let err = strict.get_max_elected_validators().await.unwrap_err();
+        assert!(err.to_string().contains("exceeds the configured cap"));
+    }
+
+    #[tokio::test]
+    async fn test_for_chain_selects_registered_deployment() {
+        let eth_rpc = Arc::new(EthereumRPC::new("http://127.0.0.1:8545".parse().unwrap()).unwrap());
+
+        let client = StakeHubClient::for_chain(eth_rpc.clone(), Some(DEVNET_CHAIN_ID))
+            .await
+            .unwrap();
+        assert_eq!(
+            client.stake_hub_address,
+            Network::Devnet.stake_hub_address()
+        );
+        assert_eq!(
+            client.election_info_layout,
+            ElectionInfoLayout::ParallelArrays
+        );
+        assert!(client
+            .stake_hub_abi
+            .function("getValidatorElectionInfo")
+            .is_some());
+
+        let err = StakeHubClient::for_chain(eth_rpc, Some(31337))
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("31337"));
+    }
+
+    #[tokio::test]
+    async fn test_zero_address_candidates_follow_policy() {
+        let mut zero_operator = candidate(2, U256::from(10u64.pow(12)));
+        zero_operator.operator_address = Address::ZERO;
+        let candidates = [candidate(1, U256::from(10u64.pow(12))), zero_operator];
+        let client = |zero_address| {
+            StakeHubClient::with_caller(Arc::new(mock_stake_hub(2, &candidates)), Address::ZERO)
+                .unwrap()
+                .with_election_config(ElectionConfig {
+                    zero_address,
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+
+        let err = client(CandidatePolicy::Reject)
+            .get_validator_election_info()
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("zero consensus or operator address"));
+
+        let (validators, _) = client(CandidatePolicy::Skip)
+            .get_validator_election_info()
+            .await
+            .unwrap();
+        assert_eq!(validators, vec![candidates[0].clone()]);
+        assert_eq!(
+            ElectionConfig::default().zero_address,
+            CandidatePolicy::Skip
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validator_lookup_by_tendermint_pubkey() {
+        let candidates: Vec<_> = (1..=3)
+            .map(|i| candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10))))
+            .collect();
+        let client =
+            StakeHubClient::with_caller(Arc::new(mock_stake_hub(3, &candidates)), Address::ZERO)
+                .unwrap();
+
+        let found = client
+            .get_validator_by_tendermint_pubkey(&[2; 32])
+            .await
+            .unwrap();
+        assert_eq!(found, Some(candidates[1].clone()));
+
+        let missing = client
+            .get_validator_by_tendermint_pubkey(&[9; 32])
+            .await
+            .unwrap();
+        assert_eq!(missing, None);
+    }
+
+    #[tokio::test]
+    async fn test_historical_fetches_are_bounded_and_cancel_safe() {
+        let candidates = [candidate(1, U256::from(10u64.pow(12)))];
+        let caller = Arc::new(mock_stake_hub(1, &candidates));
+        caller.set_delay(Duration::from_millis(20));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO)
+            .unwrap()
+            .with_history_concurrency(2);
+        let blocks: Vec<u64> = (1..=6).map(|epoch| epoch * 100 - 1).collect();
+
+        let history = client.get_top_validators_at_blocks(&blocks).await.unwrap();
+        assert_eq!(history.len(), blocks.len());
+        assert_eq!(caller.max_in_flight(), 2);
+
+        // Dropping the aggregate future mid-flight cancels the outstanding calls
+        caller.set_delay(Duration::from_secs(30));
+        let aborted = tokio::time::timeout(
+            Duration::from_millis(50),
+            client.get_top_validators_at_blocks(&blocks),
+        )
+        .await;
+        assert!(aborted.is_err());
+        assert_eq!(caller.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_eligibility_of_validator_below_threshold() {
+        let candidates: Vec<_> = (1..=3)
+            .map(|i| candidate(i, U256::from(i as u64) * U256::from(10u64.pow(10))))
+            .collect();
+        let client =
+            StakeHubClient::with_caller(Arc::new(mock_stake_hub(2, &candidates)), Address::ZERO)
+                .unwrap();
+
+        let report = client
+            .explain_eligibility(candidates[0].consensus_address)
+            .await
+            .unwrap();
+        assert_eq!(
+            report,
+            EligibilityReport {
+                is_candidate: true,
+                voting_power: candidates[0].voting_power,
+                rank: Some(3),
+                elected: false,
+                reasons: vec![EligibilityReason::BelowThreshold],
+            }
+        );
+
+        let elected = client
+            .explain_eligibility(candidates[2].consensus_address)
+            .await
+            .unwrap();
+        assert!(elected.elected && elected.reasons.is_empty());
+
+        let unknown = client
+            .explain_eligibility(Address::repeat_byte(0x42))
+            .await
+            .unwrap();
+        assert_eq!(unknown.reasons, vec![EligibilityReason::NotRegistered]);
+
+        // Candidates the client leaves out are explained too, and the rest rank without them
+        let mut no_pubkey = candidates.clone();
+        no_pubkey[2].tendermint_pub_key.clear();
+        let client =
+            StakeHubClient::with_caller(Arc::new(mock_stake_hub(2, &no_pubkey)), Address::ZERO)
+                .unwrap()
+                .with_election_config(ElectionConfig {
+                    missing_pubkey: CandidatePolicy::Skip,
+                    ..ElectionConfig::default()
+                })
+                .unwrap();
+        let skipped = client
+            .explain_eligibility(candidates[2].consensus_address)
+            .await
+            .unwrap();
+        assert!(skipped.is_candidate && !skipped.elected);
+        assert_eq!(skipped.reasons, vec![EligibilityReason::Excluded]);
+        let report = client
+            .explain_eligibility(candidates[0].consensus_address)
+            .await
+            .unwrap();
+        assert_eq!((report.rank, report.elected), (Some(2), true));
+    }
+
+    #[test]
+    fn test_minimal_quorum_set() {
+        let candidates = [10, 40, 5, 20, 25]
+            .iter()
+            .enumerate()
+            .map(|(i, &power)| validator_candidate(i as u8 + 1, power))
+            .collect();
+        let elected = get_top_validators_by_voting_power(
+            candidates,
+            U256::from(5),
+            &ElectionConfig::default(),
+        );
+        let power_of = |set: &[Address]| -> u64 {
+            let by_address = elected.consensus_map();
+            set.iter().map(|a| by_address[a].voting_power).sum()
+        };
+
+        // 40 + 25 + 20 = 85 of 100
+        let quorum = elected.minimal_quorum_set();
+        assert_eq!(quorum.len(), 3);
+        assert!(power_of(&quorum) * 3 > 100 * 2);
+        assert!(power_of(&quorum[..quorum.len() - 1]) * 3 <= 100 * 2);
+
+        assert!(ElectedValidators::default().minimal_quorum_set().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pending_rewards_per_operator() {
+        let caller = Arc::new(MockCaller::new());
+        let abi = stake_hub_abi();
+        let function = &abi.function("pendingReward").unwrap()[0];
+        let operators = [Address::repeat_byte(0x81), Address::repeat_byte(0x82)];
+        for (operator, reward) in operators.iter().zip([7u64, 11]) {
+            caller.set_call_response(
+                function
+                    .abi_encode_input(&[DynSolValue::from(*operator)])
+                    .unwrap(),
+                encode_stake_hub_output("pendingReward", &[DynSolValue::from(U256::from(reward))]),
+            );
+        }
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+
+        assert_eq!(
+            client.get_pending_reward(operators[1]).await.unwrap(),
+            U256::from(11)
+        );
+        assert_eq!(
+            client.get_pending_rewards(&operators).await.unwrap(),
+            vec![U256::from(7), U256::from(11)]
+        );
+
+        // Deployments without the view report it missing
+        caller.set_error(
+            function.selector().0,
+            RpcError {
+                code: -32000,
+                message: "execution reverted".to_string(),
+            },
+        );
+        let err = client.get_pending_reward(operators[0]).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<MissingView>(),
+            Some(&MissingView("pendingReward"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_candidates_fingerprint_tracks_any_change() {
+        let candidates = [
+            validator_candidate(1, 10),
+            validator_candidate(2, 20),
+            validator_candidate(3, 30),
+        ];
+        let caller = Arc::new(mock_stake_hub(1, &candidates));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+
+        let fingerprint = client.candidates_fingerprint().await.unwrap();
+        assert_eq!(client.candidates_fingerprint().await.unwrap(), fingerprint);
+
+        // Order doesn't matter
+        let mut reordered = candidates.clone();
+        reordered.reverse();
+        assert_eq!(candidates_fingerprint(&reordered), fingerprint);
+
+        // A change to a candidate that isn't elected still shows
+        let mut changed = candidates.clone();
+        changed[0].voting_power += U256::from(1);
+        set_candidates(&caller, &changed);
+        assert_ne!(client.candidates_fingerprint().await.unwrap(), fingerprint);
+
+        // So do the active and unbonding flags, unknown or not
+        let flagged = |active, unbonding| {
+            let mut flagged = candidates.clone();
+            flagged[0].active = active;
+            flagged[0].unbonding = unbonding;
+            candidates_fingerprint(&flagged)
+        };
+        let fingerprints: HashSet<B256> = [None, Some(false), Some(true)]
+            .into_iter()
+            .flat_map(|active| {
+                [None, Some(false), Some(true)]
+                    .into_iter()
+                    .map(move |unbonding| flagged(active, unbonding))
+            })
+            .collect();
+        assert_eq!(fingerprints.len(), 9);
+    }
+
+    #[tokio::test]
+    async fn test_compatibility_probe_rejects_mismatched_return_data() {
+        let caller = Arc::new(mock_stake_hub(21, &[]));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        client.check_compatibility().await.unwrap();
+
+        // A view returning `bytes` instead of `uint256` at the same selector
+        let selector = stake_hub_selector(COMPATIBILITY_PROBE);
+        caller.set_response(
+            selector,
+            DynSolValue::Tuple(vec![DynSolValue::Bytes(vec![1, 2, 3])]).abi_encode_params(),
+        );
+        let err = client.check_compatibility().await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CompatibilityError>(),
+            Some(CompatibilityError::Incompatible { details, .. }) if details.contains("3 words")
+        ));
+
+        caller.set_response(selector, Vec::new());
+        let err = client.check_compatibility().await.unwrap_err();
+        assert!(err.downcast_ref::<CompatibilityError>().is_some());
+    }
+
+    #[test]
+    fn test_power_shares() {
+        let candidates = [50, 30, 20]
+            .iter()
+            .enumerate()
+            .map(|(i, &power)| validator_candidate(i as u8 + 1, power))
+            .collect();
+        let elected = get_top_validators_by_voting_power(
+            candidates,
+            U256::from(3),
+            &ElectionConfig::default(),
+        );
+
+        let shares = elected.power_shares();
+        for (share, expected) in shares.iter().zip([0.5, 0.3, 0.2]) {
+            assert!((share - expected).abs() < 1e-12);
+        }
+        assert!((shares.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert_eq!(elected.power_shares_bps(), vec![5000, 3000, 2000]);
+
+        assert!(ElectedValidators::default().power_shares().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delegator_contribution() {
+        let validator = validator_candidate(1, 10);
+        let delegator = Address::repeat_byte(0xd1);
+        let caller = Arc::new(mock_stake_hub(1, std::slice::from_ref(&validator)));
+        // A quarter of the validator's power, as shares worth 2 METIS each
+        let delegator_power = validator.voting_power / U256::from(4);
+        let shares = 25 * 10u64.pow(9) / 2;
+        set_stake_credit(&caller, validator.operator_address, 0, 4 * shares, 2);
+        set_credit_holding(&caller, validator.operator_address, delegator, shares, 2);
+        let client = StakeHubClient::with_caller(caller, Address::ZERO).unwrap();
+
+        assert_eq!(
+            client
+                .get_delegator_power(validator.operator_address, delegator)
+                .await
+                .unwrap(),
+            delegator_power
+        );
+        let contribution = client
+            .get_delegator_contribution(validator.operator_address, delegator)
+            .await
+            .unwrap();
+        assert_eq!(contribution.validator_power, validator.voting_power);
+        assert_eq!(contribution.share_bps(), 2500);
+    }
+
+    #[test]
+    fn test_validate_ordering_finds_first_unsorted_index() {
+        let mut elected = elected_set(4);
+        elected.validate_ordering().unwrap();
+
+        elected.voting_powers.swap(2, 3);
+        let err = elected.validate_ordering().unwrap_err().to_string();
+        assert!(err.contains("at index 3"), "{}", err);
+    }
+
+    #[test]
+    fn test_aggregate_by_operator_ranks_by_combined_power() {
+        let operator = Address::repeat_byte(0xa0);
+        let mut first_key = validator_candidate(1, 30);
+        first_key.operator_address = operator;
+        let mut second_key = validator_candidate(2, 25);
+        second_key.operator_address = operator;
+        let candidates = vec![first_key, second_key, validator_candidate(3, 50)];
+
+        let separate = get_top_validators_by_voting_power(
+            candidates.clone(),
+            U256::from(2),
+            &ElectionConfig::default(),
+        );
+        assert_eq!(
+            separate.consensus_addrs,
+            vec![Address::repeat_byte(3), Address::repeat_byte(1)]
+        );
+
+        let config = ElectionConfig {
+            aggregate_by_operator: true,
+            ..ElectionConfig::default()
+        };
+        let aggregated = get_top_validators_by_voting_power(candidates, U256::from(2), &config);
+        assert_eq!(
+            aggregated.consensus_addrs,
+            vec![Address::repeat_byte(1), Address::repeat_byte(2)]
+        );
+        assert_eq!(aggregated.voting_powers, vec![30, 25]);
+    }
+
+    #[tokio::test]
+    async fn test_client_elects_keys_sharing_an_operator_when_aggregating() {
+        let operator = Address::repeat_byte(0xa0);
+        let mut first_key = validator_candidate(1, 30);
+        first_key.operator_address = operator;
+        let mut second_key = validator_candidate(2, 25);
+        second_key.operator_address = operator;
+        let caller = Arc::new(mock_stake_hub(
+            2,
+            &[first_key, second_key, validator_candidate(3, 50)],
+        ));
+
+        // Without aggregation, a shared operator is a collision
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        assert!(client.get_top_validators_by_voting_power().await.is_err());
+
+        // or leaves both keys out when skipping duplicates
+        let skipping = StakeHubClient::with_caller(caller.clone(), Address::ZERO)
+            .unwrap()
+            .with_election_config(ElectionConfig {
+                duplicates: CandidatePolicy::Skip,
+                ..ElectionConfig::default()
+            })
+            .unwrap();
+        let elected = skipping.get_top_validators_by_voting_power().await.unwrap();
+        assert_eq!(elected.consensus_addrs, vec![Address::repeat_byte(3)]);
+
+        let client = client
+            .with_election_config(ElectionConfig {
+                aggregate_by_operator: true,
+                ..ElectionConfig::default()
+            })
+            .unwrap();
+        let elected = client.get_top_validators_by_voting_power().await.unwrap();
+        assert_eq!(
+            elected.consensus_addrs,
+            vec![Address::repeat_byte(1), Address::repeat_byte(2)]
+        );
+        assert_eq!(elected.operator_addrs, vec![operator, operator]);
+    }
+
+    /// Give `operator` a StakeCredit contract at `0xc0..`-prefixed address where it holds
+    /// `self_shares` of `total_shares`, each share worth `rate` METIS
+    fn set_stake_credit(
+        caller: &MockCaller,
+        operator: Address,
+        self_shares: u64,
+        total_shares: u64,
+        rate: u64,
+    ) {
+        let mut credit = operator;
+        credit.0[0] = 0xc0;
+        let abi = stake_hub_abi();
+        caller.set_call_response(
+            abi.function("getValidatorCreditContract").unwrap()[0]
+                .abi_encode_input(&[DynSolValue::from(operator)])
+                .unwrap(),
+            encode_stake_hub_output("getValidatorCreditContract", &[DynSolValue::from(credit)]),
+        );
+
+        let function = |name: &str| {
+            STAKE_CREDIT_VIEWS
+                .iter()
+                .map(|signature| Function::parse(signature).unwrap())
+                .find(|function| function.name == name)
+                .unwrap()
+        };
+        let respond = |name: &str, args: &[DynSolValue], amount: u64| {
+            let function = function(name);
+            caller.set_contract_response(
+                credit,
+                function.abi_encode_input(args).unwrap(),
+                function
+                    .abi_encode_output(&[DynSolValue::from(U256::from(amount))])
+                    .unwrap(),
+            );
+        };
+        respond("totalSupply", &[], total_shares);
+        respond(
+            "getPooledMETISByShares",
+            &[DynSolValue::from(U256::from(total_shares))],
+            total_shares * rate,
+        );
+        set_credit_holding(caller, operator, operator, self_shares, rate);
+    }
+
+    /// Give `holder` `shares` of the StakeCredit pool set up by [`set_stake_credit`]
+    fn set_credit_holding(
+        caller: &MockCaller,
+        operator: Address,
+        holder: Address,
+        shares: u64,
+        rate: u64,
+    ) {
+        let mut credit = operator;
+        credit.0[0] = 0xc0;
+        let respond = |name: &str, args: &[DynSolValue], amount: u64| {
+            let function = STAKE_CREDIT_VIEWS
+                .iter()
+                .map(|signature| Function::parse(signature).unwrap())
+                .find(|function| function.name == name)
+                .unwrap();
+            caller.set_contract_response(
+                credit,
+                function.abi_encode_input(args).unwrap(),
+                function
+                    .abi_encode_output(&[DynSolValue::from(U256::from(amount))])
+                    .unwrap(),
+            );
+        };
+        respond("balanceOf", &[DynSolValue::from(holder)], shares);
+        respond(
+            "getPooledMETISByShares",
+            &[DynSolValue::from(U256::from(shares))],
+            shares * rate,
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delegation_breakdown() {
+        let caller = Arc::new(MockCaller::new());
+        // Shares of the pools are worth different amounts
+        set_stake_credit(&caller, Address::repeat_byte(0x81), 125, 500, 2);
+        set_stake_credit(&caller, Address::repeat_byte(0x82), 400, 400, 1);
+        set_stake_credit(&caller, Address::repeat_byte(0x83), 0, 0, 1);
+        let client = StakeHubClient::with_caller(caller, Address::ZERO).unwrap();
+        let breakdown = |operator| client.get_delegation_breakdown(Address::repeat_byte(operator));
+
+        let delegated = breakdown(0x81).await.unwrap();
+        assert_eq!(delegated.self_delegation, U256::from(250));
+        assert_eq!(delegated.delegated(), U256::from(750));
+        assert_eq!(delegated.self_bond_bps(), Some(2500));
+
+        // Without outside delegation, or without any stake
+        let self_bonded = breakdown(0x82).await.unwrap();
+        assert_eq!(self_bonded.delegated(), U256::ZERO);
+        assert_eq!(self_bonded.self_bond_bps(), Some(10_000));
+        assert_eq!(breakdown(0x83).await.unwrap().self_bond_bps(), None);
+    }
+
+    #[tokio::test]
+    async fn test_min_self_stake_excludes_low_self_bond() {
+        // Validator 1 has the most stake, but almost all of it is delegated. Validator 3's
+        // self-stake can't be read.
+        let caller = Arc::new(mock_stake_hub(
+            10,
+            &[
+                validator_candidate(1, 100),
+                validator_candidate(2, 50),
+                validator_candidate(3, 20),
+            ],
+        ));
+        set_stake_credit(&caller, Address::repeat_byte(0x81), 5, 100, 1);
+        set_stake_credit(&caller, Address::repeat_byte(0x82), 50, 100, 1);
+
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let elected = client.get_top_validators_by_voting_power().await.unwrap();
+        assert_eq!(elected.len(), 3);
+
+        let client = StakeHubClient::with_caller(caller, Address::ZERO)
+            .unwrap()
+            .with_election_config(ElectionConfig {
+                min_self_stake: U256::from(10),
+                ..ElectionConfig::default()
+            })
+            .unwrap();
+        let elected = client.get_top_validators_by_voting_power().await.unwrap();
+        assert_eq!(elected.consensus_addrs, vec![Address::repeat_byte(2)]);
+
+        // The filter itself works on the self-stakes carried by the candidates
+        let (candidates, _) = client
+            .get_election_candidates_at(BlockTag::Latest)
+            .await
+            .unwrap();
+        let self_stakes: Vec<_> = candidates.iter().map(|c| c.self_stake).collect();
+        assert_eq!(
+            self_stakes,
+            vec![Some(U256::from(5)), Some(U256::from(50)), None]
+        );
+        let config = ElectionConfig {
+            min_self_stake: U256::from(10),
+            ..ElectionConfig::default()
+        };
+        assert_eq!(
+            get_top_validators_by_voting_power(candidates, U256::from(10), &config).consensus_addrs,
+            vec![Address::repeat_byte(2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_require_opt_in_excludes_absent_validators() {
+        let caller = Arc::new(mock_stake_hub(
+            10,
+            &(1..=3)
+                .map(|i| validator_candidate(i, 10))
+                .collect::<Vec<_>>(),
+        ));
+        let function =
+            Function::parse("function hasOptedIn(address consensusAddress) view returns (bool)")
+                .unwrap();
+        for (consensus, opted_in) in [(1, true), (2, false), (3, true)] {
+            caller.set_call_response(
+                function
+                    .abi_encode_input(&[DynSolValue::from(Address::repeat_byte(consensus))])
+                    .unwrap(),
+                function
+                    .abi_encode_output(&[DynSolValue::Bool(opted_in)])
+                    .unwrap(),
+            );
+        }
+
+        // The embedded ABI doesn't declare the view
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        assert!(client.has_opted_in(Address::repeat_byte(2)).await.is_err());
+        let err = StakeHubClient::with_caller(caller.clone(), Address::ZERO)
+            .unwrap()
+            .with_election_config(ElectionConfig {
+                require_opt_in: true,
+                ..ElectionConfig::default()
+            })
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("hasOptedIn"));
+
+        let mut abi = stake_hub_abi();
+        abi.functions.insert(function.name.clone(), vec![function]);
+        let client = client.with_stake_hub_abi(abi);
+        assert!(!client.has_opted_in(Address::repeat_byte(2)).await.unwrap());
+        assert_eq!(
+            client
+                .get_top_validators_by_voting_power()
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+
+        let client = client
+            .with_election_config(ElectionConfig {
+                require_opt_in: true,
+                ..ElectionConfig::default()
+            })
+            .unwrap();
+        let mut elected = client
+            .get_top_validators_by_voting_power()
+            .await
+            .unwrap()
+            .consensus_addrs;
+        elected.sort();
+        assert_eq!(
+            elected,
+            vec![Address::repeat_byte(1), Address::repeat_byte(3)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_call_view_matches_typed_method() {
+        let caller = Arc::new(MockCaller::new());
+        set_epoch_length(&caller, 200);
+        let client = StakeHubClient::with_caller(caller, Address::ZERO).unwrap();
+
+        let output = client.call_view("epochLength", vec![]).await.unwrap();
+        let epoch_length = client.get_epoch_length().await.unwrap();
+        assert_eq!(output, vec![DynSolValue::from(U256::from(epoch_length))]);
+
+        let err = client.call_view("notAFunction", vec![]).await.unwrap_err();
+        assert!(err.to_string().contains("notAFunction"));
     }
 }
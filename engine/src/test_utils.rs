@@ -0,0 +1,326 @@
+//! Test helpers shared by the engine unit tests
+
+use crate::ethereum_rpc::{BlockTag, ContractCaller, Log, RpcError};
+use crate::stake_hub_client::{load_stake_hub_abi, ValidatorElectionInfo};
+use alloy_dyn_abi::{DynSolValue, FunctionExt};
+use alloy_json_abi::JsonAbi;
+use alloy_primitives::{Address, U256};
+use async_trait::async_trait;
+use color_eyre::eyre::{eyre, Result};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Responses for calls against specific blocks, keyed by block number and selector
+type BlockResponses = HashMap<(u64, [u8; 4]), Vec<u8>>;
+
+/// Responses for calls to specific contracts, keyed by contract address and call data
+type ContractResponses = HashMap<(Address, Vec<u8>), Vec<u8>>;
+
+/// In-memory [`ContractCaller`] answering calls by function selector and counting them
+#[derive(Default)]
+pub struct MockCaller {
+    responses: Mutex<HashMap<[u8; 4], Vec<u8>>>,
+    errors: Mutex<HashMap<[u8; 4], RpcError>>,
+    call_responses: Mutex<HashMap<Vec<u8>, Vec<u8>>>,
+    contract_responses: Mutex<ContractResponses>,
+    block_responses: Mutex<BlockResponses>,
+    logs: Mutex<Vec<Log>>,
+    calls: Mutex<HashMap<[u8; 4], usize>>,
+    blocks: Mutex<Vec<BlockTag>>,
+    finalized_block: Mutex<Option<u64>>,
+    delay: Mutex<Option<Duration>>,
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+}
+
+/// Counts a call as in flight until dropped, including when the call is cancelled
+struct InFlightGuard<'a>(&'a AtomicUsize);
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl MockCaller {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer calls to `selector` with the raw return data `response`
+    pub fn set_response(&self, selector: [u8; 4], response: Vec<u8>) {
+        self.responses.lock().unwrap().insert(selector, response);
+    }
+
+    /// Fail calls to `selector` with the node error `error`, taking precedence over every
+    /// response
+    pub fn set_error(&self, selector: [u8; 4], error: RpcError) {
+        self.errors.lock().unwrap().insert(selector, error);
+    }
+
+    /// Answer calls with exactly `call_data` with `response`, taking precedence over the
+    /// response for their selector
+    pub fn set_call_response(&self, call_data: Vec<u8>, response: Vec<u8>) {
+        self.call_responses
+            .lock()
+            .unwrap()
+            .insert(call_data, response);
+    }
+
+    /// Answer calls to contract `to` with exactly `call_data` with `response`, taking precedence
+    /// over the responses for every contract
+    pub fn set_contract_response(&self, to: Address, call_data: Vec<u8>, response: Vec<u8>) {
+        self.contract_responses
+            .lock()
+            .unwrap()
+            .insert((to, call_data), response);
+    }
+
+    /// Answer calls to `selector` against block `block` with `response`, taking precedence over
+    /// the responses for every block
+    pub fn set_response_at(&self, block: u64, selector: [u8; 4], response: Vec<u8>) {
+        self.block_responses
+            .lock()
+            .unwrap()
+            .insert((block, selector), response);
+    }
+
+    /// Report `block` as the latest finalized block
+    pub fn set_finalized_block(&self, block: u64) {
+        *self.finalized_block.lock().unwrap() = Some(block);
+    }
+
+    /// Answer `eth_getLogs` with the `logs` in the requested block range, ignoring the rest of
+    /// the filter
+    pub fn set_logs(&self, logs: Vec<Log>) {
+        *self.logs.lock().unwrap() = logs;
+    }
+
+    /// Make every subsequent call take `delay` before answering
+    pub fn set_delay(&self, delay: Duration) {
+        *self.delay.lock().unwrap() = Some(delay);
+    }
+
+    /// Number of calls started but neither answered nor cancelled yet
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Highest number of calls in flight at the same time
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Number of calls received for `selector`
+    pub fn call_count(&self, selector: [u8; 4]) -> usize {
+        self.calls
+            .lock()
+            .unwrap()
+            .get(&selector)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Block of every call received through `eth_call_at`, in order
+    pub fn call_blocks(&self) -> Vec<BlockTag> {
+        self.blocks.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl ContractCaller for MockCaller {
+    async fn eth_call(&self, to: &str, data: &[u8]) -> Result<Vec<u8>> {
+        let selector: [u8; 4] = data
+            .get(..4)
+            .and_then(|s| s.try_into().ok())
+            .ok_or_else(|| eyre!("call data too short"))?;
+        *self.calls.lock().unwrap().entry(selector).or_default() += 1;
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        let _guard = InFlightGuard(&self.in_flight);
+        let delay = *self.delay.lock().unwrap();
+        if let Some(delay) = delay {
+            tokio::time::sleep(delay).await;
+        }
+        if let Some(error) = self.errors.lock().unwrap().get(&selector) {
+            return Err(error.clone().into());
+        }
+        if let Ok(to) = to.parse::<Address>() {
+            let key = (to, data.to_vec());
+            if let Some(response) = self.contract_responses.lock().unwrap().get(&key) {
+                return Ok(response.clone());
+            }
+        }
+        if let Some(response) = self.call_responses.lock().unwrap().get(data) {
+            return Ok(response.clone());
+        }
+        self.responses
+            .lock()
+            .unwrap()
+            .get(&selector)
+            .cloned()
+            .ok_or_else(|| eyre!("no mock response for selector 0x{}", hex::encode(selector)))
+    }
+
+    async fn get_logs(&self, filter: serde_json::Value) -> Result<Vec<Log>> {
+        let block = |key: &str| {
+            filter[key]
+                .as_str()
+                .and_then(|block| u64::from_str_radix(block.trim_start_matches("0x"), 16).ok())
+                .ok_or_else(|| eyre!("invalid {} in log filter", key))
+        };
+        let range = block("fromBlock")?..=block("toBlock")?;
+        Ok(self
+            .logs
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|log| {
+                u64::from_str_radix(log.block_number.trim_start_matches("0x"), 16)
+                    .is_ok_and(|block| range.contains(&block))
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Historical state is only modelled through [`Self::set_response_at`]: other blocks answer
+    /// like the latest one
+    async fn eth_call_at(&self, to: &str, data: &[u8], block: BlockTag) -> Result<Vec<u8>> {
+        self.blocks.lock().unwrap().push(block);
+        if let (BlockTag::Number(number), Some(selector)) = (block, data.get(..4)) {
+            let selector: [u8; 4] = selector.try_into().unwrap();
+            if let Some(response) = self
+                .block_responses
+                .lock()
+                .unwrap()
+                .get(&(number, selector))
+            {
+                return Ok(response.clone());
+            }
+        }
+        self.eth_call(to, data).await
+    }
+
+    async fn block_number_at(&self, block: BlockTag) -> Result<u64> {
+        match block {
+            BlockTag::Number(number) => Ok(number),
+            BlockTag::Finalized => (*self.finalized_block.lock().unwrap())
+                .ok_or_else(|| eyre!("no mock finalized block")),
+            block => Err(eyre!("no mock block number for {:?}", block)),
+        }
+    }
+}
+
+/// Candidate with a valid Ed25519 key derived from `seed` and `power` in consensus units
+/// (the contract reports stake in units of 1e10)
+pub fn validator_candidate(seed: u8, power: u64) -> ValidatorElectionInfo {
+    let verification_key = ed25519_consensus::SigningKey::from([seed; 32]).verification_key();
+    ValidatorElectionInfo {
+        consensus_address: Address::repeat_byte(seed),
+        voting_power: U256::from(power) * U256::from(10u64.pow(10)),
+        operator_address: Address::repeat_byte(seed.wrapping_add(0x80)),
+        tendermint_pub_key: verification_key.to_bytes().to_vec(),
+        status: None,
+        active: None,
+        unbonding: None,
+        self_stake: None,
+    }
+}
+
+/// The StakeHub ABI used by the client, including its optional views
+pub fn stake_hub_abi() -> JsonAbi {
+    load_stake_hub_abi().unwrap()
+}
+
+/// Selector of the StakeHub function `name`
+pub fn stake_hub_selector(name: &str) -> [u8; 4] {
+    stake_hub_abi().function(name).unwrap()[0].selector().0
+}
+
+/// ABI-encode `values` as the return data of the StakeHub function `name`
+pub fn encode_stake_hub_output(name: &str, values: &[DynSolValue]) -> Vec<u8> {
+    stake_hub_abi().function(name).unwrap()[0]
+        .abi_encode_output(values)
+        .unwrap()
+}
+
+/// Epoch length reported by [`mock_stake_hub`]
+pub const MOCK_EPOCH_LENGTH: u64 = 100;
+
+/// Mock StakeHub answering `epochLength`, `maxElectedValidators` and `getValidatorElectionInfo`
+pub fn mock_stake_hub(max_elected: u64, candidates: &[ValidatorElectionInfo]) -> MockCaller {
+    let caller = MockCaller::new();
+    set_epoch_length(&caller, MOCK_EPOCH_LENGTH);
+    set_max_elected(&caller, max_elected);
+    set_candidates(&caller, candidates);
+    caller
+}
+
+pub fn set_epoch_length(caller: &MockCaller, epoch_length: u64) {
+    caller.set_response(
+        stake_hub_selector("epochLength"),
+        encode_stake_hub_output(
+            "epochLength",
+            &[DynSolValue::from(U256::from(epoch_length))],
+        ),
+    );
+}
+
+pub fn set_max_elected(caller: &MockCaller, max_elected: u64) {
+    caller.set_response(
+        stake_hub_selector("maxElectedValidators"),
+        encode_stake_hub_output(
+            "maxElectedValidators",
+            &[DynSolValue::from(U256::from(max_elected))],
+        ),
+    );
+}
+
+pub fn set_candidates(caller: &MockCaller, candidates: &[ValidatorElectionInfo]) {
+    caller.set_response(
+        stake_hub_selector("getValidatorElectionInfo"),
+        encode_candidates(candidates),
+    );
+}
+
+/// Like [`set_candidates`], for calls against block `block` only
+pub fn set_candidates_at(caller: &MockCaller, block: u64, candidates: &[ValidatorElectionInfo]) {
+    caller.set_response_at(
+        block,
+        stake_hub_selector("getValidatorElectionInfo"),
+        encode_candidates(candidates),
+    );
+}
+
+fn encode_candidates(candidates: &[ValidatorElectionInfo]) -> Vec<u8> {
+    let output = [
+        DynSolValue::Array(
+            candidates
+                .iter()
+                .map(|c| DynSolValue::from(c.consensus_address))
+                .collect(),
+        ),
+        DynSolValue::Array(
+            candidates
+                .iter()
+                .map(|c| DynSolValue::from(c.voting_power))
+                .collect(),
+        ),
+        DynSolValue::Array(
+            candidates
+                .iter()
+                .map(|c| DynSolValue::from(c.operator_address))
+                .collect(),
+        ),
+        DynSolValue::Array(
+            candidates
+                .iter()
+                .map(|c| DynSolValue::Bytes(c.tendermint_pub_key.clone()))
+                .collect(),
+        ),
+        DynSolValue::from(U256::from(candidates.len())),
+    ];
+    encode_stake_hub_output("getValidatorElectionInfo", &output)
+}
@@ -1,17 +1,224 @@
 // Validator Executor module
 // Handles pre-execution and post-execution logic for validator set management
 
-use crate::ethereum_rpc::EthereumRPC;
-use crate::stake_hub_client::StakeHubClient;
+use crate::block_height::BlockHeightSource;
+use crate::diff::ValidatorSetDiff;
+use crate::epoch::{EpochError, EpochSchedule};
+use crate::ethereum_rpc::{BlockTag, EthereumRPC};
+use crate::genesis::GenesisValidatorInfo;
+use crate::metrics::{self, MetricsSnapshot};
+use crate::network::Network;
+use crate::pubkey::{to_public_key_with, PublicKeyError};
+use crate::snapshot::ValidatorSnapshot;
+use crate::stake_hub_client::{candidates_fingerprint, ElectedValidators, StakeHubClient};
 use crate::system_contracts::STAKE_HUB_CONTRACT;
-use color_eyre::eyre::Result;
-use std::sync::Arc;
-use tracing::{info, warn};
+use alloy_primitives::{Address, B256, U256};
+use color_eyre::eyre::{eyre, Result};
+use ed25519_consensus::{Signature, VerificationKey};
+use futures::{Stream, StreamExt};
+use malachitebft_eth_types::ValidatorSet;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Write;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::{broadcast, watch, Mutex};
+use tokio::time::Instant;
+use tracing::{debug, error, info, warn};
+
+/// Capacity of the validator set update channel; slower subscribers observe a lag error
+const VALIDATOR_SET_UPDATE_CAPACITY: usize = 16;
+
+/// Default minimum time between two "validator set unchanged" log lines
+pub const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Outcome of [`ValidatorExecutor::get_validator_set_from_stake_hub`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidatorSetFetch {
+    /// StakeHub elected at least one validator
+    Available(ValidatorSet),
+    /// StakeHub was read but elected no validator
+    Empty,
+    /// StakeHub couldn't be read, for the given reason
+    Unavailable(String),
+}
+
+/// A validator left out by [`build_validator_set`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SetBuildWarning {
+    /// The pubkey isn't 32 bytes long
+    InvalidPubkeyLength {
+        consensus_address: Address,
+        len: usize,
+    },
+    /// The pubkey isn't a valid Ed25519 point
+    InvalidPubkey { consensus_address: Address },
+    /// The pubkey isn't canonically encoded, under strict pubkey validation
+    NonCanonicalPubkey { consensus_address: Address },
+}
+
+impl fmt::Display for SetBuildWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetBuildWarning::InvalidPubkeyLength {
+                consensus_address,
+                len,
+            } => write!(
+                f,
+                "validator {} has a {}-byte pubkey, expected 32",
+                consensus_address, len
+            ),
+            SetBuildWarning::InvalidPubkey { consensus_address } => write!(
+                f,
+                "validator {} has an invalid Ed25519 pubkey",
+                consensus_address
+            ),
+            SetBuildWarning::NonCanonicalPubkey { consensus_address } => write!(
+                f,
+                "validator {} has a non-canonical Ed25519 pubkey",
+                consensus_address
+            ),
+        }
+    }
+}
+
+/// A change of the validator set published by [`ValidatorExecutor`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidatorSetUpdate {
+    /// Increases by one with every published change, starting at 1.
+    /// A gap between two received updates means updates were missed.
+    pub sequence: u64,
+    /// Epoch the validator set was fetched for
+    pub epoch: u64,
+    /// The new validator set
+    pub validator_set: ValidatorSet,
+    /// Whether the set was read from a finalized block. A provisional set may still change if
+    /// the block it was read from is reorged out.
+    pub is_final: bool,
+}
+
+/// One line of the audit log set by [`ValidatorExecutor::with_audit_log`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// Epoch the new validator set was fetched for
+    pub epoch: u64,
+    /// First block of `epoch`
+    pub boundary_block: u64,
+    /// From the previously cached set to the new one
+    pub diff: ValidatorSetDiff,
+}
+
+/// How [`ValidatorExecutor::refresh_validator_set`] handles a set too small to be safe
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnsafeSetPolicy {
+    /// Sets with fewer validators are unsafe
+    pub min_validators: usize,
+    /// Fetches retried after an unsafe set before declaring an emergency
+    pub retries: u32,
+    /// Wait before the first retry, doubled before each following one
+    pub backoff: Duration,
+}
+
+impl Default for UnsafeSetPolicy {
+    fn default() -> Self {
+        Self {
+            min_validators: 1,
+            retries: 3,
+            backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// A chain head as reported to [`ValidatorExecutor::observe_head`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChainHead {
+    pub number: u64,
+    pub hash: B256,
+    pub parent_hash: B256,
+}
+
+/// A reorg detected between two consecutively observed heads
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReorgEvent {
+    /// Head observed before the reorg
+    pub previous: ChainHead,
+    /// Head that doesn't build on `previous`
+    pub new: ChainHead,
+}
+
+/// Callback run on every detected reorg, after the caches were invalidated
+pub type ReorgCallback = Box<dyn Fn(&ReorgEvent) + Send + Sync>;
 
 /// Validator Executor
 pub struct ValidatorExecutor {
     /// StakeHub client for validator set management
     stake_hub_client: StakeHubClient,
+    /// Last validator set fetched by [`Self::refresh_validator_set`].
+    /// Only ever replaced as a whole, so readers never observe a partially updated set.
+    cached_validator_set: RwLock<Option<ValidatorSet>>,
+    /// Epoch the cached validator set was last refreshed for
+    cached_epoch: RwLock<Option<u64>>,
+    /// Held for the duration of a refresh; shutdown acquires it to wait for in-flight fetches
+    fetch_lock: Mutex<()>,
+    /// Flipped to `true` once on shutdown
+    shutdown_tx: watch::Sender<bool>,
+    /// Sequence number of the last published [`ValidatorSetUpdate`]
+    update_sequence: AtomicU64,
+    /// Publishes a [`ValidatorSetUpdate`] whenever a refresh changes the validator set
+    updates_tx: broadcast::Sender<ValidatorSetUpdate>,
+    /// Last head passed to [`Self::observe_head`]
+    last_head: RwLock<Option<ChainHead>>,
+    /// Registered with [`Self::on_reorg`]
+    reorg_callbacks: RwLock<Vec<ReorgCallback>>,
+    /// Blocks to wait after an epoch boundary before fetching the new validator set
+    transition_grace_blocks: u64,
+    /// Block whose StakeHub state [`Self::refresh_validator_set`] reads
+    read_tag: BlockTag,
+    /// Where [`Self::current_transition_epoch`] gets the current height
+    height_source: Option<Arc<dyn BlockHeightSource>>,
+    /// Last epoch length read by [`Self::get_epoch_length_from_stake_hub`]
+    epoch_length: RwLock<Option<u64>>,
+    /// Validators left out of the last set built by [`Self::get_validator_set_from_stake_hub`]
+    build_warnings: RwLock<Vec<SetBuildWarning>>,
+    /// Held while a StakeHub fetch is in flight, with the block and result of the last
+    /// completed one
+    last_fetch: Mutex<Option<(BlockTag, ValidatorSetFetch)>>,
+    /// Number of completed StakeHub fetches
+    completed_fetches: AtomicU64,
+    /// Last set built from StakeHub, keyed by its candidate fingerprint and seat count
+    last_build: RwLock<Option<((B256, U256), ValidatorSet)>>,
+    /// Number of validator sets built from StakeHub candidates
+    set_builds: AtomicU64,
+    /// Snapshot file served instead of StakeHub, set by [`Self::with_emergency_override`]
+    emergency_override: Option<PathBuf>,
+    /// Keys of which one must have signed the emergency override file
+    emergency_override_signers: Vec<VerificationKey>,
+    /// Receives an [`AuditRecord`] per validator set change
+    audit_log: Option<std::sync::Mutex<Box<dyn Write + Send>>>,
+    /// Applied to the sets fetched by [`Self::refresh_validator_set`]
+    unsafe_set_policy: UnsafeSetPolicy,
+    /// Whether the last refresh gave up on an unsafe set
+    emergency: AtomicBool,
+    /// Leave out validators whose pubkey isn't canonically encoded
+    strict_pubkeys: bool,
+    /// Minimum time between two logs of an unchanged validator set
+    heartbeat_interval: Duration,
+    /// When the validator set was last logged, changed or not
+    last_heartbeat: std::sync::Mutex<Option<Instant>>,
+    /// Number of unchanged-set heartbeats logged
+    heartbeats: AtomicU64,
+    /// Validators added or removed by the last change of the cached set
+    last_churn: AtomicUsize,
+    /// Minimum blocks between the boundaries of two StakeHub refreshes, 0 for no limit
+    min_refresh_interval_blocks: u64,
+    /// Boundary block of the epoch the cached set was last fetched for
+    last_refresh_block: RwLock<Option<u64>>,
+    /// Epoch boundaries across epoch length changes, extended as the live epoch length changes
+    epoch_schedule: RwLock<Option<EpochSchedule>>,
 }
 
 impl ValidatorExecutor {
@@ -20,69 +227,1986 @@ impl ValidatorExecutor {
         let stake_hub_client =
             StakeHubClient::new(eth_rpc.clone(), STAKE_HUB_CONTRACT.parse().unwrap())?;
 
-        Ok(Self { stake_hub_client })
+        Ok(Self::with_stake_hub_client(stake_hub_client).with_block_height_source(eth_rpc))
+    }
+
+    /// Create a new ValidatorExecutor using the system contracts of `network`
+    pub fn for_network(eth_rpc: Arc<EthereumRPC>, network: Network) -> Result<Self> {
+        let stake_hub_client = StakeHubClient::new(eth_rpc.clone(), network.stake_hub_address())?;
+
+        Ok(Self::with_stake_hub_client(stake_hub_client).with_block_height_source(eth_rpc))
+    }
+
+    /// Create a new ValidatorExecutor on top of an existing StakeHub client
+    pub fn with_stake_hub_client(stake_hub_client: StakeHubClient) -> Self {
+        Self {
+            stake_hub_client,
+            cached_validator_set: RwLock::new(None),
+            cached_epoch: RwLock::new(None),
+            fetch_lock: Mutex::new(()),
+            shutdown_tx: watch::Sender::new(false),
+            update_sequence: AtomicU64::new(0),
+            updates_tx: broadcast::Sender::new(VALIDATOR_SET_UPDATE_CAPACITY),
+            last_head: RwLock::new(None),
+            reorg_callbacks: RwLock::new(Vec::new()),
+            transition_grace_blocks: 0,
+            read_tag: BlockTag::Latest,
+            height_source: None,
+            epoch_length: RwLock::new(None),
+            build_warnings: RwLock::new(Vec::new()),
+            last_fetch: Mutex::new(None),
+            completed_fetches: AtomicU64::new(0),
+            last_build: RwLock::new(None),
+            set_builds: AtomicU64::new(0),
+            emergency_override: None,
+            emergency_override_signers: Vec::new(),
+            audit_log: None,
+            unsafe_set_policy: UnsafeSetPolicy::default(),
+            emergency: AtomicBool::new(false),
+            strict_pubkeys: false,
+            heartbeat_interval: DEFAULT_HEARTBEAT_INTERVAL,
+            last_heartbeat: std::sync::Mutex::new(None),
+            heartbeats: AtomicU64::new(0),
+            last_churn: AtomicUsize::new(0),
+            min_refresh_interval_blocks: 0,
+            last_refresh_block: RwLock::new(None),
+            epoch_schedule: RwLock::new(None),
+        }
+    }
+
+    /// Read validator sets from finalized blocks instead of the latest one, so published
+    /// updates are final rather than provisional. [`Self::refresh_validator_set`] then reads
+    /// the set of an epoch at the block deciding it, and reports it unavailable until that
+    /// block is finalized.
+    pub fn with_finalized_reads(mut self, finalized: bool) -> Self {
+        self.read_tag = if finalized {
+            BlockTag::Finalized
+        } else {
+            BlockTag::Latest
+        };
+        self
+    }
+
+    /// Get the current height from `source` instead of the execution client, e.g. a
+    /// [`LocalBlockHeight`](crate::block_height::LocalBlockHeight) fed by the blocks consensus
+    /// commits, saving an RPC round trip per check
+    pub fn with_block_height_source(mut self, source: Arc<dyn BlockHeightSource>) -> Self {
+        self.height_source = Some(source);
+        self
+    }
+
+    /// Fetch the validator set of a new epoch `grace` blocks after its boundary instead of
+    /// right at it, for StakeHub state that settles a few blocks late.
+    ///
+    /// The set only takes effect once fetched, so the first `grace` blocks of the epoch keep the
+    /// previous set. Every node must use the same value, otherwise they switch sets at different
+    /// heights and disagree on the validators of those blocks.
+    pub fn with_transition_grace_blocks(mut self, grace: u64) -> Self {
+        self.transition_grace_blocks = grace;
+        self
+    }
+
+    /// Serve the validator set of the [`ValidatorSnapshot`] JSON file at `path` instead of
+    /// electing one from StakeHub, as an escape hatch when the contract can't elect a usable set.
+    ///
+    /// Only takes effect with `enabled`, so the path can stay configured while the override is
+    /// off. The file must be signed by one of the operator keys in `signers`: the sidecar file
+    /// `<path>.sig` holds hex Ed25519 signatures over the exact file bytes, one per line. The
+    /// file is read and verified on every fetch; an unsigned, badly signed or unreadable file
+    /// makes the fetch unavailable rather than falling back to StakeHub. Every node must serve
+    /// the same file, otherwise they disagree on the validators.
+    pub fn with_emergency_override(
+        mut self,
+        path: impl Into<PathBuf>,
+        enabled: bool,
+        signers: impl IntoIterator<Item = VerificationKey>,
+    ) -> Self {
+        let path = path.into();
+        self.emergency_override_signers = signers.into_iter().collect();
+        if enabled {
+            error!(
+                "🚨 Emergency validator set override enabled: validator sets are read from {} instead of StakeHub",
+                path.display()
+            );
+            self.emergency_override = Some(path);
+        } else {
+            warn!(
+                "Emergency validator set override at {} is configured but disabled",
+                path.display()
+            );
+            self.emergency_override = None;
+        }
+        self
+    }
+
+    /// Append an [`AuditRecord`] JSON line to `writer` for every change of the cached validator
+    /// set, flushed before the change takes effect. If the record can't be written, the refresh
+    /// fails and the cache keeps its previous set, so every applied change is logged.
+    pub fn with_audit_log(mut self, writer: impl Write + Send + 'static) -> Self {
+        self.audit_log = Some(std::sync::Mutex::new(Box::new(writer)));
+        self
+    }
+
+    /// Hold the last safe validator set when a refresh fetches one with fewer than
+    /// `policy.min_validators` validators, retrying the fetch with backoff before declaring an
+    /// emergency (see [`Self::in_emergency`])
+    pub fn with_unsafe_set_policy(mut self, policy: UnsafeSetPolicy) -> Self {
+        self.unsafe_set_policy = policy;
+        self
+    }
+
+    /// Also leave validators whose pubkey isn't canonically encoded out of the sets built from
+    /// StakeHub, on top of the point validation always done
+    pub fn with_strict_pubkey_validation(mut self, strict: bool) -> Self {
+        self.strict_pubkeys = strict;
+        self
+    }
+
+    /// Log that a refresh found the validator set unchanged at most once per `interval`.
+    /// Changes are always logged, with the validators they affect.
+    pub fn with_heartbeat_interval(mut self, interval: Duration) -> Self {
+        self.heartbeat_interval = interval;
+        self
+    }
+
+    /// Refresh the validator set at most once per `blocks` blocks, to bound RPC load on chains
+    /// with very short epochs. Refreshes for boundaries less than `blocks` after the last fetched
+    /// one keep the cached set, and the next refresh past the interval catches up on all of them.
+    /// Every node must use the same value, otherwise they switch sets at different epochs.
+    pub fn with_min_refresh_interval_blocks(mut self, blocks: u64) -> Self {
+        self.min_refresh_interval_blocks = blocks;
+        self
+    }
+
+    /// Place epoch boundaries by `schedule` rather than by the current epoch length, so epochs
+    /// stay numbered right on chains whose epoch length changed. Live epoch lengths passed to
+    /// the executor that differ from the schedule's last one extend it.
+    pub fn with_epoch_schedule(self, schedule: EpochSchedule) -> Self {
+        *self.epoch_schedule.write().unwrap() = Some(schedule);
+        self
+    }
+
+    /// Schedule for the live `epoch_length` read at `observed_at`: the [`EpochSchedule`] if
+    /// set, extended if the length changed since its last run of epochs, or a constant schedule
+    /// otherwise. A change read at an unknown block can't be placed and is left for later.
+    fn epoch_schedule(
+        &self,
+        epoch_length: u64,
+        observed_at: Option<u64>,
+    ) -> Result<EpochSchedule, EpochError> {
+        let Some(schedule) = self.epoch_schedule.read().unwrap().clone() else {
+            return EpochSchedule::new(epoch_length);
+        };
+        let Some(block) = observed_at.filter(|_| schedule.latest_epoch_length() != epoch_length)
+        else {
+            return Ok(schedule);
+        };
+
+        let mut stored = self.epoch_schedule.write().unwrap();
+        let stored = stored.get_or_insert(schedule);
+        if stored.latest_epoch_length() != epoch_length {
+            stored.extend(block, epoch_length)?;
+            info!(
+                "Epoch length changed to {} blocks as of block #{}",
+                epoch_length, block
+            );
+        }
+        Ok(stored.clone())
+    }
+
+    /// If committing `block_number` is the point where the validator set of a new epoch must be
+    /// fetched, return that epoch. With no grace period this is the block right before the
+    /// boundary, and `grace` blocks later otherwise.
+    pub fn transition_epoch_at(&self, block_number: u64, epoch_length: u64) -> Option<u64> {
+        let boundary = block_number
+            .checked_add(1)?
+            .checked_sub(self.transition_grace_blocks)?;
+        let schedule = self.epoch_schedule(epoch_length, Some(block_number)).ok()?;
+        schedule
+            .is_epoch_boundary(boundary)
+            .then(|| schedule.epoch_of(boundary))
+    }
+
+    /// [`Self::transition_epoch_at`] the current height of the block height source
+    pub async fn current_transition_epoch(&self, epoch_length: u64) -> Result<Option<u64>> {
+        let source = self
+            .height_source
+            .as_ref()
+            .ok_or_else(|| eyre!("No block height source configured"))?;
+        let height = source.block_height().await?;
+        Ok(self.transition_epoch_at(height, epoch_length))
+    }
+
+    /// Whether the last [`Self::refresh_validator_set`] ran out of retries on an unsafe set and
+    /// kept the previous one. Cleared by the next safe set.
+    pub fn in_emergency(&self) -> bool {
+        self.emergency.load(Ordering::SeqCst)
+    }
+
+    /// The validator set stored by the last successful [`Self::refresh_validator_set`]
+    pub fn cached_validator_set(&self) -> Option<ValidatorSet> {
+        self.cached_validator_set.read().unwrap().clone()
+    }
+
+    /// KPIs of the cached validator set, for one-shot export as JSON.
+    /// Fails if no set has been fetched yet.
+    pub fn metrics_snapshot(&self) -> Result<MetricsSnapshot> {
+        let validator_set = self
+            .cached_validator_set()
+            .ok_or_else(|| eyre!("No validator set cached yet"))?;
+        let voting_powers: Vec<u64> = validator_set.iter().map(|v| v.voting_power).collect();
+
+        Ok(MetricsSnapshot {
+            epoch: *self.cached_epoch.read().unwrap(),
+            validator_count: voting_powers.len(),
+            total_voting_power: metrics::total_voting_power(&voting_powers),
+            nakamoto_coefficient: metrics::nakamoto_coefficient(&voting_powers),
+            gini_coefficient: metrics::gini_coefficient(&voting_powers),
+            churn: self.last_churn.load(Ordering::Relaxed),
+        })
+    }
+
+    /// 1-based rank of `operator` among the elected validators, highest voting power first,
+    /// like [`crate::stake_hub_client::EligibilityReport::rank`]. Reads the same state as [`Self::is_operator_elected`];
+    /// `None` if the operator isn't elected.
+    pub async fn my_rank(&self, operator: Address) -> Result<Option<usize>> {
+        let elected_validators = self
+            .stake_hub_client
+            .get_top_validators_by_voting_power_at(self.read_tag)
+            .await?;
+
+        Ok(elected_validators
+            .operator_addrs
+            .iter()
+            .position(|address| *address == operator)
+            .map(|index| index + 1))
+    }
+
+    /// Subscribe to validator set changes
+    pub fn subscribe_updates(&self) -> broadcast::Receiver<ValidatorSetUpdate> {
+        self.updates_tx.subscribe()
+    }
+
+    /// Run `callback` whenever [`Self::observe_head`] detects a reorg
+    pub fn on_reorg(&self, callback: impl Fn(&ReorgEvent) + Send + Sync + 'static) {
+        self.reorg_callbacks
+            .write()
+            .unwrap()
+            .push(Box::new(callback));
+    }
+
+    /// Record a new chain head and check it against the previous one.
+    ///
+    /// A head is a reorg if it is the next block but its parent isn't the previous head, or if
+    /// it replaces a block at or below the previous head's height. Heads skipping blocks can't
+    /// be checked and are accepted as is. On a reorg, the cached validator set and the cached
+    /// StakeHub values read at `latest` are dropped before the [`Self::on_reorg`] callbacks run.
+    pub fn observe_head(&self, head: ChainHead) -> Option<ReorgEvent> {
+        let previous = self.last_head.write().unwrap().replace(head)?;
+        let reorged = if previous.number.checked_add(1) == Some(head.number) {
+            head.parent_hash != previous.hash
+        } else {
+            head.number <= previous.number && head.hash != previous.hash
+        };
+        if !reorged {
+            return None;
+        }
+
+        warn!(
+            "Reorg detected: head #{} {} replaced by #{} {} (parent {}), invalidating caches",
+            previous.number, previous.hash, head.number, head.hash, head.parent_hash
+        );
+        self.cached_validator_set.write().unwrap().take();
+        self.cached_epoch.write().unwrap().take();
+        self.stake_hub_client.invalidate_max_elected_cache();
+
+        let event = ReorgEvent {
+            previous,
+            new: head,
+        };
+        for callback in self.reorg_callbacks.read().unwrap().iter() {
+            callback(&event);
+        }
+        Some(event)
+    }
+
+    /// Handle a reorg to `new_head` whose branch forks from the old one after block
+    /// `common_ancestor`.
+    ///
+    /// Unlike [`Self::observe_head`], which drops every cache, only state read above the common
+    /// ancestor is invalidated: if the cached validator set was fetched after that block (see
+    /// [`Self::transition_epoch_at`]), it and the cached StakeHub values are dropped, and the set
+    /// is fetched again if `new_head` has reached that point too. Returns the recomputed set, or
+    /// `None` if nothing had to be recomputed.
+    pub async fn handle_reorg(
+        &self,
+        new_head: ChainHead,
+        common_ancestor: u64,
+    ) -> Result<Option<ValidatorSet>> {
+        *self.last_head.write().unwrap() = Some(new_head);
+        let Some(epoch) = *self.cached_epoch.read().unwrap() else {
+            return Ok(None);
+        };
+
+        let fetched_at = self
+            .epoch_start_block(epoch)
+            .await?
+            .saturating_add(self.transition_grace_blocks)
+            .saturating_sub(1);
+        if fetched_at <= common_ancestor {
+            return Ok(None);
+        }
+
+        warn!(
+            "Reorg below block #{} forks from #{}, invalidating the validator set of epoch {}",
+            fetched_at, common_ancestor, epoch
+        );
+        self.cached_validator_set.write().unwrap().take();
+        self.cached_epoch.write().unwrap().take();
+        self.epoch_length.write().unwrap().take();
+        self.stake_hub_client.invalidate_max_elected_cache();
+
+        if new_head.number < fetched_at {
+            return Ok(None);
+        }
+        self.refresh_validator_set(epoch).await
+    }
+
+    /// Fetch the validator set for `epoch` from StakeHub and store it in the cache.
+    /// If the set differs from the cached one, a [`ValidatorSetUpdate`] is published.
+    /// Returns `None` and keeps the cache if StakeHub is unavailable or elected no validator.
+    /// A set below the [`UnsafeSetPolicy`] is fetched again with backoff, and if it stays unsafe
+    /// the cache is kept as well.
+    ///
+    /// If [`Self::shutdown`] is called while the fetch is in flight, the fetch is abandoned and
+    /// the cache keeps its previous value.
+    ///
+    /// Within the [`Self::with_min_refresh_interval_blocks`] of the last fetch, the cached set is
+    /// returned without fetching, and recorded as the set of `epoch`.
+    pub async fn refresh_validator_set(&self, epoch: u64) -> Result<Option<ValidatorSet>> {
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        let _guard = self.fetch_lock.lock().await;
+        if *shutdown_rx.borrow_and_update() {
+            return Err(eyre!("Validator executor is shut down"));
+        }
+
+        let boundary = if self.min_refresh_interval_blocks > 0 {
+            Some(self.epoch_start_block(epoch).await?)
+        } else {
+            None
+        };
+        if let Some(throttled) = self.throttled_refresh(epoch, boundary) {
+            return Ok(Some(throttled));
+        }
+
+        let fetch = tokio::select! {
+            fetch = self.fetch_safe_validator_set(epoch) => fetch,
+            _ = shutdown_rx.wait_for(|shutdown| *shutdown) => {
+                warn!("Shutdown requested, abandoning in-flight validator set fetch");
+                return Err(eyre!("Validator set fetch abandoned on shutdown"));
+            }
+        };
+
+        let ValidatorSetFetch::Available(validator_set) = fetch else {
+            return Ok(None);
+        };
+        let previous = self.cached_validator_set();
+        if previous.as_ref() == Some(&validator_set) {
+            *self.cached_epoch.write().unwrap() = Some(epoch);
+            *self.last_refresh_block.write().unwrap() = boundary;
+            self.log_heartbeat(epoch, &validator_set);
+            return Ok(Some(validator_set));
+        }
+        let diff = ValidatorSetDiff::between(
+            previous.iter().flat_map(|set| set.iter().map(Into::into)),
+            validator_set.iter().map(Into::into),
+        );
+        self.log_changes(epoch, &diff);
+        let churn = diff.added.len() + diff.removed.len();
+        self.write_audit_record(epoch, diff).await?;
+        self.last_churn.store(churn, Ordering::Relaxed);
+        *self.cached_validator_set.write().unwrap() = Some(validator_set.clone());
+        *self.cached_epoch.write().unwrap() = Some(epoch);
+        *self.last_refresh_block.write().unwrap() = boundary;
+        self.publish_update(epoch, validator_set.clone());
+        Ok(Some(validator_set))
+    }
+
+    /// The cached set, if a refresh for the epoch starting at `boundary` falls within the minimum
+    /// refresh interval of the last fetch. The cached set then serves `epoch`, which is recorded.
+    fn throttled_refresh(&self, epoch: u64, boundary: Option<u64>) -> Option<ValidatorSet> {
+        let last = (*self.last_refresh_block.read().unwrap())?;
+        let since_last = boundary?.checked_sub(last)?;
+        if since_last >= self.min_refresh_interval_blocks {
+            return None;
+        }
+        let cached = self.cached_validator_set()?;
+        debug!(
+            "Skipping validator set refresh for epoch {}: last refresh was {} blocks ago, under the {} block minimum",
+            epoch, since_last, self.min_refresh_interval_blocks
+        );
+        *self.cached_epoch.write().unwrap() = Some(epoch);
+        Some(cached)
+    }
+
+    /// Fetch the validator set, retrying per the [`UnsafeSetPolicy`] while it is too small.
+    /// An unsafe set left after the last retry is reported as unavailable.
+    async fn fetch_safe_validator_set(&self, epoch: u64) -> ValidatorSetFetch {
+        let block = match self.epoch_read_block(epoch).await {
+            Ok(block) => block,
+            Err(e) => {
+                info!("Validator set for epoch {} not available yet: {}", epoch, e);
+                return ValidatorSetFetch::Unavailable(format!("{:#}", e));
+            }
+        };
+        let policy = self.unsafe_set_policy;
+        let mut backoff = policy.backoff;
+        let mut attempt = 0;
+        loop {
+            let fetch = self.get_validator_set_at(block).await;
+            let size = match &fetch {
+                ValidatorSetFetch::Available(set) if set.len() < policy.min_validators => set.len(),
+                ValidatorSetFetch::Available(_) => {
+                    self.emergency.store(false, Ordering::SeqCst);
+                    return fetch;
+                }
+                // Nothing to tell about the set, so an emergency carries on
+                _ => return fetch,
+            };
+
+            if attempt == policy.retries {
+                error!(
+                    "🚨 Validator set for epoch {} still has {} validators after {} retries, below the safe minimum of {}; keeping the last safe set",
+                    epoch, size, policy.retries, policy.min_validators
+                );
+                self.emergency.store(true, Ordering::SeqCst);
+                return ValidatorSetFetch::Unavailable(format!(
+                    "unsafe validator set of {} validators, below the minimum of {}",
+                    size, policy.min_validators
+                ));
+            }
+            attempt += 1;
+            warn!(
+                "Validator set for epoch {} has {} validators, below the safe minimum of {}; retry {}/{} in {:?}",
+                epoch, size, policy.min_validators, attempt, policy.retries, backoff
+            );
+            tokio::time::sleep(backoff).await;
+            backoff = backoff.saturating_mul(2);
+        }
+    }
+
+    /// Block to read the validator set of `epoch` at: the read tag, or with finalized reads the
+    /// block deciding the epoch, once it is finalized
+    async fn epoch_read_block(&self, epoch: u64) -> Result<BlockTag> {
+        if self.read_tag != BlockTag::Finalized {
+            return Ok(self.read_tag);
+        }
+        let decided_at = self.epoch_start_block(epoch).await?.saturating_sub(1);
+        let finalized = self.stake_hub_client.get_finalized_block_number().await?;
+        if finalized < decided_at {
+            return Err(eyre!(
+                "finalized block #{} is behind block #{} deciding epoch {}",
+                finalized,
+                decided_at,
+                epoch
+            ));
+        }
+        Ok(BlockTag::Number(decided_at))
+    }
+
+    /// Log the unchanged set unless the last log is less than the heartbeat interval old
+    fn log_heartbeat(&self, epoch: u64, validator_set: &ValidatorSet) {
+        let now = Instant::now();
+        let mut last_heartbeat = self.last_heartbeat.lock().unwrap();
+        if last_heartbeat.is_some_and(|last| now.duration_since(last) < self.heartbeat_interval) {
+            return;
+        }
+        *last_heartbeat = Some(now);
+        self.heartbeats.fetch_add(1, Ordering::Relaxed);
+        info!(
+            "Validator set unchanged at epoch {} ({} validators)",
+            epoch,
+            validator_set.len()
+        );
+    }
+
+    fn log_changes(&self, epoch: u64, diff: &ValidatorSetDiff) {
+        *self.last_heartbeat.lock().unwrap() = Some(Instant::now());
+        info!(
+            "Validator set diff at epoch {}: {} added, {} removed, {} changed",
+            epoch,
+            diff.added.len(),
+            diff.removed.len(),
+            diff.changed.len()
+        );
+        for validator in &diff.added {
+            info!(
+                "   ➕ {} (voting power {})",
+                validator.consensus_address, validator.voting_power
+            );
+        }
+        for validator in &diff.removed {
+            info!(
+                "   ➖ {} (voting power {})",
+                validator.consensus_address, validator.voting_power
+            );
+        }
+        for change in &diff.changed {
+            info!(
+                "   🔁 {} (voting power {} -> {})",
+                change.after.consensus_address,
+                change.before.voting_power,
+                change.after.voting_power
+            );
+        }
     }
 
-    /// Check if current block is at epoch boundary
+    async fn write_audit_record(&self, epoch: u64, diff: ValidatorSetDiff) -> Result<()> {
+        let Some(audit_log) = &self.audit_log else {
+            return Ok(());
+        };
+        if diff.is_empty() {
+            return Ok(());
+        }
+
+        let record = AuditRecord {
+            epoch,
+            boundary_block: self.epoch_start_block(epoch).await?,
+            diff,
+        };
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+
+        let mut writer = audit_log.lock().unwrap();
+        writer
+            .write_all(&line)
+            .and_then(|()| writer.flush())
+            .map_err(|e| eyre!("Failed to write the validator set audit log: {}", e))
+    }
+
+    fn publish_update(&self, epoch: u64, validator_set: ValidatorSet) {
+        let sequence = self.update_sequence.fetch_add(1, Ordering::SeqCst) + 1;
+        info!(
+            "Validator set changed at epoch {}, publishing update #{}",
+            epoch, sequence
+        );
+        // Sending only fails when nobody is subscribed
+        let _ = self.updates_tx.send(ValidatorSetUpdate {
+            sequence,
+            epoch,
+            validator_set,
+            is_final: self.read_tag == BlockTag::Finalized,
+        });
+    }
+
+    /// Stop accepting refreshes and wait until any in-flight fetch has completed or been abandoned
+    pub async fn shutdown(&self) {
+        self.shutdown_tx.send_replace(true);
+        let _guard = self.fetch_lock.lock().await;
+        info!("Validator executor shut down");
+    }
+
+    /// Check if current block is at epoch boundary.
+    /// Never true for block 0 or a zero epoch length.
     pub async fn is_epoch_boundary(&self, block_number: u64, epoch_length: u64) -> bool {
-        block_number > 0 && block_number % epoch_length == 0
+        self.epoch_schedule(epoch_length, Some(block_number))
+            .is_ok_and(|schedule| schedule.is_epoch_boundary(block_number))
     }
 
-    /// Get epoch length from StakeHub contract
+    /// Get epoch length from StakeHub contract, and cache it
     pub async fn get_epoch_length_from_stake_hub(&self) -> Result<u64> {
-        self.stake_hub_client.get_epoch_length().await
+        let epoch_length = self.stake_hub_client.get_epoch_length().await?;
+        *self.epoch_length.write().unwrap() = Some(epoch_length);
+        Ok(epoch_length)
+    }
+
+    /// The cached epoch length, fetched from StakeHub if nothing is cached yet
+    async fn cached_epoch_length(&self) -> Result<u64> {
+        let cached = *self.epoch_length.read().unwrap();
+        match cached {
+            Some(epoch_length) => Ok(epoch_length),
+            None => self.get_epoch_length_from_stake_hub().await,
+        }
+    }
+
+    /// First block of `epoch`, by the schedule of the cached epoch length as of the last
+    /// observed head
+    async fn epoch_start_block(&self, epoch: u64) -> Result<u64> {
+        let epoch_length = self.cached_epoch_length().await?;
+        let head = self.last_head.read().unwrap().map(|head| head.number);
+        Ok(self
+            .epoch_schedule(epoch_length, head)?
+            .epoch_start_block(epoch)?)
+    }
+
+    /// Consume `blocks` until they reach or cross an epoch boundary of the schedule of the
+    /// cached epoch length, and return that boundary. A stream jumping over the boundary still
+    /// resolves with it. Fails if the stream ends first.
+    pub async fn wait_for_next_boundary(&self, blocks: impl Stream<Item = u64>) -> Result<u64> {
+        let epoch_length = self.cached_epoch_length().await?;
+        let mut blocks = pin!(blocks);
+
+        let mut previous_epoch = None;
+        while let Some(block) = blocks.next().await {
+            let schedule = self.epoch_schedule(epoch_length, Some(block))?;
+            let epoch = schedule.epoch_of(block);
+            let crossed = previous_epoch.is_some_and(|previous| epoch > previous);
+            if crossed || schedule.is_epoch_boundary(block) {
+                return Ok(schedule.epoch_start_block(epoch)?);
+            }
+            previous_epoch = Some(epoch);
+        }
+        Err(eyre!("Block stream ended before the next epoch boundary"))
     }
 
     /// Get validator set from StakeHub contract and convert to ValidatorSet format
-    /// This is a higher-level function that returns a ValidatorSet for consensus
-    pub async fn get_validator_set_from_stake_hub(
-        &self,
-    ) -> Result<Option<malachitebft_eth_types::ValidatorSet>> {
-        // Get top validators by voting power
-        match self
+    /// This is a higher-level function that returns a ValidatorSet for consensus.
+    /// Validators left out of the set are listed by [`Self::last_build_warnings`]. If they hold
+    /// a third or more of the elected voting power, the rest couldn't reach a quorum on their
+    /// own and the fetch is reported as unavailable instead.
+    ///
+    /// Concurrent calls are coalesced: callers arriving while a fetch is in flight wait for it
+    /// and share its result instead of issuing their own.
+    pub async fn get_validator_set_from_stake_hub(&self) -> ValidatorSetFetch {
+        self.get_validator_set_at(self.read_tag).await
+    }
+
+    /// [`Self::get_validator_set_from_stake_hub`] from the StakeHub state at `block`. Only
+    /// fetches of the same block are coalesced.
+    async fn get_validator_set_at(&self, block: BlockTag) -> ValidatorSetFetch {
+        let completed = self.completed_fetches.load(Ordering::SeqCst);
+        let mut last_fetch = self.last_fetch.lock().await;
+        if self.completed_fetches.load(Ordering::SeqCst) != completed {
+            if let Some((_, fetch)) = last_fetch.as_ref().filter(|(at, _)| *at == block) {
+                return fetch.clone();
+            }
+        }
+
+        // Dropping this future mid-fetch leaves the counter untouched, so a waiter fetches again
+        let fetch = self.fetch_validator_set_from_stake_hub(block).await;
+        *last_fetch = Some((block, fetch.clone()));
+        self.completed_fetches.fetch_add(1, Ordering::SeqCst);
+        fetch
+    }
+
+    async fn fetch_validator_set_from_stake_hub(&self, block: BlockTag) -> ValidatorSetFetch {
+        if let Some(path) = &self.emergency_override {
+            return self.load_emergency_override(path);
+        }
+
+        let (candidates, max_elected) = match self
             .stake_hub_client
-            .get_top_validators_by_voting_power()
+            .get_election_candidates_at(block)
             .await
         {
-            Ok(elected_validators) => {
-                info!(
-                    "✅ Retrieved {} validators from StakeHub",
-                    elected_validators.consensus_addrs.len()
-                );
+            Ok(candidates) => candidates,
+            Err(e) => {
+                warn!("Failed to get validators from StakeHub: {}", e);
+                self.build_warnings.write().unwrap().clear();
+                return ValidatorSetFetch::Unavailable(format!("{:#}", e));
+            }
+        };
+
+        // Same candidates and seats elect the same set, so skip selection and conversion
+        let fingerprint = (candidates_fingerprint(&candidates), max_elected);
+        if let Some((last_fingerprint, validator_set)) = &*self.last_build.read().unwrap() {
+            if *last_fingerprint == fingerprint {
+                debug!("StakeHub candidates unchanged, reusing the last validator set");
+                return ValidatorSetFetch::Available(validator_set.clone());
+            }
+        }
+
+        // Get top validators by voting power
+        let elected_validators = self.stake_hub_client.elect(candidates, max_elected);
+        info!(
+            "✅ Retrieved {} validators from StakeHub",
+            elected_validators.consensus_addrs.len()
+        );
 
-                // Convert to ValidatorSet format
-                let validators: Vec<malachitebft_eth_types::Validator> = elected_validators
-                    .consensus_addrs
-                    .into_iter()
-                    .zip(elected_validators.voting_powers.into_iter())
-                    .zip(elected_validators.operator_addrs.into_iter())
-                    .zip(elected_validators.tendermint_pub_keys.into_iter())
-                    .map(
-                        |(((consensus_addr, voting_power), operator_addr), tendermint_pub_key)| {
-                            let consensus_addr =
-                                malachitebft_eth_types::Address::from(consensus_addr);
-                            let operator_addr =
-                                malachitebft_eth_types::Address::from(operator_addr);
-                            let public_key = malachitebft_eth_types::PublicKey::from_bytes(
-                                tendermint_pub_key.try_into().unwrap(),
-                            );
-
-                            malachitebft_eth_types::Validator {
-                                consensus_address: consensus_addr,
-                                operator_address: operator_addr,
-                                public_key,
-                                voting_power: voting_power as u64,
-                            }
-                        },
-                    )
-                    .collect();
-
-                Ok(Some(malachitebft_eth_types::ValidatorSet::new(validators)))
+        let elected_power: u128 = elected_validators
+            .voting_powers
+            .iter()
+            .map(|&power| u128::from(power))
+            .sum();
+        let (validator_set, warnings) =
+            build_validator_set(elected_validators, self.strict_pubkeys);
+        self.set_builds.fetch_add(1, Ordering::Relaxed);
+        let skipped = !warnings.is_empty();
+        *self.build_warnings.write().unwrap() = warnings;
+        match validator_set {
+            Some(validator_set) => {
+                let usable_power: u128 = validator_set
+                    .iter()
+                    .map(|v| u128::from(v.voting_power))
+                    .sum();
+                if skipped && usable_power * 3 <= elected_power * 2 {
+                    error!(
+                        "🚨 Validators with unusable pubkeys hold {} of the {} elected voting power, the others can't reach a quorum",
+                        elected_power - usable_power,
+                        elected_power
+                    );
+                    self.last_build.write().unwrap().take();
+                    return ValidatorSetFetch::Unavailable(format!(
+                        "only {} of the {} elected voting power has a usable pubkey",
+                        usable_power, elected_power
+                    ));
+                }
+                *self.last_build.write().unwrap() = Some((fingerprint, validator_set.clone()));
+                ValidatorSetFetch::Available(validator_set)
+            }
+            None => {
+                warn!("StakeHub elected no validators with a usable pubkey");
+                self.last_build.write().unwrap().take();
+                ValidatorSetFetch::Empty
             }
+        }
+    }
+
+    fn load_emergency_override(&self, path: &Path) -> ValidatorSetFetch {
+        let snapshot = match std::fs::read(path).map_err(Into::into).and_then(|bytes| {
+            verify_override_signature(path, &bytes, &self.emergency_override_signers)?;
+            ValidatorSnapshot::from_json(std::str::from_utf8(&bytes)?)
+        }) {
+            Ok(snapshot) => snapshot,
             Err(e) => {
-                warn!("Failed to get validators from StakeHub: {}", e);
-                Ok(None)
+                error!(
+                    "🚨 Failed to load the emergency validator set override {}: {:#}",
+                    path.display(),
+                    e
+                );
+                self.build_warnings.write().unwrap().clear();
+                return ValidatorSetFetch::Unavailable(format!(
+                    "emergency override {}: {:#}",
+                    path.display(),
+                    e
+                ));
+            }
+        };
+
+        let (validator_set, warnings) =
+            build_validator_set(snapshot.elected_validators(), self.strict_pubkeys);
+        *self.build_warnings.write().unwrap() = warnings;
+        match validator_set {
+            Some(validator_set) => {
+                error!(
+                    "🚨 Using the emergency validator set override {} ({} validators) instead of StakeHub",
+                    path.display(),
+                    validator_set.len()
+                );
+                ValidatorSetFetch::Available(validator_set)
+            }
+            None => {
+                error!(
+                    "🚨 Emergency validator set override {} has no validator with a usable pubkey",
+                    path.display()
+                );
+                ValidatorSetFetch::Empty
             }
         }
     }
+
+    /// Validators left out of the set built by the last [`Self::get_validator_set_from_stake_hub`]
+    pub fn last_build_warnings(&self) -> Vec<SetBuildWarning> {
+        self.build_warnings.read().unwrap().clone()
+    }
+
+    /// Whether `operator` is in the validator set currently elected by StakeHub
+    pub async fn is_operator_elected(&self, operator: Address) -> Result<bool> {
+        let elected_validators = self
+            .stake_hub_client
+            .get_top_validators_by_voting_power_at(self.read_tag)
+            .await?;
+
+        Ok(elected_validators.operator_addrs.contains(&operator))
+    }
+
+    /// Root of the validator set commitment ([`ElectedValidators::merkle_root`]) that governs
+    /// `block`: the set elected for the block's epoch, as decided by the state at the block
+    /// before that epoch started. The epoch length is read at `block`.
+    pub async fn expected_set_root_for_block(&self, block: u64) -> Result<B256> {
+        let epoch_length = self
+            .stake_hub_client
+            .get_epoch_length_at(BlockTag::Number(block))
+            .await?;
+        let schedule = self.epoch_schedule(epoch_length, Some(block))?;
+        let epoch = schedule.epoch_of(block);
+        // The deciding block always precedes `block` (or is the genesis for epoch 0)
+        let decided_at = schedule.epoch_decision_block(epoch, block)?;
+        let elected_validators = self
+            .stake_hub_client
+            .get_top_validators_by_voting_power_at(BlockTag::Number(decided_at))
+            .await?;
+
+        Ok(elected_validators.merkle_root())
+    }
+
+    /// Diff the `genesis` validators against the set elected from the StakeHub state at
+    /// `at_block`, to show how far the validator set drifted since genesis
+    pub async fn drift_from_genesis(
+        &self,
+        genesis: &[GenesisValidatorInfo],
+        at_block: u64,
+    ) -> Result<ValidatorSetDiff> {
+        let elected_validators = self
+            .stake_hub_client
+            .get_top_validators_by_voting_power_at(BlockTag::Number(at_block))
+            .await?;
+
+        Ok(ValidatorSetDiff::between(
+            genesis.iter().map(Into::into),
+            elected_validators.iter(),
+        ))
+    }
+
+    /// Elected validators of every epoch in `epochs`, each read at the block deciding it (see
+    /// [`crate::epoch::epoch_decision_block`]), with `epoch_length` the length at `head`.
+    /// Fails if any of those blocks is beyond `head`.
+    pub async fn election_history(
+        &self,
+        epochs: RangeInclusive<u64>,
+        epoch_length: u64,
+        head: u64,
+    ) -> Result<Vec<(u64, ElectedValidators)>> {
+        let schedule = self.epoch_schedule(epoch_length, Some(head))?;
+        let epochs: Vec<u64> = epochs.collect();
+        let blocks = epochs
+            .iter()
+            .map(|&epoch| schedule.epoch_decision_block(epoch, head))
+            .collect::<Result<Vec<_>, _>>()?;
+        let elected = self
+            .stake_hub_client
+            .get_top_validators_at_blocks(&blocks)
+            .await?;
+
+        Ok(epochs.into_iter().zip(elected).collect())
+    }
+
+    /// Get the validator set of `epoch` from the StakeHub state at the block deciding it
+    /// (see [`crate::epoch::epoch_decision_block`]), with `epoch_length` the length at `head`.
+    /// Fails if that block is beyond `head`.
+    pub async fn validator_set_for_epoch(
+        &self,
+        epoch: u64,
+        epoch_length: u64,
+        head: u64,
+    ) -> Result<ValidatorSet> {
+        let decided_at = self
+            .epoch_schedule(epoch_length, Some(head))?
+            .epoch_decision_block(epoch, head)?;
+        let elected_validators = self
+            .stake_hub_client
+            .get_top_validators_by_voting_power_at(BlockTag::Number(decided_at))
+            .await?;
+
+        build_validator_set(elected_validators, self.strict_pubkeys)
+            .0
+            .ok_or_else(|| {
+                eyre!(
+                    "No validator with a usable pubkey elected for epoch {}",
+                    epoch
+                )
+            })
+    }
+}
+
+/// Sidecar file holding the signatures of the emergency override file at `path`
+fn override_signature_path(path: &Path) -> PathBuf {
+    let mut signature_path = path.as_os_str().to_owned();
+    signature_path.push(".sig");
+    signature_path.into()
+}
+
+/// Check that one of the signatures next to the override file at `path` is a signature of
+/// `bytes` by one of `signers`
+fn verify_override_signature(path: &Path, bytes: &[u8], signers: &[VerificationKey]) -> Result<()> {
+    if signers.is_empty() {
+        return Err(eyre!("no emergency override signer is configured"));
+    }
+    let signature_path = override_signature_path(path);
+    let signatures = std::fs::read_to_string(&signature_path)
+        .map_err(|e| eyre!("missing signature file {}: {}", signature_path.display(), e))?;
+
+    for line in signatures.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let Some(signature) = hex::decode(line.trim_start_matches("0x"))
+            .ok()
+            .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+            .map(Signature::from)
+        else {
+            return Err(eyre!("malformed signature in {}", signature_path.display()));
+        };
+        if let Some(signer) = signers
+            .iter()
+            .find(|signer| signer.verify(&signature, bytes).is_ok())
+        {
+            info!(
+                "Emergency override {} signed by {}",
+                path.display(),
+                hex::encode(signer.as_bytes())
+            );
+            return Ok(());
+        }
+    }
+    Err(eyre!(
+        "no valid signature by a configured signer in {}",
+        signature_path.display()
+    ))
+}
+
+/// Convert the elected validators into the consensus ValidatorSet format, leaving out
+/// validators whose pubkey isn't a valid Ed25519 key and reporting each one.
+/// Non-canonical pubkeys are left out too when `strict_pubkeys`.
+/// The set is `None` if no validator is left, since a ValidatorSet can't be empty.
+pub fn build_validator_set(
+    elected_validators: ElectedValidators,
+    strict_pubkeys: bool,
+) -> (Option<ValidatorSet>, Vec<SetBuildWarning>) {
+    let mut warnings = Vec::new();
+    let mut validators = Vec::with_capacity(elected_validators.len());
+    for validator in elected_validators.iter() {
+        let consensus_address = validator.consensus_address;
+        let public_key = match to_public_key_with(&validator.tendermint_pub_key, strict_pubkeys) {
+            Ok(public_key) => public_key,
+            Err(PublicKeyError::InvalidLength(len)) => {
+                warnings.push(SetBuildWarning::InvalidPubkeyLength {
+                    consensus_address,
+                    len,
+                });
+                continue;
+            }
+            Err(PublicKeyError::InvalidPoint) => {
+                warnings.push(SetBuildWarning::InvalidPubkey { consensus_address });
+                continue;
+            }
+            Err(PublicKeyError::NonCanonical) => {
+                warnings.push(SetBuildWarning::NonCanonicalPubkey { consensus_address });
+                continue;
+            }
+        };
+
+        validators.push(malachitebft_eth_types::Validator {
+            consensus_address: malachitebft_eth_types::Address::from(consensus_address),
+            operator_address: malachitebft_eth_types::Address::from(validator.operator_address),
+            public_key,
+            voting_power: validator.voting_power,
+        });
+    }
+
+    for warning in &warnings {
+        warn!("Skipping validator: {}", warning);
+    }
+    let validator_set = (!validators.is_empty()).then(|| ValidatorSet::new(validators));
+    (validator_set, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_height::LocalBlockHeight;
+    use crate::stake_hub_client::{get_top_validators_by_voting_power, ElectionConfig};
+    use crate::test_utils::{
+        mock_stake_hub, set_candidates, set_candidates_at, set_epoch_length, set_max_elected,
+        stake_hub_selector, validator_candidate, MOCK_EPOCH_LENGTH,
+    };
+    use ed25519_consensus::SigningKey;
+    use futures::FutureExt;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_shutdown_mid_fetch_keeps_cache_consistent() {
+        let caller = Arc::new(mock_stake_hub(
+            10,
+            &[validator_candidate(1, 10), validator_candidate(2, 20)],
+        ));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let executor = Arc::new(ValidatorExecutor::with_stake_hub_client(client));
+
+        let old_set = executor.refresh_validator_set(1).await.unwrap().unwrap();
+        assert_eq!(executor.cached_validator_set(), Some(old_set.clone()));
+
+        // The next fetch would return a different set, but hangs long enough to be interrupted
+        set_candidates(&caller, &[validator_candidate(3, 30)]);
+        caller.set_delay(Duration::from_secs(30));
+
+        let in_flight = tokio::spawn({
+            let executor = executor.clone();
+            async move { executor.refresh_validator_set(1).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        executor.shutdown().await;
+
+        assert!(in_flight.await.unwrap().is_err());
+        assert_eq!(executor.cached_validator_set(), Some(old_set));
+        assert!(executor.refresh_validator_set(1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_update_sequence_increments_only_on_change() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+        let mut updates = executor.subscribe_updates();
+
+        executor.refresh_validator_set(1).await.unwrap();
+        let first = updates.try_recv().unwrap();
+        assert_eq!((first.sequence, first.epoch), (1, 1));
+
+        // Same set at the next epoch: nothing is published
+        executor.refresh_validator_set(2).await.unwrap();
+        assert!(updates.try_recv().is_err());
+
+        set_candidates(
+            &caller,
+            &[validator_candidate(1, 10), validator_candidate(2, 20)],
+        );
+        executor.refresh_validator_set(3).await.unwrap();
+        let second = updates.try_recv().unwrap();
+        assert_eq!((second.sequence, second.epoch), (2, 3));
+        assert_eq!(second.validator_set.len(), 2);
+        assert_eq!(executor.cached_validator_set(), Some(second.validator_set));
+    }
+
+    #[tokio::test]
+    async fn test_refreshes_within_min_interval_are_coalesced() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let executor =
+            ValidatorExecutor::with_stake_hub_client(client).with_min_refresh_interval_blocks(350);
+        let fetches = || caller.call_count(stake_hub_selector("getValidatorElectionInfo"));
+
+        let first = executor.refresh_validator_set(1).await.unwrap().unwrap();
+        assert_eq!(fetches(), 1);
+
+        // Epochs of 100 blocks: the next three boundaries fall within the interval
+        set_candidates(
+            &caller,
+            &[validator_candidate(1, 10), validator_candidate(2, 20)],
+        );
+        for epoch in 2..=4 {
+            assert_eq!(
+                executor.refresh_validator_set(epoch).await.unwrap(),
+                Some(first.clone())
+            );
+            assert_eq!(executor.metrics_snapshot().unwrap().epoch, Some(epoch));
+        }
+        assert_eq!(fetches(), 1);
+
+        let caught_up = executor.refresh_validator_set(5).await.unwrap().unwrap();
+        assert_eq!(fetches(), 2);
+        assert_eq!(caught_up.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_validator_set_for_epoch_beyond_head_is_rejected() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let client = StakeHubClient::with_caller(caller, Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+
+        let genesis_set = executor.validator_set_for_epoch(0, 100, 0).await.unwrap();
+        assert_eq!(genesis_set.len(), 1);
+
+        let err = executor
+            .validator_set_for_epoch(5, 100, 250)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<EpochError>(),
+            Some(&EpochError::FutureEpoch {
+                epoch: 5,
+                decided_at: 499,
+                head: 250,
+            })
+        );
+        assert!(!executor.is_epoch_boundary(0, 100).await);
+        assert!(!executor.is_epoch_boundary(100, 0).await);
+    }
+
+    #[tokio::test]
+    async fn test_expected_set_root_matches_governing_set() {
+        let candidates = [
+            validator_candidate(1, 10),
+            validator_candidate(2, 20),
+            validator_candidate(3, 30),
+        ];
+        let caller = Arc::new(mock_stake_hub(2, &candidates));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let expected = client
+            .get_top_validators_by_voting_power()
+            .await
+            .unwrap()
+            .merkle_root();
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+
+        let root = executor.expected_set_root_for_block(250).await.unwrap();
+        assert_eq!(root, expected);
+        assert_ne!(root, B256::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_reorg_invalidates_caches() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO)
+            .unwrap()
+            .with_max_elected_cache_ttl(Duration::from_secs(3600));
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+        let reorgs = Arc::new(AtomicU64::new(0));
+        executor.on_reorg({
+            let reorgs = reorgs.clone();
+            move |_| {
+                reorgs.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        let head = |number: u64, hash: u8, parent: u8| ChainHead {
+            number,
+            hash: B256::repeat_byte(hash),
+            parent_hash: B256::repeat_byte(parent),
+        };
+        assert_eq!(executor.observe_head(head(10, 0xa, 0x9)), None);
+        assert_eq!(executor.observe_head(head(11, 0xb, 0xa)), None);
+        executor.refresh_validator_set(1).await.unwrap();
+        assert!(executor.cached_validator_set().is_some());
+
+        // Cached max elected value is served until a reorg drops it
+        set_max_elected(&caller, 1);
+        set_candidates(
+            &caller,
+            &[validator_candidate(1, 10), validator_candidate(2, 20)],
+        );
+        let event = executor.observe_head(head(12, 0xc, 0xf)).unwrap();
+        assert_eq!(event.previous.number, 11);
+        assert_eq!(reorgs.load(Ordering::SeqCst), 1);
+        assert_eq!(executor.cached_validator_set(), None);
+
+        let refreshed = executor.refresh_validator_set(1).await.unwrap().unwrap();
+        assert_eq!(refreshed.len(), 1);
+
+        // Same block again is not a reorg, a competing block at the same height is
+        assert_eq!(executor.observe_head(head(12, 0xc, 0xf)), None);
+        assert!(executor.observe_head(head(12, 0xd, 0xb)).is_some());
+        assert_eq!(reorgs.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_handle_reorg_below_boundary_recomputes_set() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+        let head = |number: u64, hash: u8| ChainHead {
+            number,
+            hash: B256::repeat_byte(hash),
+            parent_hash: B256::ZERO,
+        };
+
+        // Epoch 2 is fetched when block 199 is committed
+        let orphaned = executor.refresh_validator_set(2).await.unwrap().unwrap();
+        set_candidates(
+            &caller,
+            &[validator_candidate(1, 10), validator_candidate(2, 20)],
+        );
+
+        // Forking after the fetch keeps the set
+        let new_head = head(2 * MOCK_EPOCH_LENGTH + 5, 0xa);
+        assert_eq!(
+            executor
+                .handle_reorg(new_head, 2 * MOCK_EPOCH_LENGTH - 1)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(executor.cached_validator_set(), Some(orphaned.clone()));
+
+        // Forking below the boundary recomputes it from the new branch
+        let new_head = head(2 * MOCK_EPOCH_LENGTH + 5, 0xb);
+        let recomputed = executor
+            .handle_reorg(new_head, 2 * MOCK_EPOCH_LENGTH - 10)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(recomputed.len(), 2);
+        assert_ne!(recomputed, orphaned);
+        assert_eq!(executor.cached_validator_set(), Some(recomputed));
+
+        // A new head short of the boundary only invalidates
+        let new_head = head(2 * MOCK_EPOCH_LENGTH - 5, 0xc);
+        assert_eq!(
+            executor
+                .handle_reorg(new_head, 2 * MOCK_EPOCH_LENGTH - 10)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(executor.cached_validator_set(), None);
+    }
+
+    #[tokio::test]
+    async fn test_unsafe_set_keeps_last_safe_set() {
+        let caller = Arc::new(mock_stake_hub(
+            10,
+            &[validator_candidate(1, 10), validator_candidate(2, 20)],
+        ));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client).with_unsafe_set_policy(
+            UnsafeSetPolicy {
+                min_validators: 2,
+                retries: 2,
+                backoff: Duration::from_millis(1),
+            },
+        );
+        let safe = executor.refresh_validator_set(1).await.unwrap().unwrap();
+
+        // The next boundary elects a single validator: retried, then held
+        set_candidates(&caller, &[validator_candidate(1, 10)]);
+        let calls = caller.call_count(stake_hub_selector("getValidatorElectionInfo"));
+        assert_eq!(executor.refresh_validator_set(2).await.unwrap(), None);
+        assert_eq!(
+            caller.call_count(stake_hub_selector("getValidatorElectionInfo")),
+            calls + 3
+        );
+        assert_eq!(executor.cached_validator_set(), Some(safe));
+        assert!(executor.in_emergency());
+
+        // An unavailable or empty fetch doesn't end it
+        caller.set_response(stake_hub_selector("getValidatorElectionInfo"), Vec::new());
+        assert_eq!(executor.refresh_validator_set(3).await.unwrap(), None);
+        assert!(executor.in_emergency());
+        set_candidates(&caller, &[]);
+        assert_eq!(executor.refresh_validator_set(3).await.unwrap(), None);
+        assert!(executor.in_emergency());
+
+        // A safe set ends the emergency
+        set_candidates(
+            &caller,
+            &[validator_candidate(1, 10), validator_candidate(3, 30)],
+        );
+        let recovered = executor.refresh_validator_set(3).await.unwrap().unwrap();
+        assert_eq!(executor.cached_validator_set(), Some(recovered));
+        assert!(!executor.in_emergency());
+    }
+
+    #[tokio::test]
+    async fn test_drift_from_genesis() {
+        let genesis: Vec<GenesisValidatorInfo> =
+            [validator_candidate(1, 10), validator_candidate(2, 20)]
+                .iter()
+                .map(|v| GenesisValidatorInfo {
+                    consensus_address: v.consensus_address,
+                    operator_address: v.operator_address,
+                    tendermint_pubkey: v.tendermint_pub_key.clone(),
+                    voting_power: 10 * (v.consensus_address[0] as u64),
+                })
+                .collect();
+        let caller = Arc::new(mock_stake_hub(
+            10,
+            &[validator_candidate(2, 25), validator_candidate(3, 30)],
+        ));
+        let client = StakeHubClient::with_caller(caller, Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+
+        let diff = executor.drift_from_genesis(&genesis, 500).await.unwrap();
+
+        let address = Address::repeat_byte;
+        assert_eq!(
+            diff.added
+                .iter()
+                .map(|v| v.consensus_address)
+                .collect::<Vec<_>>(),
+            vec![address(3)]
+        );
+        assert_eq!(
+            diff.removed
+                .iter()
+                .map(|v| v.consensus_address)
+                .collect::<Vec<_>>(),
+            vec![address(1)]
+        );
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].before.voting_power, 20);
+        assert_eq!(diff.changed[0].after.voting_power, 25);
+    }
+
+    #[test]
+    fn test_transition_fetch_waits_for_grace_blocks() {
+        let client =
+            StakeHubClient::with_caller(Arc::new(mock_stake_hub(1, &[])), Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+        assert_eq!(executor.transition_epoch_at(99, 100), Some(1));
+        assert_eq!(executor.transition_epoch_at(100, 100), None);
+
+        let client =
+            StakeHubClient::with_caller(Arc::new(mock_stake_hub(1, &[])), Address::ZERO).unwrap();
+        let executor =
+            ValidatorExecutor::with_stake_hub_client(client).with_transition_grace_blocks(3);
+        // Boundary at 200: fetched after committing block 202 rather than 199
+        assert_eq!(executor.transition_epoch_at(199, 100), None);
+        assert_eq!(executor.transition_epoch_at(201, 100), None);
+        assert_eq!(executor.transition_epoch_at(202, 100), Some(2));
+        assert_eq!(executor.transition_epoch_at(1, 100), None);
+        assert_eq!(executor.transition_epoch_at(202, 0), None);
+    }
+
+    #[tokio::test]
+    async fn test_update_finality_follows_block_tag() {
+        for (finalized, tag) in [(false, "latest"), (true, "finalized")] {
+            let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+            caller.set_finalized_block(99);
+            let client = StakeHubClient::with_caller(caller, Address::ZERO).unwrap();
+            let executor =
+                ValidatorExecutor::with_stake_hub_client(client).with_finalized_reads(finalized);
+            let mut updates = executor.subscribe_updates();
+
+            executor.refresh_validator_set(1).await.unwrap();
+
+            assert_eq!(executor.read_tag.to_param(), tag);
+            assert_eq!(updates.try_recv().unwrap().is_final, finalized);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_finalized_reads_wait_for_the_decision_block() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        // Epoch 2 is decided at block 199, where candidate 2 had joined as well
+        let decided = [validator_candidate(1, 10), validator_candidate(2, 20)];
+        set_candidates_at(&caller, 199, &decided);
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client).with_finalized_reads(true);
+
+        caller.set_finalized_block(198);
+        assert_eq!(executor.refresh_validator_set(2).await.unwrap(), None);
+        assert_eq!(executor.cached_validator_set(), None);
+        assert_eq!(
+            caller.call_count(stake_hub_selector("getValidatorElectionInfo")),
+            0
+        );
+
+        caller.set_finalized_block(250);
+        let validator_set = executor.refresh_validator_set(2).await.unwrap().unwrap();
+        assert_eq!(validator_set.len(), 2);
+        assert_eq!(caller.call_blocks().last(), Some(&BlockTag::Number(199)));
+    }
+
+    #[tokio::test]
+    async fn test_operator_election_and_rank() {
+        let candidates = [
+            validator_candidate(1, 10),
+            validator_candidate(2, 30),
+            validator_candidate(3, 20),
+        ];
+        let caller = Arc::new(mock_stake_hub(2, &candidates));
+        let client = StakeHubClient::with_caller(caller, Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+        let elected = candidates[2].operator_address;
+        let not_elected = candidates[0].operator_address;
+
+        assert!(executor.is_operator_elected(elected).await.unwrap());
+        assert!(!executor.is_operator_elected(not_elected).await.unwrap());
+
+        // Ranks are read fresh, without a cached set
+        let rank = |operator| executor.my_rank(operator);
+        assert_eq!(rank(candidates[1].operator_address).await.unwrap(), Some(1));
+        assert_eq!(rank(elected).await.unwrap(), Some(2));
+        assert_eq!(rank(not_elected).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_local_height_source_drives_boundary_detection() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let height = Arc::new(LocalBlockHeight::new(98));
+        let executor = ValidatorExecutor::with_stake_hub_client(client)
+            .with_block_height_source(height.clone());
+
+        assert_eq!(executor.current_transition_epoch(100).await.unwrap(), None);
+        height.set(99);
+        assert_eq!(
+            executor.current_transition_epoch(100).await.unwrap(),
+            Some(1)
+        );
+        height.set(100);
+        assert_eq!(executor.current_transition_epoch(100).await.unwrap(), None);
+
+        assert_eq!(caller.max_in_flight(), 0);
+    }
+
+    #[test]
+    fn test_epoch_schedule_drives_transitions() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let client = StakeHubClient::with_caller(caller, Address::ZERO).unwrap();
+        let schedule = EpochSchedule::from_history(&[(0, 100), (250, 150)]).unwrap();
+        let executor =
+            ValidatorExecutor::with_stake_hub_client(client).with_epoch_schedule(schedule);
+
+        assert_eq!(executor.transition_epoch_at(299, 150), Some(3));
+        assert_eq!(executor.transition_epoch_at(399, 150), None);
+        assert_eq!(executor.transition_epoch_at(449, 150), Some(4));
+
+        // A live length differing from the schedule takes effect at the next boundary
+        assert_eq!(executor.transition_epoch_at(500, 50), None);
+        assert_eq!(executor.transition_epoch_at(599, 50), Some(5));
+        assert_eq!(executor.transition_epoch_at(649, 50), Some(6));
+        // and stays in the schedule for earlier blocks and other helpers
+        assert_eq!(executor.transition_epoch_at(299, 50), Some(3));
+        assert!(executor.is_epoch_boundary(650, 50).now_or_never().unwrap());
+        assert!(!executor.is_epoch_boundary(675, 50).now_or_never().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_epoch_schedule_places_decision_blocks() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let schedule = EpochSchedule::from_history(&[(0, 100), (250, 150)]).unwrap();
+        let executor =
+            ValidatorExecutor::with_stake_hub_client(client).with_epoch_schedule(schedule);
+
+        // Epoch 4 (450..600) is decided under the schedule, not at 399 by plain arithmetic
+        let read_at = || {
+            let mut blocks: Vec<u64> = caller
+                .call_blocks()
+                .into_iter()
+                .filter_map(|block| match block {
+                    BlockTag::Number(number) => Some(number),
+                    _ => None,
+                })
+                .collect();
+            blocks.sort_unstable();
+            blocks.dedup();
+            blocks
+        };
+        executor.validator_set_for_epoch(4, 150, 500).await.unwrap();
+        assert_eq!(read_at(), vec![449]);
+        let history = executor.election_history(3..=4, 150, 500).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(read_at(), vec![299, 449]);
+
+        set_epoch_length(&caller, 150);
+        executor.expected_set_root_for_block(500).await.unwrap();
+        assert_eq!(read_at(), vec![299, 449, 500]);
+    }
+
+    #[tokio::test]
+    async fn test_missing_height_source_is_an_error() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let client = StakeHubClient::with_caller(caller, Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+
+        assert!(executor.current_transition_epoch(100).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_next_boundary() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        set_epoch_length(&caller, 10);
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+
+        let boundary = executor
+            .wait_for_next_boundary(futures::stream::iter(15..))
+            .await
+            .unwrap();
+        assert_eq!(boundary, 20);
+
+        // Skipped blocks still cross the boundary, and the epoch length is cached
+        let boundary = executor
+            .wait_for_next_boundary(futures::stream::iter([27, 28, 33, 34]))
+            .await
+            .unwrap();
+        assert_eq!(boundary, 30);
+        assert_eq!(caller.call_count(stake_hub_selector("epochLength")), 1);
+
+        assert!(executor
+            .wait_for_next_boundary(futures::stream::iter([41, 42]))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validator_set_fetch_outcomes() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+
+        let fetch = executor.get_validator_set_from_stake_hub().await;
+        assert!(matches!(&fetch, ValidatorSetFetch::Available(set) if set.len() == 1));
+        executor.refresh_validator_set(1).await.unwrap();
+        let cached = executor.cached_validator_set();
+
+        set_candidates(&caller, &[]);
+        assert_eq!(
+            executor.get_validator_set_from_stake_hub().await,
+            ValidatorSetFetch::Empty
+        );
+        assert_eq!(executor.refresh_validator_set(2).await.unwrap(), None);
+        assert_eq!(executor.cached_validator_set(), cached);
+
+        caller.set_response(stake_hub_selector("getValidatorElectionInfo"), Vec::new());
+        let fetch = executor.get_validator_set_from_stake_hub().await;
+        assert!(matches!(fetch, ValidatorSetFetch::Unavailable(reason) if !reason.is_empty()));
+        assert_eq!(executor.refresh_validator_set(3).await.unwrap(), None);
+        assert_eq!(executor.cached_validator_set(), cached);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_validators_are_reported() {
+        let mut short_key = validator_candidate(2, 20);
+        short_key.tendermint_pub_key.truncate(31);
+        let mut invalid_key = validator_candidate(3, 30);
+        invalid_key.tendermint_pub_key = vec![2; 32];
+        let mut long_key = validator_candidate(4, 40);
+        long_key.tendermint_pub_key.push(0);
+        let caller = Arc::new(mock_stake_hub(
+            10,
+            &[
+                validator_candidate(1, 1000),
+                short_key,
+                invalid_key,
+                long_key,
+            ],
+        ));
+        let client = StakeHubClient::with_caller(caller, Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+
+        let fetch = executor.get_validator_set_from_stake_hub().await;
+        assert!(matches!(fetch, ValidatorSetFetch::Available(set) if set.len() == 1));
+        assert_eq!(
+            executor.last_build_warnings(),
+            vec![
+                SetBuildWarning::InvalidPubkeyLength {
+                    consensus_address: Address::repeat_byte(4),
+                    len: 33
+                },
+                SetBuildWarning::InvalidPubkey {
+                    consensus_address: Address::repeat_byte(3)
+                },
+                SetBuildWarning::InvalidPubkeyLength {
+                    consensus_address: Address::repeat_byte(2),
+                    len: 31
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bad_pubkeys_are_skipped_above_quorum() {
+        let mut bad_key = validator_candidate(2, 10);
+        bad_key.tendermint_pub_key = vec![2; 32];
+        let mut candidates: Vec<_> = (3..=6).map(|i| validator_candidate(i, 10)).collect();
+        candidates.push(bad_key);
+        let caller = Arc::new(mock_stake_hub(10, &candidates));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+
+        let fetch = executor.get_validator_set_from_stake_hub().await;
+        assert!(matches!(fetch, ValidatorSetFetch::Available(set) if set.len() == 4));
+        assert_eq!(
+            executor.last_build_warnings(),
+            vec![SetBuildWarning::InvalidPubkey {
+                consensus_address: Address::repeat_byte(2)
+            }]
+        );
+
+        // With a third of the power behind the bad key, the rest can't reach a quorum
+        candidates[4].voting_power = candidates[0].voting_power * U256::from(2);
+        set_candidates(&caller, &candidates);
+        let fetch = executor.get_validator_set_from_stake_hub().await;
+        assert!(matches!(fetch, ValidatorSetFetch::Unavailable(_)));
+        assert_eq!(executor.last_build_warnings().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_strict_pubkey_validation_drops_non_canonical_keys() {
+        // y = p + 1, a non-canonical encoding of the point y = 1
+        let mut non_canonical = validator_candidate(2, 1);
+        non_canonical.tendermint_pub_key = [0xff; 32].to_vec();
+        non_canonical.tendermint_pub_key[0] = 0xee;
+        non_canonical.tendermint_pub_key[31] = 0x7f;
+        let candidates = [validator_candidate(1, 10), non_canonical];
+
+        for (strict, expected_len) in [(false, 2), (true, 1)] {
+            let caller = Arc::new(mock_stake_hub(10, &candidates));
+            let client = StakeHubClient::with_caller(caller, Address::ZERO).unwrap();
+            let executor = ValidatorExecutor::with_stake_hub_client(client)
+                .with_strict_pubkey_validation(strict);
+
+            let fetch = executor.get_validator_set_from_stake_hub().await;
+            assert!(
+                matches!(fetch, ValidatorSetFetch::Available(set) if set.len() == expected_len)
+            );
+            let warnings = executor.last_build_warnings();
+            if strict {
+                assert_eq!(
+                    warnings,
+                    vec![SetBuildWarning::NonCanonicalPubkey {
+                        consensus_address: Address::repeat_byte(2)
+                    }]
+                );
+            } else {
+                assert!(warnings.is_empty());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_fetches_are_coalesced() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        caller.set_delay(Duration::from_millis(50));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+
+        let fetches =
+            futures::future::join_all((0..5).map(|_| executor.get_validator_set_from_stake_hub()))
+                .await;
+
+        assert!(matches!(fetches[0], ValidatorSetFetch::Available(_)));
+        assert!(fetches.iter().all(|fetch| *fetch == fetches[0]));
+        assert_eq!(
+            caller.call_count(stake_hub_selector("getValidatorElectionInfo")),
+            1
+        );
+
+        // A later call fetches again
+        executor.get_validator_set_from_stake_hub().await;
+        assert_eq!(
+            caller.call_count(stake_hub_selector("getValidatorElectionInfo")),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_candidates_skip_rebuild() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO)
+            .unwrap()
+            .with_max_elected_cache_ttl(Duration::ZERO);
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+
+        let first = executor.get_validator_set_from_stake_hub().await;
+        assert!(matches!(first, ValidatorSetFetch::Available(_)));
+        assert_eq!(executor.set_builds.load(Ordering::Relaxed), 1);
+
+        // Same candidates: the set is reused
+        assert_eq!(executor.get_validator_set_from_stake_hub().await, first);
+        assert_eq!(executor.set_builds.load(Ordering::Relaxed), 1);
+
+        // Changed candidates are elected again
+        set_candidates(&caller, &[validator_candidate(1, 20)]);
+        let second = executor.get_validator_set_from_stake_hub().await;
+        assert_ne!(second, first);
+        assert_eq!(executor.set_builds.load(Ordering::Relaxed), 2);
+
+        // So is a changed seat count
+        set_max_elected(&caller, 5);
+        assert_eq!(executor.get_validator_set_from_stake_hub().await, second);
+        assert_eq!(executor.set_builds.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn test_emergency_override_supersedes_stake_hub() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let override_set = get_top_validators_by_voting_power(
+            vec![validator_candidate(2, 10), validator_candidate(3, 10)],
+            U256::from(10),
+            &ElectionConfig::default(),
+        );
+        let path =
+            std::env::temp_dir().join(format!("emergency-override-{}.json", std::process::id()));
+        let signer = SigningKey::from([7; 32]);
+        let json = ValidatorSnapshot::new(1, 100, &override_set)
+            .to_json()
+            .unwrap();
+        let write_signed = |contents: &str, key: &SigningKey| {
+            std::fs::write(&path, contents).unwrap();
+            let signature = key.sign(contents.as_bytes());
+            std::fs::write(
+                override_signature_path(&path),
+                format!("{}\n", hex::encode(signature.to_bytes())),
+            )
+            .unwrap();
+        };
+        write_signed(&json, &signer);
+
+        // Configured but disabled: StakeHub is still queried
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client).with_emergency_override(
+            &path,
+            false,
+            [signer.verification_key()],
+        );
+        let ValidatorSetFetch::Available(set) = executor.get_validator_set_from_stake_hub().await
+        else {
+            panic!("expected a validator set");
+        };
+        assert_eq!(set.len(), 1);
+
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client).with_emergency_override(
+            &path,
+            true,
+            [signer.verification_key()],
+        );
+        let calls = caller.call_count(stake_hub_selector("getValidatorElectionInfo"));
+        let fetch = executor.get_validator_set_from_stake_hub().await;
+        assert_eq!(
+            fetch,
+            ValidatorSetFetch::Available(build_validator_set(override_set, false).0.unwrap())
+        );
+        assert_eq!(
+            caller.call_count(stake_hub_selector("getValidatorElectionInfo")),
+            calls
+        );
+
+        // A broken override doesn't fall back to StakeHub
+        write_signed("not json", &signer);
+        let fetch = executor.get_validator_set_from_stake_hub().await;
+        assert!(matches!(fetch, ValidatorSetFetch::Unavailable(_)));
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(override_signature_path(&path)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_emergency_override_requires_a_valid_signature() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let override_set = get_top_validators_by_voting_power(
+            vec![validator_candidate(2, 10)],
+            U256::from(10),
+            &ElectionConfig::default(),
+        );
+        let path = std::env::temp_dir().join(format!(
+            "emergency-override-unsigned-{}.json",
+            std::process::id()
+        ));
+        let json = ValidatorSnapshot::new(1, 100, &override_set)
+            .to_json()
+            .unwrap();
+        std::fs::write(&path, &json).unwrap();
+        let signer = SigningKey::from([7; 32]);
+        let executor = |signers: Vec<VerificationKey>| {
+            let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+            ValidatorExecutor::with_stake_hub_client(client)
+                .with_emergency_override(&path, true, signers)
+        };
+        let rejected = |fetch: ValidatorSetFetch, reason: &str| match fetch {
+            ValidatorSetFetch::Unavailable(error) => assert!(error.contains(reason), "{}", error),
+            fetch => panic!("expected the override to be rejected, got {:?}", fetch),
+        };
+
+        // Unsigned
+        let fetch = executor(vec![signer.verification_key()])
+            .get_validator_set_from_stake_hub()
+            .await;
+        rejected(fetch, "missing signature file");
+
+        // Signed by a key that isn't configured
+        let signature_path = override_signature_path(&path);
+        let other = SigningKey::from([8; 32]);
+        std::fs::write(
+            &signature_path,
+            hex::encode(other.sign(json.as_bytes()).to_bytes()),
+        )
+        .unwrap();
+        let fetch = executor(vec![signer.verification_key()])
+            .get_validator_set_from_stake_hub()
+            .await;
+        rejected(fetch, "no valid signature");
+
+        // Signed, but the file changed afterwards
+        std::fs::write(
+            &signature_path,
+            hex::encode(signer.sign(json.as_bytes()).to_bytes()),
+        )
+        .unwrap();
+        std::fs::write(&path, format!("{}\n", json)).unwrap();
+        let fetch = executor(vec![signer.verification_key()])
+            .get_validator_set_from_stake_hub()
+            .await;
+        rejected(fetch, "no valid signature");
+
+        // No signer configured
+        std::fs::write(&path, &json).unwrap();
+        let fetch = executor(Vec::new())
+            .get_validator_set_from_stake_hub()
+            .await;
+        rejected(fetch, "no emergency override signer");
+
+        let fetch = executor(vec![other.verification_key(), signer.verification_key()])
+            .get_validator_set_from_stake_hub()
+            .await;
+        assert!(matches!(fetch, ValidatorSetFetch::Available(_)));
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&signature_path).unwrap();
+    }
+
+    /// Writer appending to a buffer the test keeps a handle on
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_records_transitions() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let buffer = SharedBuffer::default();
+        let executor =
+            ValidatorExecutor::with_stake_hub_client(client).with_audit_log(buffer.clone());
+
+        executor.refresh_validator_set(1).await.unwrap();
+        set_candidates(
+            &caller,
+            &[validator_candidate(1, 20), validator_candidate(2, 10)],
+        );
+        executor.refresh_validator_set(2).await.unwrap();
+        // Unchanged: nothing is logged
+        executor.refresh_validator_set(3).await.unwrap();
+
+        let log = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let records: Vec<AuditRecord> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0].epoch, 1);
+        assert_eq!(records[0].boundary_block, MOCK_EPOCH_LENGTH);
+        assert_eq!(records[0].diff.added.len(), 1);
+        assert!(records[0].diff.removed.is_empty());
+
+        assert_eq!(records[1].epoch, 2);
+        assert_eq!(records[1].boundary_block, 2 * MOCK_EPOCH_LENGTH);
+        assert_eq!(records[1].diff.added.len(), 1);
+        assert_eq!(records[1].diff.changed.len(), 1);
+        assert_eq!(
+            records[1].diff.changed[0].after.voting_power,
+            2 * records[1].diff.changed[0].before.voting_power
+        );
+    }
+
+    /// Writer failing every write while its flag is set
+    #[derive(Clone, Default)]
+    struct FailingWriter(Arc<AtomicBool>);
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.0.load(Ordering::SeqCst) {
+                return Err(std::io::Error::other("disk full"));
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_audit_write_keeps_the_cache() {
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let writer = FailingWriter::default();
+        let executor = ValidatorExecutor::with_stake_hub_client(client)
+            .with_audit_log(writer.clone())
+            .with_min_refresh_interval_blocks(50);
+
+        let first = executor.refresh_validator_set(1).await.unwrap();
+        set_candidates(
+            &caller,
+            &[validator_candidate(1, 10), validator_candidate(2, 10)],
+        );
+        writer.0.store(true, Ordering::SeqCst);
+        assert!(executor.refresh_validator_set(2).await.is_err());
+
+        // The cache is still fully the one of epoch 1
+        assert_eq!(executor.cached_validator_set(), first);
+        assert_eq!(executor.metrics_snapshot().unwrap().epoch, Some(1));
+        assert_eq!(
+            *executor.last_refresh_block.read().unwrap(),
+            Some(MOCK_EPOCH_LENGTH)
+        );
+
+        // So the next attempt fetches again instead of being throttled
+        writer.0.store(false, Ordering::SeqCst);
+        let second = executor.refresh_validator_set(2).await.unwrap().unwrap();
+        assert_eq!(second.len(), 2);
+        assert_eq!(executor.metrics_snapshot().unwrap().epoch, Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_set_logs_throttled_heartbeats() {
+        tokio::time::pause();
+        let caller = Arc::new(mock_stake_hub(10, &[validator_candidate(1, 10)]));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client)
+            .with_heartbeat_interval(Duration::from_millis(100));
+        let mut updates = executor.subscribe_updates();
+        let refresh_unchanged = |count| {
+            let executor = &executor;
+            async move {
+                for _ in 0..count {
+                    executor.refresh_validator_set(1).await.unwrap();
+                }
+            }
+        };
+
+        // The change itself is logged, so no heartbeat is due right after it
+        executor.refresh_validator_set(1).await.unwrap();
+        refresh_unchanged(20).await;
+        assert_eq!(executor.heartbeats.load(Ordering::Relaxed), 0);
+
+        tokio::time::advance(Duration::from_millis(150)).await;
+        refresh_unchanged(20).await;
+        assert_eq!(executor.heartbeats.load(Ordering::Relaxed), 1);
+
+        set_candidates(&caller, &[validator_candidate(2, 10)]);
+        executor.refresh_validator_set(2).await.unwrap();
+        refresh_unchanged(20).await;
+        assert_eq!(executor.heartbeats.load(Ordering::Relaxed), 1);
+        assert_eq!(updates.try_recv().unwrap().sequence, 1);
+        assert_eq!(updates.try_recv().unwrap().sequence, 2);
+        assert!(updates.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_metrics_snapshot() {
+        let caller = Arc::new(mock_stake_hub(
+            10,
+            &(1..=4)
+                .map(|i| validator_candidate(i, 10 * i as u64))
+                .collect::<Vec<_>>(),
+        ));
+        let client = StakeHubClient::with_caller(caller.clone(), Address::ZERO).unwrap();
+        let executor = ValidatorExecutor::with_stake_hub_client(client);
+        assert!(executor.metrics_snapshot().is_err());
+
+        executor.refresh_validator_set(1).await.unwrap();
+        // Validator 1 leaves and validator 5 joins with its power
+        set_candidates(
+            &caller,
+            &[2, 3, 4, 1]
+                .into_iter()
+                .enumerate()
+                .map(|(i, power)| validator_candidate(i as u8 + 2, 10 * power))
+                .collect::<Vec<_>>(),
+        );
+        executor.refresh_validator_set(2).await.unwrap();
+
+        let snapshot = executor.metrics_snapshot().unwrap();
+        assert_eq!(snapshot.epoch, Some(2));
+        assert_eq!(snapshot.validator_count, 4);
+        assert_eq!(snapshot.total_voting_power, 100);
+        assert_eq!(snapshot.nakamoto_coefficient, 1);
+        assert!((snapshot.gini_coefficient - 0.25).abs() < 1e-12);
+        assert_eq!(snapshot.churn, 2);
+
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(json["nakamoto_coefficient"], 1);
+        assert_eq!(json["churn"], 2);
+    }
 }
@@ -9,6 +9,7 @@ mod genesis;
 mod pubkey;
 mod spammer;
 mod tx;
+mod validate;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -32,6 +33,9 @@ pub(crate) enum Commands {
 
     /// Print Tendermint Ed25519 public key (0x-prefixed hex) from priv_validator_key.json
     Pubkey(PubkeyCmd),
+
+    /// Check the validators and epoch length in a genesis file's extraData before starting a node
+    ValidateGenesis(ValidateGenesisCmd),
 }
 
 #[derive(Parser, Debug, Clone, Default, PartialEq)]
@@ -79,6 +83,7 @@ async fn main() -> Result<()> {
                 .await
         }
         Commands::Pubkey(cmd) => pubkey::run_pubkey(cmd),
+        Commands::ValidateGenesis(cmd) => validate::run_validate_genesis(cmd),
     }
 }
 
@@ -88,3 +93,10 @@ pub struct PubkeyCmd {
     #[clap(long)]
     key_file: PathBuf,
 }
+
+#[derive(Parser, Debug, Clone, Default, PartialEq)]
+pub struct ValidateGenesisCmd {
+    /// Path to the execution genesis.json
+    #[clap(long)]
+    genesis_file: PathBuf,
+}
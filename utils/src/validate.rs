@@ -0,0 +1,23 @@
+use crate::ValidateGenesisCmd;
+use color_eyre::eyre::{eyre, Result};
+use malachitebft_eth_engine::genesis::validate_extra_data;
+use std::fs;
+
+pub fn run_validate_genesis(cmd: ValidateGenesisCmd) -> Result<()> {
+    let contents = fs::read_to_string(&cmd.genesis_file)?;
+    let genesis: serde_json::Value = serde_json::from_str(&contents)?;
+
+    let extra_data = genesis["extraData"]
+        .as_str()
+        .ok_or_else(|| eyre!("Genesis file has no extraData field"))?;
+    let extra_data = hex::decode(extra_data.trim_start_matches("0x"))?;
+
+    let report = validate_extra_data(&extra_data);
+    println!("{}", report);
+
+    if report.is_valid() {
+        Ok(())
+    } else {
+        Err(eyre!("Genesis extraData failed validation"))
+    }
+}